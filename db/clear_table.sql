@@ -1,3 +1,9 @@
 DROP TABLE users;
 DROP TABLE groups;
 
+DROP TABLE history;
+DROP TABLE events;
+DROP TABLE notifications;
+DROP TABLE webhooks;
+DROP TABLE webhook_deliveries;
+DROP TABLE search_cache;
@@ -9,3 +9,50 @@ CREATE TABLE groups
     id TEXT NOT NULL UNIQUE,
     data TEXT NOT NULL
 );
+
+CREATE TABLE history
+(
+    id TEXT NOT NULL UNIQUE,
+    group_id TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE events
+(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    group_id TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE notifications
+(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    is_read BOOLEAN NOT NULL DEFAULT 0,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE webhooks
+(
+    id TEXT NOT NULL UNIQUE,
+    group_id TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE webhook_deliveries
+(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE search_cache
+(
+    term TEXT NOT NULL PRIMARY KEY,
+    date INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
@@ -10,6 +10,13 @@ echo 'DATABASE_URL=sqlite:///$PWD/db/movie_site.db' >> .env
 echo 'DEV_SECRET="Umm, just a secret for testing and stuff, but Ill delete it later"' >> .env
 echo 'ACCESS_TOKEN_EXP=180000' >> .env
 echo 'REFRESH_TOKEN_EXP=6000000' >> .env
+echo 'VETO_UNDO_SECS=10' >> .env
+echo 'DEADLINE_CHECK_SECS=15' >> .env
+echo 'NOTIFIER=file' >> .env
+echo 'SEARCH_CACHE_SIZE=1000' >> .env
+echo 'SEARCH_CACHE_TTL_SECS=86400' >> .env
+echo 'SEARCH_CACHE_PERSIST=false' >> .env
+echo 'MOVIE_PROVIDERS=imdb' >> .env
 echo 'CORS_ORIGIN=http://0.0.0.0:8080' >> .env
 echo 'ROOT_URL=http://0.0.0.0:3030' >> .env
 
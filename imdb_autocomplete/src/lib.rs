@@ -1,16 +1,28 @@
 use shared_stuff::imdb_structs::ImdbQuery;
 use shared_stuff::shared_structs::MovieDisplay;
-use std::io::ErrorKind;
 
+pub mod provider;
 pub mod req;
 pub mod res;
 pub mod test_functions;
 
 use log::{info, trace};
 
+/// IMDb never suggests more than this many titles for one search term.
+pub const MAX_SUGGESTIONS: usize = 8;
+
 pub async fn autocomplete_func(
     input: ImdbQuery,
 ) -> Result<Vec<MovieDisplay>, Box<dyn std::error::Error>> {
+    let (movies, _exhaustive) = autocomplete_exhaustive(input).await?;
+    Ok(movies)
+}
+
+/// Same as `autocomplete_func`, but also says if IMDb ran out of suggestions before
+/// `MAX_SUGGESTIONS`. When it did, whatever it suggests for a longer term is already in there.
+pub async fn autocomplete_exhaustive(
+    input: ImdbQuery,
+) -> Result<(Vec<MovieDisplay>, bool), Box<dyn std::error::Error>> {
     let (search_term, url) = req::build_url(input)?;
     log::info!("{:?}", &url);
 
@@ -20,13 +32,13 @@ pub async fn autocomplete_func(
         true => {
             let body_string = response.text().await?;
             trace!("{:?}", body_string);
-            let res = res::make_movie_display(body_string, search_term);
-            info!("{:?}", &res);
-            res
+            let (movies, suggestions) = res::make_movie_display_counted(body_string, search_term)?;
+            info!("{:?}", &movies);
+            Ok((movies, suggestions < MAX_SUGGESTIONS))
         }
         false => {
             // captain wants some bad request error
-            let custom_error = std::io::Error::new(ErrorKind::Other, "oh no! bad request");
+            let custom_error = std::io::Error::other("oh no! bad request");
             Err(custom_error.into())
         }
     }
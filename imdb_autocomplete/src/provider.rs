@@ -0,0 +1,29 @@
+use async_trait::async_trait;
+use shared_stuff::imdb_structs::ImdbQuery;
+use shared_stuff::provider_structs::{
+    MovieDetails, MovieProvider, MovieSource, ProviderError, SearchResults,
+};
+
+use crate::autocomplete_exhaustive;
+
+/// IMDb's autocomplete. It only ever suggests titles, so there are no details.
+#[derive(Debug, Default, Clone)]
+pub struct ImdbProvider;
+
+#[async_trait]
+impl MovieProvider for ImdbProvider {
+    fn source(&self) -> MovieSource {
+        MovieSource::Imdb
+    }
+
+    async fn search(&self, query: &str) -> Result<SearchResults, ProviderError> {
+        autocomplete_exhaustive(ImdbQuery::from(query))
+            .await
+            .map(|(movies, exhaustive)| SearchResults { movies, exhaustive })
+            .map_err(|e| ProviderError::Upstream(e.to_string()))
+    }
+
+    async fn details(&self, _movie_id: &str) -> Result<MovieDetails, ProviderError> {
+        Err(ProviderError::Unsupported)
+    }
+}
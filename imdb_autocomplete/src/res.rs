@@ -1,6 +1,6 @@
 use shared_stuff::imdb_structs::{JsonQuery, MediaType, MovieInfo};
+use shared_stuff::provider_structs::MovieSource;
 use shared_stuff::shared_structs::MovieDisplay;
-use uuid::Uuid;
 
 use log::trace;
 
@@ -13,12 +13,17 @@ fn trim_body_string(body_string: String, query: String) -> String {
     body_string[start_len..end_len].to_string()
 }
 
-fn serialize_raw_json(input: String) -> Result<Vec<MovieDisplay>, Box<dyn std::error::Error>> {
+// ALSO GIVES BACK HOW MANY SUGGESTIONS THERE WERE BEFORE THE NON MOVIES WERE DROPPED.
+fn serialize_raw_json(
+    input: String,
+) -> Result<(Vec<MovieDisplay>, usize), Box<dyn std::error::Error>> {
     let mut result_vec: Vec<MovieDisplay> = vec![];
     let json_query: JsonQuery = serde_json::from_str(&input)?;
     let temp_movie_vec: Option<Vec<MovieInfo>> = json_query.d;
+    let mut suggestions = 0;
 
     if let Some(movie_vec) = temp_movie_vec {
+        suggestions = movie_vec.len();
         for movie in movie_vec {
             trace!("{:?}", &movie.q);
             let media_type = MediaType::new(movie.q);
@@ -45,6 +50,7 @@ fn serialize_raw_json(input: String) -> Result<Vec<MovieDisplay>, Box<dyn std::e
                         movie_year,
                         movie_stars,
                         movie_images,
+                        source: MovieSource::Imdb,
                     });
                     //}
                 }
@@ -53,15 +59,22 @@ fn serialize_raw_json(input: String) -> Result<Vec<MovieDisplay>, Box<dyn std::e
         }
     }
 
-    Ok(result_vec)
+    Ok((result_vec, suggestions))
 }
 
 pub fn make_movie_display(
     body_string: String,
     search_term: String,
 ) -> Result<Vec<MovieDisplay>, Box<dyn std::error::Error>> {
+    let (movie_display_vec, _suggestions) = make_movie_display_counted(body_string, search_term)?;
+    Ok(movie_display_vec)
+}
+
+pub fn make_movie_display_counted(
+    body_string: String,
+    search_term: String,
+) -> Result<(Vec<MovieDisplay>, usize), Box<dyn std::error::Error>> {
     let trimmed_json = trim_body_string(body_string, search_term);
     trace!("trimmed json string is: {}", &trimmed_json);
-    let movie_display_vec = serialize_raw_json(trimmed_json)?;
-    Ok(movie_display_vec)
+    serialize_raw_json(trimmed_json)
 }
@@ -1,47 +1,151 @@
 use anyhow::Result;
-use ctor::ctor;
-use lazy_static::lazy_static;
-use shared_stuff::omdb_structs::OmdbStruct;
-use std::fs::File;
-use std::io::Read;
-
-const OMDB_URL: &str = "http://www.omdbapi.com/";
-const API_KEY_PATH: &str = "../keys/omdb_api.txt";
-
-lazy_static! {
-    static ref API_KEY: String = {
-        let mut file = File::open(API_KEY_PATH).expect("can't open file");
-        let mut api_key = String::new();
-        file.read_to_string(&mut api_key)
-            .expect("problem reading file");
-        api_key = api_key.trim().to_string();
-        api_key
-    };
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use shared_stuff::omdb_structs::{OmdbSearch, OmdbStruct};
+use shared_stuff::provider_structs::{
+    MovieDetails, MovieProvider, MovieSource, ProviderError, SearchResults,
+};
+
+pub const OMDB_URL: &str = "http://www.omdbapi.com/";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmdbConfig {
+    pub api_key: String,
+    pub base_url: String,
+}
+
+impl OmdbConfig {
+    /// The real OMDb.
+    pub fn new(api_key: String) -> Self {
+        OmdbConfig {
+            api_key,
+            base_url: OMDB_URL.to_string(),
+        }
+    }
 }
 
-#[ctor]
-fn load_logger() {
-    dotenv::dotenv().ok();
-    pretty_env_logger::init();
+/// OMDb goes by IMDb ids, so it has details for whatever IMDb's autocomplete finds.
+#[derive(Debug, Clone)]
+pub struct OmdbClient {
+    config: OmdbConfig,
+    client: reqwest::Client,
 }
 
-async fn query_builder(id: &str) -> Result<OmdbStruct> {
-    let query_url = format!("{}?i={}&plot=full&apikey={}", OMDB_URL, id, *API_KEY);
-    log::info!("query is: {:?}", &query_url);
+impl OmdbClient {
+    pub fn new(config: OmdbConfig) -> Self {
+        OmdbClient {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
 
-    let response = reqwest::get(&query_url).await?;
-    let body_text: OmdbStruct = response.json().await?;
-    log::info!("body_text is: {:?}", &body_text);
-    //let omdb_struct: OmdbStruct = serde_json::from_str(&body_text)?;
-    //log::info!("omdb_struct is: {:?}", &omdb_struct);
-    Ok(body_text)
+    async fn query<T: DeserializeOwned>(&self, params: &[(&str, &str)]) -> Result<T> {
+        log::info!("query is: {:?}", params);
+        let response = self
+            .client
+            .get(&self.config.base_url)
+            .query(params)
+            .query(&[("apikey", self.config.api_key.as_str())])
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    /// Everything OMDb has on one title, with the full plot. Check `found()`, OMDb
+    /// answers an unknown id with an error in the body.
+    pub async fn movie(&self, imdb_id: &str) -> Result<OmdbStruct> {
+        let omdb_struct: OmdbStruct = self.query(&[("i", imdb_id), ("plot", "full")]).await?;
+        log::info!("omdb_struct is: {:?}", &omdb_struct);
+        Ok(omdb_struct)
+    }
+
+    /// The first page of movies matching the query.
+    pub async fn search_movies(&self, query: &str) -> Result<OmdbSearch> {
+        let search: OmdbSearch = self.query(&[("s", query), ("type", "movie")]).await?;
+        log::info!("search is: {:?}", &search);
+        Ok(search)
+    }
 }
 
+#[async_trait]
+impl MovieProvider for OmdbClient {
+    fn source(&self) -> MovieSource {
+        MovieSource::Omdb
+    }
+
+    async fn search(&self, query: &str) -> Result<SearchResults, ProviderError> {
+        let search = self
+            .search_movies(query)
+            .await
+            .map_err(|e| ProviderError::Upstream(e.to_string()))?;
+        if search.not_found() {
+            return Ok(SearchResults {
+                movies: vec![],
+                exhaustive: false,
+            });
+        }
+        match search.Error {
+            // Like "Too many results." for a short query.
+            Some(error) => Err(ProviderError::Upstream(error)),
+            None => Ok(search.into_results()),
+        }
+    }
+
+    async fn details(&self, movie_id: &str) -> Result<MovieDetails, ProviderError> {
+        // Anything else would be another source's id.
+        if !movie_id.starts_with("tt") {
+            return Err(ProviderError::Unsupported);
+        }
+        let omdb_struct = self
+            .movie(movie_id)
+            .await
+            .map_err(|e| ProviderError::Upstream(e.to_string()))?;
+        match omdb_struct.found() {
+            true => Ok(omdb_struct.into_details()),
+            false => Err(ProviderError::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
+    use shared_stuff::omdb_structs::{OmdbSearch, OmdbStruct};
+    use shared_stuff::provider_structs::MovieSource;
+
+    #[test]
+    fn details_from_omdb() {
+        let body = r#"{"Title":"The Thing","Year":"1982","Runtime":"109 min",
+            "Genre":"Horror, Mystery, Sci-Fi","Director":"John Carpenter",
+            "Actors":"Kurt Russell, Wilford Brimley, Keith David","Plot":"Antarctica.",
+            "Poster":"N/A","Ratings":[{"Source":"Internet Movie Database","Value":"8.2/10"}],
+            "imdbRating":"8.2","imdbID":"tt0084787","Type":"movie","Response":"True"}"#;
+        let omdb_struct: OmdbStruct = serde_json::from_str(body).unwrap();
+        assert!(omdb_struct.found());
+        let details = omdb_struct.into_details();
+        assert_eq!(details.movie_id, "tt0084787");
+        assert_eq!(details.source, MovieSource::Omdb);
+        assert_eq!(details.year, 1982);
+        assert_eq!(details.genres, vec!["Horror", "Mystery", "Sci-Fi"]);
+        assert_eq!(details.actors.len(), 3);
+        assert_eq!(details.poster, "");
+        assert_eq!(details.rating, Some(8.2));
+        assert_eq!(details.ratings[0].value, "8.2/10");
+    }
+
+    #[test]
+    fn search_from_omdb() {
+        let body = r#"{"Search":[{"Title":"Alien","Year":"1979","imdbID":"tt0078748",
+            "Type":"movie","Poster":"https://m.media-amazon.com/images/M/alien.jpg"}],
+            "totalResults":"1","Response":"True"}"#;
+        let search: OmdbSearch = serde_json::from_str(body).unwrap();
+        let results = search.into_results();
+        assert!(!results.exhaustive);
+        assert_eq!(results.movies[0].movie_id, "tt0078748");
+        assert_eq!(results.movies[0].source, MovieSource::Omdb);
 
-    #[tokio::test]
-    async fn first_test() -> Result<()> {
-        query_builder("tt1160419").await?;
-        Ok(())
+        let body = r#"{"Response":"False","Error":"Movie not found!"}"#;
+        let search: OmdbSearch = serde_json::from_str(body).unwrap();
+        assert!(search.not_found());
     }
 }
@@ -0,0 +1,180 @@
+use crate::db_structs::GroupData;
+use crate::event_structs::{EventKind, GroupEvent};
+use crate::selection_structs::SeededRng;
+use crate::shared_structs::{MovieDisplay, SystemState, YewMovieDisplay};
+use crate::system_structs::SystemError;
+use crate::{Deserialize, Serialize};
+
+/// A movie the group still wants to get to, either added straight to the backlog
+/// or carried over after losing a round.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BacklogEntry {
+    pub movie: YewMovieDisplay,
+    pub date_added: i64,
+    /// The round it lost, `None` when it was added by hand.
+    #[serde(default)]
+    pub from_round: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum BacklogPick {
+    Random,
+    Oldest,
+}
+
+/// Sent to `backlog_action`, the group comes back.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum BacklogAction {
+    Add(MovieDisplay),
+    Remove(String),
+    /// Moves up to `count` movies from the backlog into the round, which has to be the
+    /// active one before it starts or a planned one.
+    Fill {
+        round_id: String,
+        count: u32,
+        pick: BacklogPick,
+    },
+}
+
+impl GroupData {
+    /// `seed` is only used for random picks.
+    pub fn apply_backlog_action(
+        &mut self,
+        username: &str,
+        action: BacklogAction,
+        seed: u64,
+        now: i64,
+    ) -> Result<Vec<GroupEvent>, SystemError> {
+        if !self.members.contains_key(username) {
+            return Err(SystemError::NotInGroup);
+        }
+        let kind = match action {
+            BacklogAction::Add(movie) => {
+                let movie = movie.canonical();
+                if self.movies_watched.contains(&movie.movie_id) {
+                    return Err(SystemError::AlreadyWatched);
+                }
+                if self.backlog_entry(&movie.movie_id).is_some() {
+                    return Err(SystemError::AlreadyAdded);
+                }
+                EventKind::BacklogAdded(movie.into_yew_display(username.to_string()))
+            }
+            BacklogAction::Remove(movie_id) => {
+                self.backlog_entry(&movie_id)
+                    .ok_or(SystemError::MovieNotFound)?;
+                EventKind::BacklogRemoved { movie_id }
+            }
+            BacklogAction::Fill {
+                round_id,
+                count,
+                pick,
+            } => {
+                let movies = self.backlog_picks(&round_id, count, pick, seed)?;
+                EventKind::BacklogFilled { round_id, movies }
+            }
+        };
+        let mut events = Vec::new();
+        self.push_event(&mut events, username, kind, now);
+        Ok(events)
+    }
+
+    fn backlog_entry(&self, movie_id: &str) -> Option<&BacklogEntry> {
+        self.backlog
+            .iter()
+            .find(|entry| entry.movie.movie_id == movie_id)
+    }
+
+    /// Leaves out anything already in the round or watched since it went on the backlog.
+    fn backlog_picks(
+        &self,
+        round_id: &str,
+        count: u32,
+        pick: BacklogPick,
+        seed: u64,
+    ) -> Result<Vec<YewMovieDisplay>, SystemError> {
+        let in_round = match round_id == self.round_id {
+            true if self.system_state != SystemState::AddingMovies => {
+                return Err(SystemError::WrongState)
+            }
+            true => self.current_movies.iter().collect::<Vec<_>>(),
+            false => self
+                .planned_rounds
+                .iter()
+                .find(|planned| planned.id == round_id)
+                .ok_or(SystemError::RoundNotFound)?
+                .candidates
+                .iter()
+                .collect(),
+        };
+        let mut available = self
+            .backlog
+            .iter()
+            .filter(|entry| {
+                entry.movie.rewatch || !self.movies_watched.contains(&entry.movie.movie_id)
+            })
+            .filter(|entry| {
+                !in_round
+                    .iter()
+                    .any(|movie| movie.movie_id == entry.movie.movie_id)
+            })
+            .collect::<Vec<_>>();
+        match pick {
+            BacklogPick::Random => SeededRng::new(seed).shuffle(&mut available),
+            BacklogPick::Oldest => available.sort_by_key(|entry| entry.date_added),
+        }
+        if available.is_empty() {
+            return Err(SystemError::MovieNotFound);
+        }
+        Ok(available
+            .into_iter()
+            .take(count as usize)
+            .map(|entry| entry.movie.clone())
+            .collect())
+    }
+
+    /// Called when a round finishes, before it's reset, so the losers are still in `round`.
+    pub(crate) fn carry_over_losers(&mut self, round_id: &str, winner_id: &str, now: i64) {
+        if !self.rules.backlog_losers {
+            return;
+        }
+        let losers = self
+            .round
+            .candidates
+            .iter()
+            .filter(|movie| movie.movie_id != winner_id)
+            .filter(|movie| self.backlog_entry(&movie.movie_id).is_none())
+            .cloned()
+            .collect::<Vec<_>>();
+        self.backlog
+            .extend(losers.into_iter().map(|movie| BacklogEntry {
+                movie,
+                date_added: now,
+                from_round: Some(round_id.to_string()),
+            }));
+    }
+
+    pub(crate) fn fill_from_backlog(
+        &mut self,
+        round_id: &str,
+        movies: &[YewMovieDisplay],
+        now: i64,
+    ) {
+        self.backlog.retain(|entry| {
+            !movies
+                .iter()
+                .any(|movie| movie.movie_id == entry.movie.movie_id)
+        });
+        if round_id == self.round_id {
+            if self.current_movies.is_empty() {
+                self.deadline_from = now;
+            }
+            self.current_movies.extend(movies.iter().cloned());
+        } else if let Some(planned) = self
+            .planned_rounds
+            .iter_mut()
+            .find(|planned| planned.id == round_id)
+        {
+            planned.candidates.extend(movies.iter().cloned());
+        }
+    }
+}
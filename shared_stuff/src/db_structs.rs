@@ -1,5 +1,12 @@
-use crate::group_structs::{GroupInfo, GroupUser};
+use crate::backlog_structs::BacklogEntry;
+use crate::discussion_structs::Discussion;
+use crate::group_structs::{GroupInfo, GroupRules, GroupUser};
+use crate::notification_structs::EmailPrefs;
+use crate::round_structs::PlannedRound;
+use crate::selection_structs::SelectionMode;
 use crate::shared_structs::{SystemState, YewMovieDisplay};
+use crate::system_structs::RoundData;
+use crate::watchlist_structs::WatchlistEntry;
 use crate::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -23,6 +30,12 @@ pub struct UserData {
     pub groups: HashSet<GroupInfo>,
     pub date_created: i64,
     pub date_modified: i64,
+    #[serde(default)]
+    pub login_history: Vec<i64>,
+    #[serde(default)]
+    pub watchlist: Vec<WatchlistEntry>,
+    #[serde(default)]
+    pub email_prefs: EmailPrefs,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,6 +61,37 @@ pub struct GroupData {
     pub turn: String,
     pub date_created: i64,
     pub date_modified: i64,
+    #[serde(default)]
+    pub round: RoundData,
+    #[serde(default)]
+    pub selection_mode: SelectionMode,
+    /// Who added the last few winners, newest first, for weighting random draws.
+    #[serde(default)]
+    pub recent_winners: VecDeque<String>,
+    #[serde(default)]
+    pub rules: GroupRules,
+    /// When the adding phase got its first movie, or the current veto turn began.
+    #[serde(default)]
+    pub deadline_from: i64,
+    /// Whoever made the group. Empty for groups made before this was kept.
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub rounds_played: u32,
+    /// The active round, which is also its id in the history once it's finished.
+    #[serde(default)]
+    pub round_id: String,
+    #[serde(default)]
+    pub round_date_created: i64,
+    /// Oldest first, the first one is next.
+    #[serde(default)]
+    pub planned_rounds: Vec<PlannedRound>,
+    /// Oldest first.
+    #[serde(default)]
+    pub backlog: Vec<BacklogEntry>,
+    /// Comments and pitches on the active round's candidates, by movie id.
+    #[serde(default)]
+    pub discussions: HashMap<String, Discussion>,
 }
 
 impl GroupData {
@@ -62,8 +106,24 @@ impl GroupData {
             turn: String::from(""),
             date_created: 0,
             date_modified: 0,
+            round: RoundData::default(),
+            selection_mode: SelectionMode::default(),
+            recent_winners: VecDeque::new(),
+            rules: GroupRules::default(),
+            deadline_from: 0,
+            owner: String::from(""),
+            rounds_played: 0,
+            round_id: String::from(""),
+            round_date_created: 0,
+            planned_rounds: Vec::new(),
+            backlog: Vec::new(),
+            discussions: HashMap::new(),
         }
     }
+    /// Groups from before owners were kept have none, so nobody gets the owner's say in them.
+    pub fn is_owner(&self, username: &str) -> bool {
+        !self.owner.is_empty() && self.owner == username
+    }
     pub fn into_db_group_struct(self, id: &str) -> DBGroupStruct {
         DBGroupStruct {
             id: id.to_string(),
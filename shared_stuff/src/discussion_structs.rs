@@ -0,0 +1,132 @@
+use crate::db_structs::GroupData;
+use crate::event_structs::EventKind;
+use crate::group_structs::{CommentExport, PitchExport};
+use crate::shared_structs::{SystemState, YewMovieDisplay};
+use crate::system_structs::SystemError;
+use crate::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Longest a comment or pitch can be, in characters.
+pub const MAX_COMMENT_LENGTH: usize = 500;
+
+/// Everything said about one candidate, kept with the round history once it's over.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Discussion {
+    /// Why the member who added the movie thinks it's worth watching.
+    #[serde(default)]
+    pub pitch: Option<String>,
+    /// Oldest first.
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Comment {
+    pub username: String,
+    pub text: String,
+    pub date: i64,
+}
+
+impl GroupData {
+    /// Anyone in the group can comment on a candidate until the round is finished.
+    pub(crate) fn comment_event(
+        &self,
+        movie_id: String,
+        text: String,
+    ) -> Result<EventKind, SystemError> {
+        if self.system_state == SystemState::Finished {
+            return Err(SystemError::WrongState);
+        }
+        self.find_movie(&movie_id)
+            .ok_or(SystemError::MovieNotFound)?;
+        let text = checked_text(&text)?.ok_or(SystemError::InvalidComment)?;
+        Ok(EventKind::CommentAdded { movie_id, text })
+    }
+
+    /// Only whoever added the movie can pitch it, and only while movies are being added.
+    /// An empty pitch takes it down.
+    pub(crate) fn pitch_event(
+        &self,
+        username: &str,
+        movie_id: String,
+        pitch: Option<String>,
+    ) -> Result<EventKind, SystemError> {
+        if self.system_state != SystemState::AddingMovies {
+            return Err(SystemError::WrongState);
+        }
+        let movie = self
+            .find_movie(&movie_id)
+            .ok_or(SystemError::MovieNotFound)?;
+        if movie.added_by != username {
+            return Err(SystemError::NotYourMovie);
+        }
+        let pitch = match pitch {
+            Some(pitch) => checked_text(&pitch)?,
+            None => None,
+        };
+        Ok(EventKind::PitchSet { movie_id, pitch })
+    }
+
+    pub(crate) fn add_comment(&mut self, movie_id: &str, comment: Comment) {
+        self.discussions
+            .entry(movie_id.to_string())
+            .or_default()
+            .comments
+            .push(comment);
+    }
+
+    pub(crate) fn set_pitch(&mut self, movie_id: &str, pitch: Option<String>) {
+        self.discussions
+            .entry(movie_id.to_string())
+            .or_default()
+            .pitch = pitch;
+    }
+}
+
+/// What `username` said in one round's discussions, for their data export. A pitch is by
+/// whoever added the movie, which is why the round's candidates are needed.
+pub fn export_discussions(
+    round_id: &str,
+    discussions: &HashMap<String, Discussion>,
+    candidates: &[YewMovieDisplay],
+    username: &str,
+) -> (Vec<CommentExport>, Vec<PitchExport>) {
+    let mut comments = Vec::new();
+    let mut pitches = Vec::new();
+    for (movie_id, discussion) in discussions {
+        comments.extend(
+            discussion
+                .comments
+                .iter()
+                .filter(|comment| comment.username == username)
+                .map(|comment| CommentExport {
+                    round_id: round_id.to_string(),
+                    movie_id: movie_id.clone(),
+                    text: comment.text.clone(),
+                    date: comment.date,
+                }),
+        );
+        let added_by_user = candidates
+            .iter()
+            .any(|movie| &movie.movie_id == movie_id && movie.added_by == username);
+        if let (Some(pitch), true) = (&discussion.pitch, added_by_user) {
+            pitches.push(PitchExport {
+                round_id: round_id.to_string(),
+                movie_id: movie_id.clone(),
+                pitch: pitch.clone(),
+            });
+        }
+    }
+    comments.sort_by_key(|comment| comment.date);
+    pitches.sort_by(|a, b| a.movie_id.cmp(&b.movie_id));
+    (comments, pitches)
+}
+
+/// Trimmed, `None` if there's nothing left.
+fn checked_text(text: &str) -> Result<Option<String>, SystemError> {
+    let text = text.trim();
+    if text.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(SystemError::InvalidComment);
+    }
+    Ok(Some(text.to_string()).filter(|text| !text.is_empty()))
+}
@@ -0,0 +1,384 @@
+use crate::backlog_structs::BacklogEntry;
+use crate::db_structs::GroupData;
+use crate::discussion_structs::Comment;
+use crate::group_structs::{GroupRules, GroupUserData};
+use crate::history_structs::VetoRecord;
+use crate::round_structs::PlannedRound;
+use crate::selection_structs::{
+    Bracket, DrawResult, RunoffResult, ScoreResult, SelectionMode, DRAW_WEIGHT_ROUNDS,
+};
+use crate::shared_structs::{SystemState, YewMovieDisplay};
+use crate::system_structs::RoundData;
+use crate::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBEvent {
+    pub id: i64,
+    pub group_id: String,
+    pub date: i64,
+    pub data: String,
+}
+
+/// One entry in a group's append-only event log.
+/// `actor` is empty for things the server did on its own, like finishing a round.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GroupEvent {
+    pub actor: String,
+    pub date: i64,
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum EventKind {
+    GroupCreated {
+        group_name: String,
+    },
+    MemberAdded {
+        username: String,
+    },
+    MemberLeft {
+        username: String,
+    },
+    MovieAdded(YewMovieDisplay),
+    MovieDeleted(YewMovieDisplay),
+    ReadyToggled(bool),
+    /// `seed` is only there when the order was shuffled.
+    RoundStarted {
+        order: Vec<String>,
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    VetoCast(YewMovieDisplay),
+    VetoUndone(YewMovieDisplay),
+    TurnPassed {
+        to: String,
+    },
+    ModeChanged(SelectionMode),
+    RulesChanged(GroupRules),
+    /// The adding phase or a veto turn ran out, `missed_by` is who hadn't finished.
+    DeadlinePassed {
+        missed_by: Vec<String>,
+    },
+    WinnerDrawn(DrawResult),
+    RankingSubmitted(Vec<String>),
+    RunoffCounted(RunoffResult),
+    ScoresSubmitted(HashMap<String, u8>),
+    ScoresCounted(ScoreResult),
+    BracketSeeded(Bracket),
+    MatchupVoted {
+        matchup: usize,
+        movie_id: String,
+    },
+    MatchupDecided {
+        matchup: usize,
+        winner: String,
+    },
+    /// `movie` is only `None` in the activity feed, where it stays hidden.
+    SecretVetoCast {
+        movie: Option<YewMovieDisplay>,
+    },
+    VetoesRevealed {
+        vetoes: Vec<VetoRecord>,
+        removed: Vec<String>,
+    },
+    RoundFinished {
+        round_id: String,
+        winner: YewMovieDisplay,
+    },
+    RoundOpened {
+        round_id: String,
+    },
+    RoundPlanned {
+        round_id: String,
+    },
+    PlannedMovieAdded {
+        round_id: String,
+        movie: YewMovieDisplay,
+    },
+    PlannedMovieDeleted {
+        round_id: String,
+        movie: YewMovieDisplay,
+    },
+    BacklogAdded(YewMovieDisplay),
+    BacklogRemoved {
+        movie_id: String,
+    },
+    /// `movies` leave the backlog and become candidates in the round.
+    BacklogFilled {
+        round_id: String,
+        movies: Vec<YewMovieDisplay>,
+    },
+    CommentAdded {
+        movie_id: String,
+        text: String,
+    },
+    PitchSet {
+        movie_id: String,
+        pitch: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EventPage {
+    pub entries: Vec<GroupEvent>,
+    pub page: u32,
+    pub total_pages: u32,
+}
+
+impl GroupEvent {
+    /// Secret vetoes are shown in the feed without the movie, the reveal says what they were.
+    pub fn hide_secret(&mut self) {
+        if let EventKind::SecretVetoCast { movie } = &mut self.kind {
+            *movie = None;
+        }
+    }
+}
+
+impl GroupData {
+    /// Rebuilds a group from its event log, oldest event first.
+    pub fn replay(events: &[GroupEvent]) -> GroupData {
+        let mut group_data = GroupData::new_empty();
+        for event in events {
+            group_data.apply_event(event);
+        }
+        group_data
+    }
+
+    /// The only place group state changes, so that replaying the log gives the same result.
+    /// Events are assumed valid, checking is done before they're created in `apply_action`.
+    pub fn apply_event(&mut self, event: &GroupEvent) {
+        let was_finished = self.system_state == SystemState::Finished;
+        match &event.kind {
+            EventKind::GroupCreated { group_name } => {
+                self.group_name = group_name.clone();
+                self.owner = event.actor.clone();
+                self.members
+                    .insert(event.actor.clone(), GroupUserData::default());
+                self.date_created = event.date;
+            }
+            EventKind::MemberAdded { username } => {
+                self.members
+                    .insert(username.clone(), GroupUserData::default());
+            }
+            EventKind::MemberLeft { username } => {
+                self.members.remove(username);
+            }
+            EventKind::MovieAdded(movie) => {
+                if self.current_movies.is_empty() {
+                    self.deadline_from = event.date;
+                }
+                self.current_movies.insert(movie.clone());
+            }
+            EventKind::MovieDeleted(movie) => {
+                self.current_movies.remove(movie);
+                self.discussions.remove(&movie.movie_id);
+            }
+            EventKind::ReadyToggled(ready_status) => {
+                if let Some(user_status) = self.members.get_mut(&event.actor) {
+                    user_status.ready_status = *ready_status;
+                }
+            }
+            EventKind::RoundStarted { order, seed } => {
+                self.system_state = SystemState::SystemStarted;
+                self.deadline_from = event.date;
+                self.system_order = order.iter().cloned().collect();
+                let mut candidates = self.current_movies.iter().cloned().collect::<Vec<_>>();
+                candidates.sort_by(|a, b| a.movie_title.cmp(&b.movie_title));
+                self.round = RoundData {
+                    date_started: event.date,
+                    candidates,
+                    participants: order.clone(),
+                    vetoes: Vec::new(),
+                    draw: None,
+                    rankings: HashMap::new(),
+                    runoff: None,
+                    scores: HashMap::new(),
+                    score: None,
+                    bracket: None,
+                    secret_vetoes: HashMap::new(),
+                    order_seed: *seed,
+                    date_finished: 0,
+                };
+                // In score voting ready means the ballot is in, so everyone starts over.
+                if matches!(self.selection_mode, SelectionMode::Score { .. }) {
+                    for user_status in self.members.values_mut() {
+                        user_status.ready_status = false;
+                    }
+                }
+                if self.current_movies.len() == 1 {
+                    self.system_state = SystemState::Finished;
+                }
+            }
+            EventKind::VetoCast(movie) => {
+                self.current_movies.remove(movie);
+                self.round.vetoes.push(VetoRecord {
+                    username: event.actor.clone(),
+                    movie: movie.clone(),
+                    date: event.date,
+                });
+                if self.current_movies.len() == 1 {
+                    self.system_state = SystemState::Finished;
+                }
+            }
+            EventKind::VetoUndone(movie) => {
+                let vetoes_this_turn = self.vetoes_this_turn(&event.actor);
+                self.current_movies.insert(movie.clone());
+                self.round.vetoes.pop();
+                // The turn was only passed on if the veto used up the turn without ending the round.
+                match self.system_state {
+                    SystemState::Finished => self.system_state = SystemState::SystemStarted,
+                    _ if vetoes_this_turn.is_multiple_of(self.rules.turn_vetoes()) => {
+                        self.previous_turn()
+                    }
+                    _ => {}
+                }
+                self.turn = event.actor.clone();
+                self.deadline_from = event.date;
+            }
+            EventKind::TurnPassed { .. } => {
+                self.next_turn();
+                self.deadline_from = event.date;
+            }
+            EventKind::ModeChanged(selection_mode) => {
+                self.selection_mode = selection_mode.clone();
+            }
+            EventKind::RulesChanged(rules) => {
+                self.rules = rules.clone();
+            }
+            EventKind::DeadlinePassed { .. } => {}
+            EventKind::WinnerDrawn(draw) => {
+                self.current_movies
+                    .retain(|movie| movie.movie_id == draw.winner.movie_id);
+                self.round.draw = Some(draw.clone());
+                self.system_state = SystemState::Finished;
+            }
+            EventKind::RankingSubmitted(ranking) => {
+                self.round
+                    .rankings
+                    .insert(event.actor.clone(), ranking.clone());
+            }
+            EventKind::RunoffCounted(runoff) => {
+                self.current_movies
+                    .retain(|movie| movie.movie_id == runoff.winner.movie_id);
+                self.round.runoff = Some(runoff.clone());
+                self.system_state = SystemState::Finished;
+            }
+            EventKind::ScoresSubmitted(scores) => {
+                self.round
+                    .scores
+                    .insert(event.actor.clone(), scores.clone());
+                if let Some(user_status) = self.members.get_mut(&event.actor) {
+                    user_status.ready_status = true;
+                }
+            }
+            EventKind::ScoresCounted(score) => {
+                self.current_movies
+                    .retain(|movie| movie.movie_id == score.winner.movie_id);
+                self.round.score = Some(score.clone());
+                self.system_state = SystemState::Finished;
+            }
+            EventKind::BracketSeeded(bracket) => {
+                self.round.bracket = Some(bracket.clone());
+            }
+            EventKind::MatchupVoted { matchup, movie_id } => {
+                let current = self
+                    .round
+                    .bracket
+                    .as_mut()
+                    .and_then(|bracket| bracket.current_round_mut())
+                    .and_then(|round| round.get_mut(*matchup));
+                if let Some(current) = current {
+                    current.votes.insert(event.actor.clone(), movie_id.clone());
+                }
+            }
+            EventKind::MatchupDecided { matchup, winner } => {
+                if let Some(bracket) = self.round.bracket.as_mut() {
+                    bracket.decide(*matchup, winner.clone());
+                    if let Some(champion) = bracket.champion() {
+                        self.current_movies
+                            .retain(|movie| movie.movie_id == champion);
+                        self.system_state = SystemState::Finished;
+                    }
+                }
+            }
+            EventKind::SecretVetoCast { movie } => {
+                if let Some(movie) = movie {
+                    self.round
+                        .secret_vetoes
+                        .insert(event.actor.clone(), movie.movie_id.clone());
+                }
+            }
+            EventKind::VetoesRevealed { vetoes, removed } => {
+                self.round.vetoes.extend(vetoes.iter().cloned());
+                self.round.secret_vetoes.clear();
+                self.current_movies
+                    .retain(|movie| !removed.contains(&movie.movie_id));
+                if self.current_movies.len() == 1 {
+                    self.system_state = SystemState::Finished;
+                }
+            }
+            EventKind::RoundFinished { round_id, winner } => {
+                self.carry_over_losers(round_id, &winner.movie_id, event.date);
+                self.backlog
+                    .retain(|entry| entry.movie.movie_id != winner.movie_id);
+                self.movies_watched.insert(winner.movie_id.clone());
+                self.recent_winners.push_front(winner.added_by.clone());
+                self.recent_winners.truncate(DRAW_WEIGHT_ROUNDS);
+                self.rounds_played += 1;
+                self.reset_round();
+                self.activate_planned_round(event.date);
+            }
+            EventKind::RoundOpened { round_id } => {
+                self.round_id = round_id.clone();
+                self.round_date_created = event.date;
+            }
+            EventKind::RoundPlanned { round_id } => {
+                self.planned_rounds.push(PlannedRound {
+                    id: round_id.clone(),
+                    candidates: Vec::new(),
+                    date_created: event.date,
+                });
+            }
+            EventKind::PlannedMovieAdded { round_id, movie } => {
+                if let Some(planned) = self.planned_rounds.iter_mut().find(|p| &p.id == round_id) {
+                    planned.candidates.push(movie.clone());
+                }
+            }
+            EventKind::PlannedMovieDeleted { round_id, movie } => {
+                if let Some(planned) = self.planned_rounds.iter_mut().find(|p| &p.id == round_id) {
+                    planned.candidates.retain(|candidate| candidate != movie);
+                }
+            }
+            EventKind::BacklogAdded(movie) => {
+                self.backlog.push(BacklogEntry {
+                    movie: movie.clone(),
+                    date_added: event.date,
+                    from_round: None,
+                });
+            }
+            EventKind::BacklogRemoved { movie_id } => {
+                self.backlog
+                    .retain(|entry| &entry.movie.movie_id != movie_id);
+            }
+            EventKind::BacklogFilled { round_id, movies } => {
+                self.fill_from_backlog(round_id, movies, event.date);
+            }
+            EventKind::CommentAdded { movie_id, text } => {
+                let comment = Comment {
+                    username: event.actor.clone(),
+                    text: text.clone(),
+                    date: event.date,
+                };
+                self.add_comment(movie_id, comment);
+            }
+            EventKind::PitchSet { movie_id, pitch } => {
+                self.set_pitch(movie_id, pitch.clone());
+            }
+        }
+        if !was_finished && self.system_state == SystemState::Finished {
+            self.round.date_finished = event.date;
+        }
+        self.date_modified = event.date;
+    }
+}
@@ -1,3 +1,10 @@
+use crate::backlog_structs::BacklogEntry;
+use crate::history_structs::{Rating, VetoRecord};
+use crate::notification_structs::Notification;
+use crate::round_structs::PlannedRound;
+use crate::selection_structs::TurnOrder;
+use crate::shared_structs::YewMovieDisplay;
+use crate::watchlist_structs::WatchlistEntry;
 use crate::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
@@ -17,6 +24,72 @@ pub struct GroupUserData {
     pub turn: bool,
 }
 
+/// Limits on a round that each group sets for itself, checked by `apply_action`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct GroupRules {
+    /// Movies a member has to add before they can be ready.
+    pub min_movies_per_member: u32,
+    /// `None` for no limit.
+    pub max_movies_per_member: Option<u32>,
+    /// Still allowed once every movie left is your own.
+    pub veto_own_movie: bool,
+    pub vetoes_per_turn: u32,
+    /// When off, members have to add a movie before they can be ready.
+    pub start_before_all_added: bool,
+    /// Seconds from the first movie being added until the round starts with whatever's there.
+    pub adding_deadline_secs: Option<i64>,
+    /// Seconds each member gets for their veto turn.
+    pub turn_deadline_secs: Option<i64>,
+    pub on_turn_timeout: TimeoutAction,
+    pub turn_order: TurnOrder,
+    /// Puts every candidate that didn't win on the group's backlog when a round finishes.
+    pub backlog_losers: bool,
+}
+
+/// What the server does when a veto turn runs out.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub enum TimeoutAction {
+    #[default]
+    Skip,
+    /// Vetoes a random movie someone else added.
+    AutoVeto,
+}
+
+impl Default for GroupRules {
+    fn default() -> Self {
+        GroupRules {
+            min_movies_per_member: 0,
+            max_movies_per_member: None,
+            veto_own_movie: true,
+            vetoes_per_turn: 1,
+            start_before_all_added: true,
+            adding_deadline_secs: None,
+            turn_deadline_secs: None,
+            on_turn_timeout: TimeoutAction::default(),
+            turn_order: TurnOrder::default(),
+            backlog_losers: true,
+        }
+    }
+}
+
+impl GroupRules {
+    /// `vetoes_per_turn` for counting turns with. Rules are only checked when they're set,
+    /// so stored ones with none get one rather than dividing by zero.
+    pub fn turn_vetoes(&self) -> usize {
+        self.vetoes_per_turn.max(1) as usize
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.vetoes_per_turn > 0
+            && self.adding_deadline_secs.is_none_or(|secs| secs > 0)
+            && self.turn_deadline_secs.is_none_or(|secs| secs > 0)
+            && self
+                .max_movies_per_member
+                .is_none_or(|max| max > 0 && max >= self.min_movies_per_member)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct AddUser {
     pub username: String,
@@ -42,3 +115,60 @@ pub struct UserProfile {
     pub date_created: i64,
     pub date_modified: i64,
 }
+
+/// Everything the site stores about a single user, served by `export_user_data`.
+/// Only the caller's own entries are copied out of each group.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct UserExport {
+    pub profile: UserProfile,
+    pub groups: Vec<GroupExport>,
+    pub login_history: Vec<i64>,
+    #[serde(default)]
+    pub watchlist: Vec<WatchlistEntry>,
+    /// All of them, not just the ones the inbox shows.
+    #[serde(default)]
+    pub notifications: Vec<Notification>,
+    /// Groups that couldn't be read, so they're named rather than quietly left out.
+    #[serde(default)]
+    pub unreadable_groups: Vec<GroupInfo>,
+    pub date_exported: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct GroupExport {
+    pub group: GroupInfo,
+    pub movies_added: Vec<YewMovieDisplay>,
+    pub vetoes: Vec<VetoRecord>,
+    pub ratings: Vec<RatingExport>,
+    #[serde(default)]
+    pub comments: Vec<CommentExport>,
+    #[serde(default)]
+    pub pitches: Vec<PitchExport>,
+    #[serde(default)]
+    pub backlog: Vec<BacklogEntry>,
+    /// With only the candidates this user added, and only the rounds they added any to.
+    #[serde(default)]
+    pub planned_rounds: Vec<PlannedRound>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RatingExport {
+    pub round_id: String,
+    pub movie: YewMovieDisplay,
+    pub rating: Rating,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct CommentExport {
+    pub round_id: String,
+    pub movie_id: String,
+    pub text: String,
+    pub date: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct PitchExport {
+    pub round_id: String,
+    pub movie_id: String,
+    pub pitch: String,
+}
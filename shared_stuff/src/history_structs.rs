@@ -0,0 +1,184 @@
+use crate::discussion_structs::Discussion;
+use crate::selection_structs::{Bracket, DrawResult, RunoffResult, ScoreResult, SelectionMode};
+use crate::shared_structs::YewMovieDisplay;
+use crate::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBHistory {
+    pub id: String,
+    pub group_id: String,
+    pub date: i64,
+    pub data: String,
+}
+
+/// A finished round, stored once the system gets down to a single movie.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoundHistory {
+    pub id: String,
+    pub winner: YewMovieDisplay,
+    pub date_started: i64,
+    pub date_finished: i64,
+    pub participants: Vec<String>,
+    pub added_by: String,
+    pub candidates: Vec<YewMovieDisplay>,
+    #[serde(default)]
+    pub selection_mode: SelectionMode,
+    #[serde(default)]
+    pub ratings: HashMap<String, Rating>,
+    #[serde(default)]
+    pub vetoes: Vec<VetoRecord>,
+    #[serde(default)]
+    pub draw: Option<DrawResult>,
+    #[serde(default)]
+    pub runoff: Option<RunoffResult>,
+    #[serde(default)]
+    pub score: Option<ScoreResult>,
+    #[serde(default)]
+    pub bracket: Option<Bracket>,
+    /// By movie id, for the candidates anyone said something about.
+    #[serde(default)]
+    pub discussions: HashMap<String, Discussion>,
+}
+
+/// Who vetoed which movie, in the order they happened.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VetoRecord {
+    /// Empty for a veto the server made when a turn ran out.
+    pub username: String,
+    pub movie: YewMovieDisplay,
+    pub date: i64,
+}
+
+impl VetoRecord {
+    pub fn is_auto_veto(&self) -> bool {
+        self.username.is_empty()
+    }
+}
+
+impl RoundHistory {
+    /// Whether members took turns, so the order they went in could matter. Rounds kept
+    /// before the mode was are only known not to be if they have another mode's result.
+    pub fn took_turns(&self) -> bool {
+        self.selection_mode == SelectionMode::Veto
+            && self.draw.is_none()
+            && self.runoff.is_none()
+            && self.score.is_none()
+            && self.bracket.is_none()
+    }
+
+    pub fn average_rating(&self) -> Option<f64> {
+        if self.ratings.is_empty() {
+            return None;
+        }
+        let total: u32 = self
+            .ratings
+            .values()
+            .map(|rating| rating.score as u32)
+            .sum();
+        Some(total as f64 / self.ratings.len() as f64)
+    }
+}
+
+/// How a participant felt about the winner after watching it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Rating {
+    pub score: u8,
+    pub comment: Option<String>,
+    pub date: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, Clone, PartialEq, Default)]
+pub struct RatingForm {
+    #[validate(range(min = 1, max = 10))]
+    pub score: u8,
+    #[validate(length(max = 500))]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HistoryPage {
+    pub entries: Vec<RoundHistory>,
+    pub page: u32,
+    pub total_pages: u32,
+}
+
+/// Was the system worth it? Averages of the post-watch ratings for a group.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RatingsSummary {
+    pub average: Option<f64>,
+    pub ratings_count: u32,
+    pub over_time: Vec<RoundRating>,
+    pub by_added_by: Vec<AddedByRating>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoundRating {
+    pub round_id: String,
+    pub movie_title: String,
+    pub added_by: String,
+    pub date_finished: i64,
+    pub average: f64,
+    pub running_average: f64,
+    pub ratings_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AddedByRating {
+    pub added_by: String,
+    pub average: f64,
+    pub rounds: u32,
+    pub ratings_count: u32,
+}
+
+impl RatingsSummary {
+    pub fn from_history(history: &[RoundHistory]) -> RatingsSummary {
+        let mut rounds = history
+            .iter()
+            .filter(|round| !round.ratings.is_empty())
+            .collect::<Vec<&RoundHistory>>();
+        rounds.sort_by_key(|round| round.date_finished);
+
+        let mut summary = RatingsSummary::default();
+        let mut total_score = 0;
+        // BTreeMap so the breakdown comes out in the same order every time.
+        let mut by_added_by: BTreeMap<String, (u32, u32, u32)> = BTreeMap::new();
+        for round in rounds {
+            let round_score: u32 = round
+                .ratings
+                .values()
+                .map(|rating| rating.score as u32)
+                .sum();
+            let round_count = round.ratings.len() as u32;
+            total_score += round_score;
+            summary.ratings_count += round_count;
+            summary.over_time.push(RoundRating {
+                round_id: round.id.clone(),
+                movie_title: round.winner.movie_title.clone(),
+                added_by: round.added_by.clone(),
+                date_finished: round.date_finished,
+                average: round_score as f64 / round_count as f64,
+                running_average: total_score as f64 / summary.ratings_count as f64,
+                ratings_count: round_count,
+            });
+            let entry = by_added_by.entry(round.added_by.clone()).or_default();
+            entry.0 += round_score;
+            entry.1 += round_count;
+            entry.2 += 1;
+        }
+        if summary.ratings_count > 0 {
+            summary.average = Some(total_score as f64 / summary.ratings_count as f64);
+        }
+        summary.by_added_by = by_added_by
+            .into_iter()
+            .map(|(added_by, (score, ratings_count, rounds))| AddedByRating {
+                added_by,
+                average: score as f64 / ratings_count as f64,
+                rounds,
+                ratings_count,
+            })
+            .collect();
+        summary
+    }
+}
@@ -1,8 +1,21 @@
 pub mod auth_structs;
+pub mod backlog_structs;
 pub mod db_structs;
+pub mod discussion_structs;
+pub mod event_structs;
 pub mod group_structs;
+pub mod history_structs;
 pub mod imdb_structs;
+pub mod notification_structs;
 pub mod omdb_structs;
+pub mod provider_structs;
+pub mod round_structs;
+pub mod selection_structs;
 pub mod shared_structs;
+pub mod stats_structs;
+pub mod system_structs;
+pub mod tmdb_structs;
 pub mod utils;
+pub mod watchlist_structs;
+pub mod webhook_structs;
 pub use serde::{Deserialize, Serialize};
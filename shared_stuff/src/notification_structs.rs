@@ -0,0 +1,174 @@
+use crate::db_structs::GroupData;
+use crate::event_structs::{EventKind, GroupEvent};
+use crate::group_structs::GroupInfo;
+use crate::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBNotification {
+    pub id: i64,
+    pub username: String,
+    pub date: i64,
+    pub is_read: bool,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Notification {
+    pub id: i64,
+    pub date: i64,
+    pub read: bool,
+    pub kind: NotificationKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum NotificationKind {
+    YourTurn {
+        group: GroupInfo,
+    },
+    RoundStarted {
+        group: GroupInfo,
+    },
+    RoundFinished {
+        group: GroupInfo,
+        winner: String,
+    },
+    AddedToGroup {
+        group: GroupInfo,
+        added_by: String,
+    },
+    /// Sent with `AddedToGroup` by `add_user`, which invites and adds in one go.
+    InviteReceived {
+        group: GroupInfo,
+        invited_by: String,
+    },
+}
+
+/// The newest notifications, and how many of all of them haven't been read.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Inbox {
+    pub notifications: Vec<Notification>,
+    pub unread: u32,
+}
+
+/// Which notifications also go out by email. They all show up in the inbox either way.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct EmailPrefs {
+    pub your_turn: bool,
+    pub round_started: bool,
+    pub round_finished: bool,
+    pub added_to_group: bool,
+    pub invite_received: bool,
+}
+
+impl Default for EmailPrefs {
+    fn default() -> Self {
+        EmailPrefs {
+            your_turn: true,
+            round_started: false,
+            round_finished: false,
+            added_to_group: true,
+            invite_received: true,
+        }
+    }
+}
+
+impl EmailPrefs {
+    pub fn wants(&self, kind: &NotificationKind) -> bool {
+        match kind {
+            NotificationKind::YourTurn { .. } => self.your_turn,
+            NotificationKind::RoundStarted { .. } => self.round_started,
+            NotificationKind::RoundFinished { .. } => self.round_finished,
+            NotificationKind::AddedToGroup { .. } => self.added_to_group,
+            NotificationKind::InviteReceived { .. } => self.invite_received,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum MarkRead {
+    Ids(Vec<i64>),
+    All,
+}
+
+impl NotificationKind {
+    pub fn group(&self) -> &GroupInfo {
+        match self {
+            NotificationKind::YourTurn { group }
+            | NotificationKind::RoundStarted { group }
+            | NotificationKind::RoundFinished { group, .. }
+            | NotificationKind::AddedToGroup { group, .. }
+            | NotificationKind::InviteReceived { group, .. } => group,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            NotificationKind::YourTurn { group } => format!("It's your turn in {}", group.name),
+            NotificationKind::RoundStarted { group } => {
+                format!("A round started in {}", group.name)
+            }
+            NotificationKind::RoundFinished { group, winner } => {
+                format!("{} won the round in {}", winner, group.name)
+            }
+            NotificationKind::AddedToGroup { group, added_by } => {
+                format!("{} added you to {}", added_by, group.name)
+            }
+            NotificationKind::InviteReceived { group, invited_by } => {
+                format!("{} invited you to {}", invited_by, group.name)
+            }
+        }
+    }
+}
+
+impl GroupData {
+    /// Who should hear about the event, read after it's been applied. Nobody is told
+    /// about something they did themselves.
+    pub fn notifications_for(
+        &self,
+        group_id: &str,
+        event: &GroupEvent,
+    ) -> Vec<(String, NotificationKind)> {
+        let group = GroupInfo {
+            uuid: group_id.to_string(),
+            name: self.group_name.clone(),
+        };
+        let mut notifications = match &event.kind {
+            EventKind::TurnPassed { to } => {
+                vec![(to.clone(), NotificationKind::YourTurn { group })]
+            }
+            EventKind::RoundStarted { order, .. } => order
+                .iter()
+                .map(|username| {
+                    let group = group.clone();
+                    (username.clone(), NotificationKind::RoundStarted { group })
+                })
+                .collect(),
+            EventKind::RoundFinished { winner, .. } => self
+                .members
+                .keys()
+                .map(|username| {
+                    let kind = NotificationKind::RoundFinished {
+                        group: group.clone(),
+                        winner: winner.movie_title.clone(),
+                    };
+                    (username.clone(), kind)
+                })
+                .collect(),
+            EventKind::MemberAdded { username } => {
+                let invite = NotificationKind::InviteReceived {
+                    group: group.clone(),
+                    invited_by: event.actor.clone(),
+                };
+                let added = NotificationKind::AddedToGroup {
+                    group,
+                    added_by: event.actor.clone(),
+                };
+                vec![(username.clone(), invite), (username.clone(), added)]
+            }
+            _ => Vec::new(),
+        };
+        notifications.retain(|(username, _)| username != &event.actor);
+        notifications
+    }
+}
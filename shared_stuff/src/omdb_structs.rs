@@ -1,38 +1,162 @@
+use crate::imdb_structs::ImageData;
+use crate::provider_structs::{MovieDetails, MovieRating, MovieSource, SearchResults};
+use crate::shared_structs::MovieDisplay;
 use serde::{Deserialize, Serialize};
 
+/// What OMDb puts in any field it doesn't have.
+pub const OMDB_MISSING: &str = "N/A";
+
 #[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct OmdbRatings {
-    Source: String,
-    Value: String,
+    pub Source: String,
+    pub Value: String,
 }
 
+// Everything defaults, OMDb leaves out whatever doesn't apply to the title and
+// errors only have `Response` and `Error`.
 #[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct OmdbStruct {
-    Title: String,
-    Year: String,
-    Rated: String,
-    Released: String,
-    Runtime: String,
-    Genre: String,
-    Director: String,
-    Writer: String,
-    Actors: String,
-    Plot: String,
-    Language: String,
-    Country: String,
-    Awards: String,
-    Poster: String,
-    Ratings: Vec<OmdbRatings>,
-    Metascore: String,
-    imdbRating: String,
-    imdbVotes: String,
-    imdbID: String,
-    Type: String,
-    DVD: String,
-    BoxOffice: String,
-    Production: String,
-    Website: String,
-    Response: String,
+    pub Title: String,
+    pub Year: String,
+    pub Rated: String,
+    pub Released: String,
+    pub Runtime: String,
+    pub Genre: String,
+    pub Director: String,
+    pub Writer: String,
+    pub Actors: String,
+    pub Plot: String,
+    pub Language: String,
+    pub Country: String,
+    pub Awards: String,
+    pub Poster: String,
+    pub Ratings: Vec<OmdbRatings>,
+    pub Metascore: String,
+    pub imdbRating: String,
+    pub imdbVotes: String,
+    pub imdbID: String,
+    pub Type: String,
+    pub DVD: String,
+    pub BoxOffice: String,
+    pub Production: String,
+    pub Website: String,
+    pub Response: String,
+    pub Error: Option<String>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OmdbSearchItem {
+    pub Title: String,
+    pub Year: String,
+    pub imdbID: String,
+    pub Type: String,
+    pub Poster: String,
+}
+
+/// One page of `?s=` results, ten at most.
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OmdbSearch {
+    pub Search: Vec<OmdbSearchItem>,
+    pub totalResults: String,
+    pub Response: String,
+    pub Error: Option<String>,
+}
+
+/// `Some` only if OMDb has the field.
+fn omdb_field(field: &str) -> Option<&str> {
+    Some(field.trim()).filter(|field| !field.is_empty() && *field != OMDB_MISSING)
+}
+
+/// The first year of a range like "2008–2013", 0 if there isn't one.
+fn omdb_year(year: &str) -> u32 {
+    year.chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0)
+}
+
+/// A comma separated field as a list.
+fn omdb_list(field: &str) -> Vec<String> {
+    omdb_field(field)
+        .map(|field| {
+            field
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn omdb_poster(poster: &str) -> ImageData {
+    ImageData {
+        url: omdb_field(poster).unwrap_or_default().to_string(),
+        width: 0,
+        height: 0,
+    }
+}
+
+impl OmdbStruct {
+    pub fn found(&self) -> bool {
+        self.Response == "True"
+    }
+
+    pub fn into_details(self) -> MovieDetails {
+        MovieDetails {
+            movie_id: self.imdbID.clone(),
+            source: MovieSource::Omdb,
+            year: omdb_year(&self.Year),
+            plot: omdb_field(&self.Plot).unwrap_or_default().to_string(),
+            runtime: omdb_field(&self.Runtime).unwrap_or_default().to_string(),
+            genres: omdb_list(&self.Genre),
+            director: omdb_field(&self.Director).unwrap_or_default().to_string(),
+            actors: omdb_list(&self.Actors),
+            poster: omdb_poster(&self.Poster).url,
+            rating: omdb_field(&self.imdbRating).and_then(|rating| rating.parse().ok()),
+            ratings: self
+                .Ratings
+                .into_iter()
+                .map(|rating| MovieRating {
+                    source: rating.Source,
+                    value: rating.Value,
+                })
+                .collect(),
+            imdb_id: omdb_field(&self.imdbID).map(String::from),
+            title: self.Title,
+        }
+    }
+}
+
+impl OmdbSearch {
+    /// OMDb doesn't count "Movie not found!" as a search that worked, but it is one.
+    pub fn not_found(&self) -> bool {
+        self.Error.as_deref() == Some("Movie not found!")
+    }
+
+    pub fn into_results(self) -> SearchResults {
+        let movies = self
+            .Search
+            .into_iter()
+            .map(|item| MovieDisplay {
+                movie_year: omdb_year(&item.Year),
+                movie_images: omdb_poster(&item.Poster),
+                movie_stars: String::new(),
+                source: MovieSource::Omdb,
+                movie_id: item.imdbID,
+                movie_title: item.Title,
+            })
+            .collect::<Vec<_>>();
+        SearchResults {
+            movies,
+            exhaustive: false,
+        }
+    }
 }
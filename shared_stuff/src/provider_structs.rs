@@ -0,0 +1,156 @@
+use crate::shared_structs::MovieDisplay;
+use crate::{Deserialize, Serialize};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Where a movie came from. A source's own ids are only unique within it, so groups
+/// keep the ids from `canonical_id`.
+#[derive(Hash, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MovieSource {
+    /// Everything from before there was more than one source came from IMDb.
+    #[default]
+    Imdb,
+    Omdb,
+    Tmdb,
+}
+
+/// What TMDB's ids start with once they're canonical.
+pub const TMDB_PREFIX: &str = "tmdb:";
+
+/// The longer description shown for one movie. Anything the source doesn't have is empty.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct MovieDetails {
+    pub movie_id: String,
+    pub source: MovieSource,
+    pub title: String,
+    pub year: u32,
+    pub plot: String,
+    pub runtime: String,
+    pub genres: Vec<String>,
+    pub director: String,
+    pub actors: Vec<String>,
+    /// Empty when there's no poster.
+    pub poster: String,
+    /// Out of 10.
+    pub rating: Option<f32>,
+    /// Every rating the source knows of, as it gives them.
+    #[serde(default)]
+    pub ratings: Vec<MovieRating>,
+    /// The IMDb id, if the source knows it.
+    pub imdb_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MovieRating {
+    /// Like "Rotten Tomatoes".
+    pub source: String,
+    /// Like "93%" or "8.2/10".
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResults {
+    pub movies: Vec<MovieDisplay>,
+    /// The source had nothing more for the query, so the results for a longer one
+    /// are among these. Only true for a source that matches the start of words, like
+    /// IMDb's autocomplete. OMDb and TMDB match whole words, so "sta" finding nothing
+    /// says nothing about "star".
+    pub exhaustive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    /// The source doesn't do this at all, like details from IMDb's autocomplete.
+    Unsupported,
+    NotFound,
+    Upstream(String),
+}
+
+/// Somewhere to look movies up.
+#[async_trait]
+pub trait MovieProvider: Send + Sync {
+    fn source(&self) -> MovieSource;
+    async fn search(&self, query: &str) -> Result<SearchResults, ProviderError>;
+    async fn details(&self, movie_id: &str) -> Result<MovieDetails, ProviderError>;
+}
+
+/// Asks each provider in turn until one of them answers.
+pub struct ProviderChain {
+    providers: Vec<Arc<dyn MovieProvider>>,
+}
+
+impl ProviderChain {
+    pub fn new(providers: Vec<Arc<dyn MovieProvider>>) -> Self {
+        ProviderChain { providers }
+    }
+
+    pub fn sources(&self) -> Vec<MovieSource> {
+        self.providers
+            .iter()
+            .map(|provider| provider.source())
+            .collect()
+    }
+}
+
+impl MovieSource {
+    /// One id for the movie whichever source it came from. OMDb already uses IMDb's ids,
+    /// so those are left as they are, TMDB's get `TMDB_PREFIX` in front.
+    pub fn canonical_id(&self, movie_id: &str) -> String {
+        match self {
+            MovieSource::Imdb | MovieSource::Omdb => movie_id.to_string(),
+            MovieSource::Tmdb if movie_id.starts_with(TMDB_PREFIX) => movie_id.to_string(),
+            MovieSource::Tmdb => format!("{}{}", TMDB_PREFIX, movie_id),
+        }
+    }
+
+    /// The source's own id back out of a canonical one.
+    pub fn source_id<'a>(&self, movie_id: &'a str) -> &'a str {
+        match self {
+            MovieSource::Imdb | MovieSource::Omdb => movie_id,
+            MovieSource::Tmdb => movie_id.strip_prefix(TMDB_PREFIX).unwrap_or(movie_id),
+        }
+    }
+}
+
+#[async_trait]
+impl MovieProvider for ProviderChain {
+    /// The first in the chain.
+    fn source(&self) -> MovieSource {
+        self.providers
+            .first()
+            .map(|provider| provider.source())
+            .unwrap_or_default()
+    }
+
+    /// Returns the last error if they all fail.
+    async fn search(&self, query: &str) -> Result<SearchResults, ProviderError> {
+        let mut last_error = ProviderError::Unsupported;
+        for provider in &self.providers {
+            match provider.search(query).await {
+                Ok(results) => return Ok(results),
+                Err(e) => {
+                    log::warn!("{:?} search failed: {:?}", provider.source(), e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// A provider that doesn't know the id is skipped like one that failed, so an
+    /// `Unsupported` or `NotFound` only comes back if nobody had it.
+    async fn details(&self, movie_id: &str) -> Result<MovieDetails, ProviderError> {
+        let mut last_error = ProviderError::Unsupported;
+        for provider in &self.providers {
+            match provider.details(movie_id).await {
+                Ok(details) => return Ok(details),
+                Err(ProviderError::Unsupported) => {}
+                Err(e) => {
+                    log::warn!("{:?} details failed: {:?}", provider.source(), e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
@@ -0,0 +1,262 @@
+use crate::db_structs::GroupData;
+use crate::event_structs::{EventKind, GroupEvent};
+use crate::history_structs::RoundHistory;
+use crate::shared_structs::{MovieDisplay, SystemState, YewMovieDisplay};
+use crate::system_structs::{SystemAction, SystemError};
+use crate::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// A round queued up behind the active one. Members can add candidates to it ahead of time,
+/// and it becomes the active round once the current one finishes.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlannedRound {
+    pub id: String,
+    pub candidates: Vec<YewMovieDisplay>,
+    pub date_created: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum RoundStatus {
+    Planned,
+    Active(SystemState),
+    Finished,
+}
+
+/// The same view of a round whether it's planned, active or in the history.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoundInfo {
+    pub id: String,
+    pub status: RoundStatus,
+    pub candidates: Vec<YewMovieDisplay>,
+    /// The turn order, empty until the round starts.
+    pub order: Vec<String>,
+    pub date_created: i64,
+    pub date_started: Option<i64>,
+    pub date_finished: Option<i64>,
+    pub winner: Option<YewMovieDisplay>,
+}
+
+/// What `get_group_data` sends back. It has the same `id` and `group_data` as a
+/// `DBGroupStruct`, so it can be read as one by pages that don't need the rounds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GroupOverview {
+    pub id: String,
+    pub group_data: GroupData,
+    pub active_round: RoundInfo,
+    pub planned_rounds: Vec<RoundInfo>,
+    /// Newest first.
+    pub past_rounds: Vec<RoundInfo>,
+    /// From `GroupData::undo_deadline`, so the page doesn't need to know the grace window.
+    #[serde(default)]
+    pub undo_deadline: Option<i64>,
+}
+
+impl From<&RoundHistory> for RoundInfo {
+    fn from(history: &RoundHistory) -> Self {
+        RoundInfo {
+            id: history.id.clone(),
+            status: RoundStatus::Finished,
+            candidates: history.candidates.clone(),
+            order: history.participants.clone(),
+            date_created: history.date_started,
+            date_started: Some(history.date_started),
+            date_finished: Some(history.date_finished),
+            winner: Some(history.winner.clone()),
+        }
+    }
+}
+
+impl From<&PlannedRound> for RoundInfo {
+    fn from(planned: &PlannedRound) -> Self {
+        RoundInfo {
+            id: planned.id.clone(),
+            status: RoundStatus::Planned,
+            candidates: planned.candidates.clone(),
+            order: Vec::new(),
+            date_created: planned.date_created,
+            date_started: None,
+            date_finished: None,
+            winner: None,
+        }
+    }
+}
+
+impl GroupData {
+    pub fn active_round_info(&self) -> RoundInfo {
+        let started = self.system_state != SystemState::AddingMovies;
+        let mut candidates = match started {
+            true => self.round.candidates.clone(),
+            false => self.current_movies.iter().cloned().collect(),
+        };
+        candidates.sort_by(|a, b| a.movie_title.cmp(&b.movie_title));
+        RoundInfo {
+            id: self.round_id.clone(),
+            status: RoundStatus::Active(self.system_state.clone()),
+            candidates,
+            order: self.round.participants.clone(),
+            date_created: self.round_date_created,
+            date_started: started.then_some(self.round.date_started),
+            date_finished: None,
+            winner: None,
+        }
+    }
+
+    pub fn into_overview(
+        self,
+        id: &str,
+        history: &[RoundHistory],
+        grace_seconds: i64,
+    ) -> GroupOverview {
+        let mut past_rounds = history.iter().map(RoundInfo::from).collect::<Vec<_>>();
+        past_rounds.sort_by_key(|round| Reverse(round.date_finished));
+        GroupOverview {
+            id: id.to_string(),
+            active_round: self.active_round_info(),
+            planned_rounds: self.planned_rounds.iter().map(RoundInfo::from).collect(),
+            past_rounds,
+            undo_deadline: self.undo_deadline(grace_seconds),
+            group_data: self,
+        }
+    }
+
+    /// Gives the group a new active round if it hasn't got one, which happens when a round
+    /// finishes with nothing planned after it, and for groups made before rounds had ids.
+    pub fn open_round(&mut self, round_id: String, now: i64) -> Option<GroupEvent> {
+        if !self.round_id.is_empty() {
+            return None;
+        }
+        let event = GroupEvent {
+            actor: String::from(""),
+            date: now,
+            kind: EventKind::RoundOpened { round_id },
+        };
+        self.apply_event(&event);
+        Some(event)
+    }
+
+    /// Queues a new round after the active one and any already planned.
+    pub fn plan_round(
+        &mut self,
+        username: &str,
+        round_id: String,
+        now: i64,
+    ) -> Result<Vec<GroupEvent>, SystemError> {
+        if !self.members.contains_key(username) {
+            return Err(SystemError::NotInGroup);
+        }
+        let mut events = Vec::new();
+        self.push_event(
+            &mut events,
+            username,
+            EventKind::RoundPlanned { round_id },
+            now,
+        );
+        Ok(events)
+    }
+
+    /// Sends the action to the round it's meant for. The active round takes everything
+    /// `apply_action` does, a planned one only takes adding and removing candidates.
+    pub fn apply_round_action(
+        &mut self,
+        round_id: &str,
+        username: &str,
+        action: SystemAction,
+        now: i64,
+    ) -> Result<Vec<GroupEvent>, SystemError> {
+        if round_id == self.round_id {
+            return self.apply_action(username, action, now);
+        }
+        if !self.members.contains_key(username) {
+            return Err(SystemError::NotInGroup);
+        }
+        let planned = self
+            .planned_rounds
+            .iter()
+            .find(|planned| planned.id == round_id)
+            .ok_or(SystemError::RoundNotFound)?;
+        let kind = match action {
+            SystemAction::AddMovie(movie) => {
+                self.planned_movie_added(planned, username, movie, false)?
+            }
+            SystemAction::AddRewatch(movie) => {
+                self.planned_movie_added(planned, username, movie, true)?
+            }
+            SystemAction::DeleteMovie(movie) => {
+                let movie = planned
+                    .candidates
+                    .iter()
+                    .find(|candidate| candidate.movie_id == movie.movie_id)
+                    .ok_or(SystemError::MovieNotFound)?;
+                if movie.added_by != username {
+                    return Err(SystemError::NotYourMovie);
+                }
+                EventKind::PlannedMovieDeleted {
+                    round_id: round_id.to_string(),
+                    movie: movie.clone(),
+                }
+            }
+            _ => return Err(SystemError::WrongState),
+        };
+        let mut events = Vec::new();
+        self.push_event(&mut events, username, kind, now);
+        Ok(events)
+    }
+
+    /// The same checks as adding to the active round, against the planned round's candidates.
+    fn planned_movie_added(
+        &self,
+        planned: &PlannedRound,
+        username: &str,
+        movie: MovieDisplay,
+        allow_rewatch: bool,
+    ) -> Result<EventKind, SystemError> {
+        let movie = movie.canonical();
+        let rewatch = self.movies_watched.contains(&movie.movie_id);
+        if rewatch && !allow_rewatch {
+            return Err(SystemError::AlreadyWatched);
+        }
+        if planned
+            .candidates
+            .iter()
+            .any(|candidate| candidate.movie_id == movie.movie_id)
+        {
+            return Err(SystemError::AlreadyAdded);
+        }
+        let added = planned
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.added_by == username)
+            .count();
+        if let Some(max_movies) = self.rules.max_movies_per_member {
+            if added >= max_movies as usize {
+                return Err(SystemError::TooManyMovies);
+            }
+        }
+        let mut movie = movie.into_yew_display(username.to_string());
+        movie.rewatch = rewatch;
+        Ok(EventKind::PlannedMovieAdded {
+            round_id: planned.id.clone(),
+            movie,
+        })
+    }
+
+    /// Makes the next planned round the active one. Anything in it that's been watched since
+    /// it was planned is dropped, unless it was added as a rewatch.
+    pub(crate) fn activate_planned_round(&mut self, now: i64) {
+        if self.planned_rounds.is_empty() {
+            self.round_id = String::from("");
+            return;
+        }
+        let planned = self.planned_rounds.remove(0);
+        self.round_id = planned.id;
+        self.round_date_created = planned.date_created;
+        self.current_movies = planned
+            .candidates
+            .into_iter()
+            .filter(|movie| movie.rewatch || !self.movies_watched.contains(&movie.movie_id))
+            .collect();
+        if !self.current_movies.is_empty() {
+            self.deadline_from = now;
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use crate::shared_structs::YewMovieDisplay;
+use crate::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// How many past winners are remembered for weighting the random draw.
+pub const DRAW_WEIGHT_ROUNDS: usize = 5;
+
+/// How a round picks its winner once everyone is ready.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum SelectionMode {
+    #[default]
+    Veto,
+    RandomDraw {
+        weighted: bool,
+    },
+    RankedChoice,
+    /// Every candidate gets 0 to `MAX_SCORE`, or just 0 or 1 with `approval`.
+    Score {
+        approval: bool,
+    },
+    Bracket,
+    /// Everyone vetoes one movie at the same time, revealed once they're all in.
+    SecretVeto,
+}
+
+/// Who vetoes first, worked out on the server when the round starts.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum TurnOrder {
+    /// Shuffled with a seed that's kept with the round.
+    Shuffled,
+    /// Set by the group's owner. Members missing from it go last, alphabetically.
+    Fixed(Vec<String>),
+    /// Whoever has gone longest without their movie winning goes first.
+    LoserFirst,
+    /// Alphabetical, with the first player moving along one each round.
+    #[default]
+    RoundRobin,
+}
+
+/// Orders `members` for a new round, the same inputs always give the same order.
+pub fn turn_order(
+    policy: &TurnOrder,
+    members: &[String],
+    recent_winners: &VecDeque<String>,
+    rounds_played: u32,
+    seed: u64,
+) -> Vec<String> {
+    let mut order = members.to_vec();
+    order.sort();
+    match policy {
+        TurnOrder::Shuffled => SeededRng::new(seed).shuffle(&mut order),
+        TurnOrder::Fixed(fixed) => {
+            order.sort_by_key(|member| {
+                fixed
+                    .iter()
+                    .position(|fixed_member| fixed_member == member)
+                    .unwrap_or(fixed.len())
+            });
+        }
+        TurnOrder::LoserFirst => {
+            order.sort_by_key(|member| std::cmp::Reverse(draw_weight(member, recent_winners)));
+        }
+        TurnOrder::RoundRobin => {
+            if !order.is_empty() {
+                let start = rounds_played as usize % order.len();
+                order.rotate_left(start);
+            }
+        }
+    }
+    order
+}
+
+/// Everything needed to check a draw by hand: run `draw_winner` on the same weights and seed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DrawResult {
+    pub seed: u64,
+    pub weights: Vec<DrawWeight>,
+    pub winner: YewMovieDisplay,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DrawWeight {
+    pub movie_id: String,
+    pub weight: u64,
+}
+
+/// SplitMix64, small enough to redo anywhere, so a seed always gives the same draw.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        SeededRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_4769_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, `n` has to be more than 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Picks an index into `weights`, with each entry's chance proportional to its weight.
+pub fn draw_winner(weights: &[DrawWeight], seed: u64) -> Option<usize> {
+    let total: u64 = weights.iter().map(|entry| entry.weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut roll = SeededRng::new(seed).below(total);
+    for (i, entry) in weights.iter().enumerate() {
+        if roll < entry.weight {
+            return Some(i);
+        }
+        roll -= entry.weight;
+    }
+    None
+}
+
+/// Last round's winner gets 1, and each round without a win adds one more,
+/// up to `DRAW_WEIGHT_ROUNDS + 1` for members who haven't won in a while.
+pub fn draw_weight(added_by: &str, recent_winners: &VecDeque<String>) -> u64 {
+    match recent_winners.iter().position(|winner| winner == added_by) {
+        Some(position) => position as u64 + 1,
+        None => DRAW_WEIGHT_ROUNDS as u64 + 1,
+    }
+}
+
+/// Every round of an instant-runoff count, kept so the System page can show how it went.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunoffResult {
+    pub seed: u64,
+    pub rounds: Vec<RunoffRound>,
+    pub winner: YewMovieDisplay,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunoffRound {
+    pub counts: Vec<RunoffCount>,
+    pub eliminated: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunoffCount {
+    pub movie_id: String,
+    pub votes: u32,
+}
+
+/// Counts ballots (movie ids, favourite first) until a movie has a majority of the ballots
+/// still in play, or is the only one left. Returns the rounds and the winning movie id.
+/// A tie for last place eliminates the movie ranked lowest overall (Borda count),
+/// and if that's tied too, one of them drawn with the seed.
+pub fn instant_runoff(
+    candidates: &[String],
+    ballots: &[Vec<String>],
+    seed: u64,
+) -> (Vec<RunoffRound>, Option<String>) {
+    let mut rng = SeededRng::new(seed);
+    let mut remaining = candidates.to_vec();
+    remaining.sort();
+    let borda = |movie_id: &String| -> usize {
+        ballots
+            .iter()
+            .filter_map(|ballot| ballot.iter().position(|id| id == movie_id))
+            .map(|rank| candidates.len() - rank)
+            .sum()
+    };
+    let mut rounds = Vec::new();
+    while !remaining.is_empty() {
+        let mut counts = remaining
+            .iter()
+            .map(|movie_id| RunoffCount {
+                movie_id: movie_id.clone(),
+                votes: 0,
+            })
+            .collect::<Vec<_>>();
+        let mut ballots_in_play = 0;
+        for ballot in ballots {
+            let choice = ballot.iter().find(|id| remaining.contains(id));
+            if let Some(count) = choice.and_then(|id| counts.iter_mut().find(|c| &c.movie_id == id))
+            {
+                count.votes += 1;
+                ballots_in_play += 1;
+            }
+        }
+        let leader = counts.iter().max_by_key(|count| count.votes).cloned();
+        if let Some(leader) = leader {
+            if remaining.len() == 1 || leader.votes * 2 > ballots_in_play {
+                rounds.push(RunoffRound {
+                    counts,
+                    eliminated: None,
+                });
+                return (rounds, Some(leader.movie_id));
+            }
+        }
+        let fewest = counts.iter().map(|count| count.votes).min().unwrap_or(0);
+        let mut last_place = counts
+            .iter()
+            .filter(|count| count.votes == fewest)
+            .map(|count| count.movie_id.clone())
+            .collect::<Vec<_>>();
+        let lowest_borda = last_place.iter().map(borda).min().unwrap_or(0);
+        last_place.retain(|movie_id| borda(movie_id) == lowest_borda);
+        let eliminated = last_place.remove(rng.below(last_place.len() as u64) as usize);
+        remaining.retain(|movie_id| movie_id != &eliminated);
+        rounds.push(RunoffRound {
+            counts,
+            eliminated: Some(eliminated),
+        });
+    }
+    (rounds, None)
+}
+
+/// Highest score in score voting, 1 is approve in approval voting.
+pub const MAX_SCORE: u8 = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScoreResult {
+    pub seed: u64,
+    pub totals: Vec<ScoreTotal>,
+    /// Every member's scores by movie id, shown in full once the round is over.
+    pub ballots: HashMap<String, HashMap<String, u8>>,
+    pub winner: YewMovieDisplay,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScoreTotal {
+    pub movie_id: String,
+    pub total: u32,
+    pub zeros: u32,
+}
+
+/// Adds up every ballot. The highest total wins, then the fewest zero scores,
+/// then a draw with the seed. Totals come back best first.
+pub fn score_winner(
+    candidates: &[String],
+    ballots: &[HashMap<String, u8>],
+    seed: u64,
+) -> (Vec<ScoreTotal>, Option<String>) {
+    let mut totals = candidates
+        .iter()
+        .map(|movie_id| {
+            let scores = ballots
+                .iter()
+                .map(|ballot| *ballot.get(movie_id).unwrap_or(&0) as u32);
+            ScoreTotal {
+                movie_id: movie_id.clone(),
+                total: scores.clone().sum(),
+                zeros: scores.filter(|score| *score == 0).count() as u32,
+            }
+        })
+        .collect::<Vec<_>>();
+    totals.sort_by(|a, b| {
+        b.total
+            .cmp(&a.total)
+            .then(a.zeros.cmp(&b.zeros))
+            .then(a.movie_id.cmp(&b.movie_id))
+    });
+    let tied = match totals.first() {
+        Some(best) => totals
+            .iter()
+            .filter(|total| total.total == best.total && total.zeros == best.zeros)
+            .count(),
+        None => return (totals, None),
+    };
+    let winner = SeededRng::new(seed).below(tied as u64) as usize;
+    let winner_id = totals[winner].movie_id.clone();
+    (totals, Some(winner_id))
+}
+
+/// A knockout tournament, one `Vec` of match-ups per round, the last one being played now.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Bracket {
+    pub seed: u64,
+    pub rounds: Vec<Vec<Matchup>>,
+}
+
+/// `second` is `None` for a bye, which `first` wins without a vote.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Matchup {
+    pub first: String,
+    pub second: Option<String>,
+    /// Movie id each member voted for.
+    pub votes: HashMap<String, String>,
+    pub winner: Option<String>,
+}
+
+impl Bracket {
+    /// Shuffles the movies with the seed and pairs them off.
+    pub fn new(movie_ids: &[String], seed: u64) -> Bracket {
+        let mut movie_ids = movie_ids.to_vec();
+        movie_ids.sort();
+        SeededRng::new(seed).shuffle(&mut movie_ids);
+        let mut bracket = Bracket {
+            seed,
+            rounds: Vec::new(),
+        };
+        bracket.push_round(movie_ids);
+        bracket
+    }
+
+    /// Every draw comes off the bracket's seed, so the bracket in the history is all it
+    /// takes to redo them. Byes and tie-breaks get a stream each per round.
+    fn bye_rng(&self, round: usize) -> SeededRng {
+        SeededRng::new(self.seed.wrapping_add(round as u64 + 1))
+    }
+
+    /// For drawing the tied match-ups of the current round.
+    pub fn tie_rng(&self) -> SeededRng {
+        SeededRng::new(self.seed.wrapping_sub(self.rounds.len() as u64))
+    }
+
+    fn had_bye(&self, movie_id: &str) -> bool {
+        self.rounds
+            .iter()
+            .flatten()
+            .any(|matchup| matchup.second.is_none() && matchup.first == movie_id)
+    }
+
+    /// Pairs the movies off in order. With an odd number, one that hasn't had a bye yet
+    /// is drawn to sit the round out, and goes last.
+    fn push_round(&mut self, mut movie_ids: Vec<String>) {
+        let bye = match movie_ids.len() % 2 {
+            0 => None,
+            _ => {
+                let mut no_bye_yet = (0..movie_ids.len())
+                    .filter(|index| !self.had_bye(&movie_ids[*index]))
+                    .collect::<Vec<_>>();
+                if no_bye_yet.is_empty() {
+                    no_bye_yet = (0..movie_ids.len()).collect();
+                }
+                let drawn = self
+                    .bye_rng(self.rounds.len())
+                    .below(no_bye_yet.len() as u64) as usize;
+                Some(movie_ids.remove(no_bye_yet[drawn]))
+            }
+        };
+        let mut round = movie_ids
+            .chunks(2)
+            .map(|pair| Matchup {
+                first: pair[0].clone(),
+                second: Some(pair[1].clone()),
+                votes: HashMap::new(),
+                winner: None,
+            })
+            .collect::<Vec<_>>();
+        if let Some(bye) = bye {
+            round.push(Matchup {
+                first: bye.clone(),
+                second: None,
+                votes: HashMap::new(),
+                winner: Some(bye),
+            });
+        }
+        self.rounds.push(round);
+    }
+
+    pub fn current_round(&self) -> Option<&Vec<Matchup>> {
+        self.rounds.last()
+    }
+
+    pub fn current_round_mut(&mut self) -> Option<&mut Vec<Matchup>> {
+        self.rounds.last_mut()
+    }
+
+    /// Sets the winner, and once the whole round is decided, the winners go through to the next.
+    pub fn decide(&mut self, matchup: usize, winner: String) {
+        let round = match self.current_round_mut() {
+            Some(round) => round,
+            None => return,
+        };
+        if let Some(matchup) = round.get_mut(matchup) {
+            matchup.winner = Some(winner);
+        }
+        if round.len() > 1 && round.iter().all(|matchup| matchup.winner.is_some()) {
+            let winners = round
+                .iter()
+                .filter_map(|matchup| matchup.winner.clone())
+                .collect();
+            self.push_round(winners);
+        }
+    }
+
+    /// The winner of the final, once it's been played.
+    pub fn champion(&self) -> Option<String> {
+        match self.current_round() {
+            Some(round) if round.len() == 1 => round[0].winner.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// Which movies go when the secret vetoes are revealed. Normally every vetoed movie goes,
+/// but if that would leave nothing, only the most vetoed ones go, and if they're all level,
+/// one survivor is drawn with the seed.
+pub fn secret_veto_removals(candidates: &[String], vetoes: &[String], seed: u64) -> Vec<String> {
+    let mut candidates = candidates.to_vec();
+    candidates.sort();
+    let count = |movie_id: &String| vetoes.iter().filter(|vetoed| *vetoed == movie_id).count();
+    let mut survivors = candidates
+        .iter()
+        .filter(|movie_id| count(movie_id) == 0)
+        .cloned()
+        .collect::<Vec<_>>();
+    if survivors.is_empty() {
+        let fewest = candidates.iter().map(count).min().unwrap_or(0);
+        survivors = candidates
+            .iter()
+            .filter(|movie_id| count(movie_id) == fewest)
+            .cloned()
+            .collect();
+        if survivors.len() == candidates.len() {
+            let survivor = SeededRng::new(seed).below(survivors.len() as u64) as usize;
+            survivors = vec![survivors[survivor].clone()];
+        }
+    }
+    candidates.retain(|movie_id| !survivors.contains(movie_id));
+    candidates
+}
@@ -1,4 +1,5 @@
 use crate::imdb_structs::ImageData;
+use crate::provider_structs::MovieSource;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -16,6 +17,8 @@ pub struct MovieDisplay {
     pub movie_year: u32,
     pub movie_images: ImageData,
     pub movie_stars: String,
+    #[serde(default)]
+    pub source: MovieSource,
 }
 
 impl fmt::Display for MovieDisplay {
@@ -36,9 +39,23 @@ pub struct YewMovieDisplay {
     pub movie_images: ImageData,
     pub movie_stars: String,
     pub added_by: String,
+    #[serde(default)]
+    pub source: MovieSource,
+    /// Added with `AddRewatch`, the group has already watched it.
+    #[serde(default)]
+    pub rewatch: bool,
 }
 
 impl MovieDisplay {
+    /// With the id from `MovieSource::canonical_id`. Movies coming in from the browser go
+    /// through this, so a group never holds two sources' ids that could be mistaken.
+    pub fn canonical(self) -> MovieDisplay {
+        MovieDisplay {
+            movie_id: self.source.canonical_id(&self.movie_id),
+            ..self
+        }
+    }
+
     pub fn into_yew_display(self, added_by: String) -> YewMovieDisplay {
         YewMovieDisplay {
             movie_id: self.movie_id,
@@ -47,6 +64,8 @@ impl MovieDisplay {
             movie_images: self.movie_images,
             movie_stars: self.movie_stars,
             added_by,
+            source: self.source,
+            rewatch: false,
         }
     }
 }
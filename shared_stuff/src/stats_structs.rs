@@ -0,0 +1,123 @@
+use crate::history_structs::RoundHistory;
+use crate::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Everything on the stats page, worked out from a group's round history.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct GroupStats {
+    pub rounds_played: u32,
+    pub members: Vec<MemberStats>,
+    pub veto_counts: Vec<VetoCount>,
+    pub average_candidates: f64,
+    pub average_round_duration: f64,
+    pub turn_positions: Vec<TurnPositionStats>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct MemberStats {
+    pub username: String,
+    pub wins: u32,
+    pub movies_added: u32,
+    pub vetoes_cast: u32,
+    pub movies_vetoed: u32,
+}
+
+/// How many times `vetoed_by` vetoed a movie that `added_by` put in.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VetoCount {
+    pub vetoed_by: String,
+    pub added_by: String,
+    pub count: u32,
+}
+
+/// Position is 1 for whoever had the first veto of the round. Only veto rounds count,
+/// in the other modes nobody takes turns.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TurnPositionStats {
+    pub position: u32,
+    pub rounds: u32,
+    pub wins: u32,
+    pub win_rate: f64,
+}
+
+impl GroupStats {
+    pub fn from_history(history: &[RoundHistory]) -> GroupStats {
+        let mut members: BTreeMap<String, MemberStats> = BTreeMap::new();
+        let mut veto_counts: BTreeMap<(String, String), u32> = BTreeMap::new();
+        let mut turn_positions: BTreeMap<u32, (u32, u32)> = BTreeMap::new();
+        let mut total_candidates = 0;
+        let mut total_duration = 0;
+
+        for round in history {
+            total_candidates += round.candidates.len();
+            total_duration += round.date_finished - round.date_started;
+
+            member_entry(&mut members, &round.added_by).wins += 1;
+            for movie in round.candidates.iter() {
+                member_entry(&mut members, &movie.added_by).movies_added += 1;
+            }
+            // Nobody chose the server's vetoes, so they'd only skew the numbers.
+            for veto in round.vetoes.iter().filter(|veto| !veto.is_auto_veto()) {
+                member_entry(&mut members, &veto.username).vetoes_cast += 1;
+                member_entry(&mut members, &veto.movie.added_by).movies_vetoed += 1;
+                *veto_counts
+                    .entry((veto.username.clone(), veto.movie.added_by.clone()))
+                    .or_default() += 1;
+            }
+            if !round.took_turns() {
+                continue;
+            }
+            for (index, member) in round.participants.iter().enumerate() {
+                let entry = turn_positions.entry(index as u32 + 1).or_default();
+                entry.0 += 1;
+                if member == &round.added_by {
+                    entry.1 += 1;
+                }
+            }
+        }
+
+        let rounds_played = history.len() as u32;
+        let (average_candidates, average_round_duration) = match rounds_played {
+            0 => (0.0, 0.0),
+            rounds => (
+                total_candidates as f64 / rounds as f64,
+                total_duration as f64 / rounds as f64,
+            ),
+        };
+        GroupStats {
+            rounds_played,
+            members: members.into_values().collect(),
+            veto_counts: veto_counts
+                .into_iter()
+                .map(|((vetoed_by, added_by), count)| VetoCount {
+                    vetoed_by,
+                    added_by,
+                    count,
+                })
+                .collect(),
+            average_candidates,
+            average_round_duration,
+            turn_positions: turn_positions
+                .into_iter()
+                .map(|(position, (rounds, wins))| TurnPositionStats {
+                    position,
+                    rounds,
+                    wins,
+                    win_rate: wins as f64 / rounds as f64,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn member_entry<'a>(
+    members: &'a mut BTreeMap<String, MemberStats>,
+    username: &str,
+) -> &'a mut MemberStats {
+    members
+        .entry(username.to_string())
+        .or_insert_with(|| MemberStats {
+            username: username.to_string(),
+            ..Default::default()
+        })
+}
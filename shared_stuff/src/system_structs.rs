@@ -0,0 +1,821 @@
+use crate::db_structs::GroupData;
+use crate::event_structs::{EventKind, GroupEvent};
+use crate::group_structs::{GroupRules, TimeoutAction};
+use crate::history_structs::{RoundHistory, VetoRecord};
+use crate::selection_structs::{
+    draw_weight, draw_winner, instant_runoff, score_winner, secret_veto_removals, turn_order,
+    Bracket, DrawResult, DrawWeight, RunoffResult, ScoreResult, SeededRng, SelectionMode,
+    TurnOrder, MAX_SCORE,
+};
+use crate::shared_structs::{MovieDisplay, SystemState, YewMovieDisplay};
+use crate::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Everything a member can do to the system, sent to `system_action` and applied server side.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SystemAction {
+    AddMovie(MovieDisplay),
+    /// Same as `AddMovie`, but allowed for movies the group has already watched.
+    AddRewatch(MovieDisplay),
+    DeleteMovie(YewMovieDisplay),
+    SetReady,
+    UnsetReady,
+    Veto(YewMovieDisplay),
+    SetMode(SelectionMode),
+    SetRules(GroupRules),
+    /// Movie ids, favourite first. Sending another one replaces it until everyone has voted.
+    SubmitRanking(Vec<String>),
+    /// A score for every candidate by movie id. Marks the member ready, like in `AddingMovies`.
+    SubmitScores(HashMap<String, u8>),
+    /// Index into the bracket's current round, and the movie id voted for.
+    VoteMatchup {
+        matchup: usize,
+        movie_id: String,
+    },
+    /// Can be changed until everyone's is in.
+    SecretVeto(YewMovieDisplay),
+    Comment {
+        movie_id: String,
+        text: String,
+    },
+    /// `None` or an empty pitch takes it down.
+    SetPitch {
+        movie_id: String,
+        pitch: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SystemError {
+    NotInGroup,
+    WrongState,
+    NotYourTurn,
+    NotYourMovie,
+    AlreadyReady,
+    AlreadyAdded,
+    MovieNotFound,
+    NothingToUndo,
+    NotYourVeto,
+    UndoExpired,
+    WrongMode,
+    NotParticipant,
+    InvalidBallot,
+    InvalidRules,
+    TooManyMovies,
+    NotEnoughMovies,
+    OwnMovie,
+    AlreadyWatched,
+    NotOwner,
+    RoundNotFound,
+    InvalidComment,
+}
+
+/// Snapshot of the round taken when the system starts, so the history still has
+/// every candidate after the vetoes have removed them from `current_movies`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RoundData {
+    pub date_started: i64,
+    pub candidates: Vec<YewMovieDisplay>,
+    pub participants: Vec<String>,
+    #[serde(default)]
+    pub vetoes: Vec<VetoRecord>,
+    #[serde(default)]
+    pub draw: Option<DrawResult>,
+    #[serde(default)]
+    pub rankings: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub runoff: Option<RunoffResult>,
+    #[serde(default)]
+    pub scores: HashMap<String, HashMap<String, u8>>,
+    #[serde(default)]
+    pub score: Option<ScoreResult>,
+    #[serde(default)]
+    pub bracket: Option<Bracket>,
+    /// Movie id each member wants gone, hidden from everyone else until the reveal.
+    #[serde(default)]
+    pub secret_vetoes: HashMap<String, String>,
+    /// The seed the turn order was shuffled with, if it was.
+    #[serde(default)]
+    pub order_seed: Option<u64>,
+    /// When the round got down to its winner, 0 until it has one.
+    #[serde(default)]
+    pub date_finished: i64,
+}
+
+impl GroupData {
+    /// Checks the action is allowed, then applies it as events.
+    /// The events are returned so they can be appended to the group's log.
+    pub fn apply_action(
+        &mut self,
+        username: &str,
+        action: SystemAction,
+        now: i64,
+    ) -> Result<Vec<GroupEvent>, SystemError> {
+        let ready_status = self
+            .members
+            .get(username)
+            .ok_or(SystemError::NotInGroup)?
+            .ready_status;
+        let mut events = Vec::new();
+        match action {
+            SystemAction::AddMovie(movie) => {
+                let movie = movie.canonical();
+                if self.movies_watched.contains(&movie.movie_id) {
+                    return Err(SystemError::AlreadyWatched);
+                }
+                self.add_movie(&mut events, username, ready_status, movie, false, now)?;
+            }
+            SystemAction::AddRewatch(movie) => {
+                let movie = movie.canonical();
+                let rewatch = self.movies_watched.contains(&movie.movie_id);
+                self.add_movie(&mut events, username, ready_status, movie, rewatch, now)?;
+            }
+            SystemAction::DeleteMovie(movie) => {
+                if self.system_state != SystemState::AddingMovies {
+                    return Err(SystemError::WrongState);
+                }
+                if ready_status {
+                    return Err(SystemError::AlreadyReady);
+                }
+                let movie = self
+                    .find_movie(&movie.movie_id)
+                    .ok_or(SystemError::MovieNotFound)?;
+                if movie.added_by != username {
+                    return Err(SystemError::NotYourMovie);
+                }
+                self.push_event(&mut events, username, EventKind::MovieDeleted(movie), now);
+            }
+            SystemAction::SetReady => {
+                if self.system_state != SystemState::AddingMovies {
+                    return Err(SystemError::WrongState);
+                }
+                let movies_added = self.movies_added_by(username);
+                if movies_added < self.rules.min_movies_per_member as usize
+                    || (!self.rules.start_before_all_added && movies_added == 0)
+                {
+                    return Err(SystemError::NotEnoughMovies);
+                }
+                self.push_event(&mut events, username, EventKind::ReadyToggled(true), now);
+                // A shuffled order needs the server's seed, so `resolve_round` starts that one.
+                if self.ready_to_start() && self.rules.turn_order != TurnOrder::Shuffled {
+                    self.start_round(&mut events, username, 0, now);
+                }
+            }
+            SystemAction::UnsetReady => {
+                if self.system_state != SystemState::AddingMovies {
+                    return Err(SystemError::WrongState);
+                }
+                self.push_event(&mut events, username, EventKind::ReadyToggled(false), now);
+            }
+            SystemAction::Veto(movie) => {
+                if self.system_state != SystemState::SystemStarted {
+                    return Err(SystemError::WrongState);
+                }
+                if self.selection_mode != SelectionMode::Veto {
+                    return Err(SystemError::WrongMode);
+                }
+                if self.turn != username {
+                    return Err(SystemError::NotYourTurn);
+                }
+                let movie = self
+                    .find_movie(&movie.movie_id)
+                    .ok_or(SystemError::MovieNotFound)?;
+                self.check_own_veto(username, &movie)?;
+                self.push_event(&mut events, username, EventKind::VetoCast(movie), now);
+                if self.system_state == SystemState::SystemStarted
+                    && self
+                        .vetoes_this_turn(username)
+                        .is_multiple_of(self.rules.turn_vetoes())
+                {
+                    self.pass_turn(&mut events, username, now);
+                }
+            }
+            SystemAction::SetMode(selection_mode) => {
+                if self.system_state != SystemState::AddingMovies {
+                    return Err(SystemError::WrongState);
+                }
+                self.push_event(
+                    &mut events,
+                    username,
+                    EventKind::ModeChanged(selection_mode),
+                    now,
+                );
+            }
+            SystemAction::SetRules(rules) => {
+                if self.system_state != SystemState::AddingMovies {
+                    return Err(SystemError::WrongState);
+                }
+                if !rules.is_valid() {
+                    return Err(SystemError::InvalidRules);
+                }
+                if matches!(rules.turn_order, TurnOrder::Fixed(_))
+                    && rules.turn_order != self.rules.turn_order
+                    && !self.is_owner(username)
+                {
+                    return Err(SystemError::NotOwner);
+                }
+                self.push_event(&mut events, username, EventKind::RulesChanged(rules), now);
+            }
+            SystemAction::SubmitRanking(ranking) => {
+                if self.system_state != SystemState::SystemStarted {
+                    return Err(SystemError::WrongState);
+                }
+                if self.selection_mode != SelectionMode::RankedChoice {
+                    return Err(SystemError::WrongMode);
+                }
+                if !self.round.participants.iter().any(|p| p == username) {
+                    return Err(SystemError::NotParticipant);
+                }
+                let mut ranked = ranking.clone();
+                ranked.sort();
+                if ranked != self.candidate_ids() {
+                    return Err(SystemError::InvalidBallot);
+                }
+                self.push_event(
+                    &mut events,
+                    username,
+                    EventKind::RankingSubmitted(ranking),
+                    now,
+                );
+            }
+            SystemAction::SubmitScores(scores) => {
+                if self.system_state != SystemState::SystemStarted {
+                    return Err(SystemError::WrongState);
+                }
+                let max_score = match self.selection_mode {
+                    SelectionMode::Score { approval: true } => 1,
+                    SelectionMode::Score { approval: false } => MAX_SCORE,
+                    _ => return Err(SystemError::WrongMode),
+                };
+                if !self.round.participants.iter().any(|p| p == username) {
+                    return Err(SystemError::NotParticipant);
+                }
+                let mut scored = scores.keys().cloned().collect::<Vec<_>>();
+                scored.sort();
+                if scored != self.candidate_ids() || scores.values().any(|score| *score > max_score)
+                {
+                    return Err(SystemError::InvalidBallot);
+                }
+                self.push_event(
+                    &mut events,
+                    username,
+                    EventKind::ScoresSubmitted(scores),
+                    now,
+                );
+            }
+            SystemAction::SecretVeto(movie) => {
+                if self.system_state != SystemState::SystemStarted {
+                    return Err(SystemError::WrongState);
+                }
+                if self.selection_mode != SelectionMode::SecretVeto {
+                    return Err(SystemError::WrongMode);
+                }
+                if !self.round.participants.iter().any(|p| p == username) {
+                    return Err(SystemError::NotParticipant);
+                }
+                let movie = self
+                    .find_movie(&movie.movie_id)
+                    .ok_or(SystemError::MovieNotFound)?;
+                self.check_own_veto(username, &movie)?;
+                self.push_event(
+                    &mut events,
+                    username,
+                    EventKind::SecretVetoCast { movie: Some(movie) },
+                    now,
+                );
+            }
+            SystemAction::VoteMatchup { matchup, movie_id } => {
+                if self.system_state != SystemState::SystemStarted {
+                    return Err(SystemError::WrongState);
+                }
+                if self.selection_mode != SelectionMode::Bracket {
+                    return Err(SystemError::WrongMode);
+                }
+                if !self.round.participants.iter().any(|p| p == username) {
+                    return Err(SystemError::NotParticipant);
+                }
+                let current = self
+                    .round
+                    .bracket
+                    .as_ref()
+                    .and_then(|bracket| bracket.current_round())
+                    .and_then(|round| round.get(matchup))
+                    .ok_or(SystemError::InvalidBallot)?;
+                if current.winner.is_some()
+                    || (current.first != movie_id && current.second.as_ref() != Some(&movie_id))
+                {
+                    return Err(SystemError::InvalidBallot);
+                }
+                self.push_event(
+                    &mut events,
+                    username,
+                    EventKind::MatchupVoted { matchup, movie_id },
+                    now,
+                );
+            }
+            SystemAction::Comment { movie_id, text } => {
+                let kind = self.comment_event(movie_id, text)?;
+                self.push_event(&mut events, username, kind, now);
+            }
+            SystemAction::SetPitch { movie_id, pitch } => {
+                let kind = self.pitch_event(username, movie_id, pitch)?;
+                self.push_event(&mut events, username, kind, now);
+            }
+        }
+        Ok(events)
+    }
+
+    fn add_movie(
+        &mut self,
+        events: &mut Vec<GroupEvent>,
+        username: &str,
+        ready_status: bool,
+        movie: MovieDisplay,
+        rewatch: bool,
+        now: i64,
+    ) -> Result<(), SystemError> {
+        if self.system_state != SystemState::AddingMovies {
+            return Err(SystemError::WrongState);
+        }
+        if ready_status {
+            return Err(SystemError::AlreadyReady);
+        }
+        if self.find_movie(&movie.movie_id).is_some() {
+            return Err(SystemError::AlreadyAdded);
+        }
+        if let Some(max_movies) = self.rules.max_movies_per_member {
+            if self.movies_added_by(username) >= max_movies as usize {
+                return Err(SystemError::TooManyMovies);
+            }
+        }
+        let mut movie = movie.into_yew_display(username.to_string());
+        movie.rewatch = rewatch;
+        self.push_event(events, username, EventKind::MovieAdded(movie), now);
+        Ok(())
+    }
+
+    /// Picks the winner for modes that don't need any more input once the round has started.
+    /// The seed comes from the server, so members can't work out the result in advance.
+    pub fn resolve_round(&mut self, seed: u64, now: i64) -> Vec<GroupEvent> {
+        let mut events = Vec::new();
+        if self.system_state == SystemState::AddingMovies
+            && self.rules.turn_order == TurnOrder::Shuffled
+            && self.ready_to_start()
+        {
+            self.start_round(&mut events, "", seed, now);
+        }
+        if self.system_state != SystemState::SystemStarted {
+            return events;
+        }
+        if let SelectionMode::RandomDraw { weighted } = self.selection_mode {
+            let mut candidates = self.current_movies.iter().cloned().collect::<Vec<_>>();
+            candidates.sort_by(|a, b| a.movie_id.cmp(&b.movie_id));
+            let weights = candidates
+                .iter()
+                .map(|movie| DrawWeight {
+                    movie_id: movie.movie_id.clone(),
+                    weight: match weighted {
+                        true => draw_weight(&movie.added_by, &self.recent_winners),
+                        false => 1,
+                    },
+                })
+                .collect::<Vec<_>>();
+            if let Some(winner) = draw_winner(&weights, seed) {
+                let draw = DrawResult {
+                    seed,
+                    weights,
+                    winner: candidates[winner].clone(),
+                };
+                self.push_event(&mut events, "", EventKind::WinnerDrawn(draw), now);
+            }
+        }
+        if self.selection_mode == SelectionMode::RankedChoice
+            && self.all_voted(&self.round.rankings)
+        {
+            let ballots = self.round.rankings.values().cloned().collect::<Vec<_>>();
+            let (rounds, winner) = instant_runoff(&self.candidate_ids(), &ballots, seed);
+            if let Some(winner) = winner.and_then(|movie_id| self.find_movie(&movie_id)) {
+                let runoff = RunoffResult {
+                    seed,
+                    rounds,
+                    winner,
+                };
+                self.push_event(&mut events, "", EventKind::RunoffCounted(runoff), now);
+            }
+        }
+        if matches!(self.selection_mode, SelectionMode::Score { .. })
+            && self.all_voted(&self.round.scores)
+        {
+            let ballots = self.round.scores.values().cloned().collect::<Vec<_>>();
+            let (totals, winner) = score_winner(&self.candidate_ids(), &ballots, seed);
+            if let Some(winner) = winner.and_then(|movie_id| self.find_movie(&movie_id)) {
+                let score = ScoreResult {
+                    seed,
+                    totals,
+                    ballots: self.round.scores.clone(),
+                    winner,
+                };
+                self.push_event(&mut events, "", EventKind::ScoresCounted(score), now);
+            }
+        }
+        if self.selection_mode == SelectionMode::Bracket {
+            self.resolve_bracket(&mut events, seed, now);
+        }
+        if self.selection_mode == SelectionMode::SecretVeto
+            && !self.round.secret_vetoes.is_empty()
+            && self.all_voted(&self.round.secret_vetoes)
+        {
+            let mut vetoes = self
+                .round
+                .secret_vetoes
+                .iter()
+                .filter_map(|(username, movie_id)| {
+                    let movie = self.find_movie(movie_id)?;
+                    Some(VetoRecord {
+                        username: username.clone(),
+                        movie,
+                        date: now,
+                    })
+                })
+                .collect::<Vec<_>>();
+            vetoes.sort_by(|a, b| a.username.cmp(&b.username));
+            let vetoed_ids = vetoes
+                .iter()
+                .map(|veto| veto.movie.movie_id.clone())
+                .collect::<Vec<_>>();
+            let removed = secret_veto_removals(&self.candidate_ids(), &vetoed_ids, seed);
+            self.push_event(
+                &mut events,
+                "",
+                EventKind::VetoesRevealed { vetoes, removed },
+                now,
+            );
+        }
+        events
+    }
+
+    /// Blanks out everyone else's secret veto, so members can see who's still to vote, not what.
+    pub fn hide_secret_vetoes(&mut self, username: &str) {
+        for (voter, movie_id) in self.round.secret_vetoes.iter_mut() {
+            if voter != username {
+                movie_id.clear();
+            }
+        }
+    }
+
+    /// Seeds the bracket when the round starts, then decides every match-up everyone has
+    /// voted on. A tied match-up is drawn with the bracket's own seed, which the history keeps.
+    fn resolve_bracket(&mut self, events: &mut Vec<GroupEvent>, seed: u64, now: i64) {
+        if self.round.bracket.is_none() {
+            let bracket = Bracket::new(&self.candidate_ids(), seed);
+            self.push_event(events, "", EventKind::BracketSeeded(bracket), now);
+        }
+        let bracket = match self.round.bracket.as_ref() {
+            Some(bracket) => bracket,
+            None => return,
+        };
+        let mut rng = bracket.tie_rng();
+        let round = match bracket.current_round() {
+            Some(round) => round.clone(),
+            None => return,
+        };
+        for (index, matchup) in round.iter().enumerate() {
+            let second = match &matchup.second {
+                Some(second) if matchup.winner.is_none() && self.all_voted(&matchup.votes) => {
+                    second
+                }
+                _ => continue,
+            };
+            let first_votes = matchup
+                .votes
+                .values()
+                .filter(|movie_id| **movie_id == matchup.first)
+                .count();
+            let second_votes = matchup.votes.len() - first_votes;
+            let winner = match first_votes.cmp(&second_votes) {
+                Ordering::Greater => matchup.first.clone(),
+                Ordering::Less => second.clone(),
+                Ordering::Equal => match rng.below(2) {
+                    0 => matchup.first.clone(),
+                    _ => second.clone(),
+                },
+            };
+            self.push_event(
+                events,
+                "",
+                EventKind::MatchupDecided {
+                    matchup: index,
+                    winner,
+                },
+                now,
+            );
+        }
+    }
+
+    /// Whether every participant still in the group has a ballot in.
+    pub fn all_voted<T>(&self, ballots: &HashMap<String, T>) -> bool {
+        self.round
+            .participants
+            .iter()
+            .filter(|participant| self.members.contains_key(*participant))
+            .all(|participant| ballots.contains_key(participant))
+    }
+
+    pub fn movies_added_by(&self, username: &str) -> usize {
+        self.current_movies
+            .iter()
+            .filter(|movie| movie.added_by == username)
+            .count()
+    }
+
+    /// How many vetoes in a row the member has cast at the end of the round so far.
+    pub fn vetoes_this_turn(&self, username: &str) -> usize {
+        self.round
+            .vetoes
+            .iter()
+            .rev()
+            .take_while(|veto| veto.username == username)
+            .count()
+    }
+
+    /// With `veto_own_movie` off, your own movies are safe unless they're all that's left.
+    fn check_own_veto(&self, username: &str, movie: &YewMovieDisplay) -> Result<(), SystemError> {
+        if !self.rules.veto_own_movie
+            && movie.added_by == username
+            && self.movies_added_by(username) < self.current_movies.len()
+        {
+            return Err(SystemError::OwnMovie);
+        }
+        Ok(())
+    }
+
+    /// Sorted, so ballots can be checked against them.
+    fn candidate_ids(&self) -> Vec<String> {
+        let mut candidate_ids = self
+            .current_movies
+            .iter()
+            .map(|movie| movie.movie_id.clone())
+            .collect::<Vec<_>>();
+        candidate_ids.sort();
+        candidate_ids
+    }
+
+    /// Puts back the last vetoed movie if it's the member's own veto and it's still inside
+    /// the grace window. A veto that finished the round reopens it.
+    pub fn undo_veto(
+        &mut self,
+        username: &str,
+        now: i64,
+        grace_seconds: i64,
+    ) -> Result<Vec<GroupEvent>, SystemError> {
+        if !self.members.contains_key(username) {
+            return Err(SystemError::NotInGroup);
+        }
+        if self.system_state == SystemState::AddingMovies {
+            return Err(SystemError::WrongState);
+        }
+        if self.selection_mode != SelectionMode::Veto {
+            return Err(SystemError::WrongMode);
+        }
+        let last_veto = self.round.vetoes.last().ok_or(SystemError::NothingToUndo)?;
+        if last_veto.username != username {
+            return Err(SystemError::NotYourVeto);
+        }
+        if !self.can_undo(now, grace_seconds) {
+            return Err(SystemError::UndoExpired);
+        }
+        // A deadline since the veto has moved the turn on, so it can't be handed back.
+        let turn_moved = match self.system_state {
+            SystemState::Finished => false,
+            _ if self
+                .vetoes_this_turn(username)
+                .is_multiple_of(self.rules.turn_vetoes()) =>
+            {
+                self.deadline_from != last_veto.date
+            }
+            _ => self.turn != username,
+        };
+        if turn_moved {
+            return Err(SystemError::UndoExpired);
+        }
+        let movie = last_veto.movie.clone();
+        let mut events = Vec::new();
+        self.push_event(&mut events, username, EventKind::VetoUndone(movie), now);
+        Ok(events)
+    }
+
+    /// A finished round can't be moved into the history while its last veto can still be undone.
+    pub fn can_undo(&self, now: i64, grace_seconds: i64) -> bool {
+        self.undo_deadline(grace_seconds)
+            .is_some_and(|deadline| now <= deadline)
+    }
+
+    /// The last moment the last veto can be undone, `None` if there isn't one that could be.
+    pub fn undo_deadline(&self, grace_seconds: i64) -> Option<i64> {
+        if self.selection_mode != SelectionMode::Veto {
+            return None;
+        }
+        self.round
+            .vetoes
+            .last()
+            .filter(|last_veto| !last_veto.is_auto_veto())
+            .map(|last_veto| last_veto.date + grace_seconds)
+    }
+
+    /// When the adding phase or the current veto turn runs out, if the group has set a limit.
+    /// The adding phase only counts once there's something to start the round with.
+    pub fn deadline(&self) -> Option<i64> {
+        match self.system_state {
+            SystemState::AddingMovies if !self.current_movies.is_empty() => self
+                .rules
+                .adding_deadline_secs
+                .map(|secs| self.deadline_from + secs),
+            SystemState::SystemStarted if self.selection_mode == SelectionMode::Veto => self
+                .rules
+                .turn_deadline_secs
+                .map(|secs| self.deadline_from + secs),
+            _ => None,
+        }
+    }
+
+    /// Run by the server's deadline task. A late adding phase starts the round with what's
+    /// there, and a late veto turn is skipped or vetoes a random movie the member didn't add.
+    pub fn enforce_deadline(&mut self, seed: u64, now: i64) -> Vec<GroupEvent> {
+        let mut events = Vec::new();
+        match self.deadline() {
+            Some(deadline) if now >= deadline => {}
+            _ => return events,
+        }
+        if self.system_state == SystemState::AddingMovies {
+            let mut missed_by = self
+                .members
+                .iter()
+                .filter(|(_, user_status)| !user_status.ready_status)
+                .map(|(username, _)| username.clone())
+                .collect::<Vec<_>>();
+            missed_by.sort();
+            self.push_event(
+                &mut events,
+                "",
+                EventKind::DeadlinePassed { missed_by },
+                now,
+            );
+            self.start_round(&mut events, "", seed, now);
+            return events;
+        }
+        let turn = self.turn.clone();
+        self.push_event(
+            &mut events,
+            "",
+            EventKind::DeadlinePassed {
+                missed_by: vec![turn.clone()],
+            },
+            now,
+        );
+        if self.rules.on_turn_timeout == TimeoutAction::AutoVeto {
+            let mut candidates = self
+                .current_movies
+                .iter()
+                .filter(|movie| movie.added_by != turn)
+                .cloned()
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                candidates = self.current_movies.iter().cloned().collect();
+            }
+            candidates.sort_by(|a, b| a.movie_id.cmp(&b.movie_id));
+            let movie =
+                candidates.remove(SeededRng::new(seed).below(candidates.len() as u64) as usize);
+            // Under the system's name, so the late member can't undo it to win their turn back.
+            self.push_event(&mut events, "", EventKind::VetoCast(movie), now);
+        }
+        if self.system_state == SystemState::SystemStarted {
+            self.pass_turn(&mut events, "", now);
+        }
+        events
+    }
+
+    /// Moves the winner into the watch history and clears the system for the next round.
+    /// Returns `None` if the round isn't finished yet.
+    pub fn finish_round(&mut self, id: String, now: i64) -> Option<(RoundHistory, GroupEvent)> {
+        if self.system_state != SystemState::Finished {
+            return None;
+        }
+        let winner = self.current_movies.iter().next()?.clone();
+        let history = RoundHistory {
+            id: id.clone(),
+            added_by: winner.added_by.clone(),
+            winner: winner.clone(),
+            date_started: self.round.date_started,
+            // Rounds finished before this was kept only know when they were settled.
+            date_finished: match self.round.date_finished {
+                0 => now,
+                date_finished => date_finished,
+            },
+            participants: self.round.participants.clone(),
+            candidates: self.round.candidates.clone(),
+            selection_mode: self.selection_mode.clone(),
+            ratings: HashMap::new(),
+            vetoes: self.round.vetoes.clone(),
+            draw: self.round.draw.clone(),
+            runoff: self.round.runoff.clone(),
+            score: self.round.score.clone(),
+            bracket: self.round.bracket.clone(),
+            discussions: self.discussions.clone(),
+        };
+        let event = GroupEvent {
+            actor: String::from(""),
+            date: now,
+            kind: EventKind::RoundFinished {
+                round_id: id,
+                winner,
+            },
+        };
+        self.apply_event(&event);
+        Some((history, event))
+    }
+
+    pub fn find_movie(&self, movie_id: &str) -> Option<YewMovieDisplay> {
+        self.current_movies
+            .iter()
+            .find(|movie| movie.movie_id == movie_id)
+            .cloned()
+    }
+
+    pub(crate) fn push_event(
+        &mut self,
+        events: &mut Vec<GroupEvent>,
+        username: &str,
+        kind: EventKind,
+        now: i64,
+    ) {
+        let event = GroupEvent {
+            actor: username.to_string(),
+            date: now,
+            kind,
+        };
+        self.apply_event(&event);
+        events.push(event);
+    }
+
+    fn ready_to_start(&self) -> bool {
+        self.members
+            .values()
+            .all(|user_status| user_status.ready_status)
+            && !self.current_movies.is_empty()
+    }
+
+    fn start_round(&mut self, events: &mut Vec<GroupEvent>, username: &str, seed: u64, now: i64) {
+        let members = self.members.keys().cloned().collect::<Vec<String>>();
+        let order = turn_order(
+            &self.rules.turn_order,
+            &members,
+            &self.recent_winners,
+            self.rounds_played,
+            seed,
+        );
+        let seed = match self.rules.turn_order {
+            TurnOrder::Shuffled => Some(seed),
+            _ => None,
+        };
+        self.push_event(
+            events,
+            username,
+            EventKind::RoundStarted { order, seed },
+            now,
+        );
+        if self.selection_mode == SelectionMode::Veto {
+            self.pass_turn(events, username, now);
+        }
+    }
+
+    fn pass_turn(&mut self, events: &mut Vec<GroupEvent>, username: &str, now: i64) {
+        let to = self.system_order.front().cloned().unwrap_or_default();
+        self.push_event(events, username, EventKind::TurnPassed { to }, now);
+    }
+
+    pub(crate) fn next_turn(&mut self) {
+        if let Some(current_turn) = self.system_order.pop_front() {
+            self.turn = current_turn.clone();
+            self.system_order.push_back(current_turn);
+        }
+    }
+
+    pub(crate) fn previous_turn(&mut self) {
+        if let Some(current_turn) = self.system_order.pop_back() {
+            self.system_order.push_front(current_turn);
+        }
+    }
+
+    pub(crate) fn reset_round(&mut self) {
+        self.current_movies.clear();
+        self.system_state = SystemState::AddingMovies;
+        self.system_order.clear();
+        self.turn = String::from("");
+        self.round = RoundData::default();
+        self.discussions.clear();
+        for user_status in self.members.values_mut() {
+            user_status.ready_status = false;
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::imdb_structs::ImageData;
+use crate::provider_structs::{MovieDetails, MovieRating, MovieSource, SearchResults};
+use crate::shared_structs::MovieDisplay;
+use serde::{Deserialize, Serialize};
+
+/// Posters are only given as a path, this is where the small ones are.
+pub const TMDB_POSTER_URL: &str = "https://image.tmdb.org/t/p/w185";
+
+/// How many of the cast go in the details.
+pub const TMDB_MAX_ACTORS: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbMovie {
+    pub id: u64,
+    pub title: String,
+    /// "YYYY-MM-DD", or empty if it isn't out.
+    pub release_date: String,
+    pub poster_path: Option<String>,
+    pub overview: String,
+    pub vote_average: f32,
+}
+
+/// One page of `search/movie`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbSearch {
+    pub page: u32,
+    pub results: Vec<TmdbMovie>,
+    pub total_results: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbGenre {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbCastMember {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbCrewMember {
+    pub name: String,
+    pub job: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbCredits {
+    pub cast: Vec<TmdbCastMember>,
+    pub crew: Vec<TmdbCrewMember>,
+}
+
+/// `movie/{id}` with `append_to_response=credits`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TmdbDetails {
+    pub id: u64,
+    pub imdb_id: Option<String>,
+    pub title: String,
+    pub release_date: String,
+    pub overview: String,
+    /// In minutes.
+    pub runtime: Option<u32>,
+    pub genres: Vec<TmdbGenre>,
+    pub poster_path: Option<String>,
+    pub vote_average: f32,
+    pub credits: TmdbCredits,
+}
+
+fn tmdb_year(release_date: &str) -> u32 {
+    release_date
+        .split('-')
+        .next()
+        .and_then(|year| year.parse().ok())
+        .unwrap_or(0)
+}
+
+fn tmdb_poster(poster_path: &Option<String>) -> String {
+    poster_path
+        .as_ref()
+        .map(|path| format!("{}{}", TMDB_POSTER_URL, path))
+        .unwrap_or_default()
+}
+
+impl TmdbSearch {
+    pub fn into_results(self) -> SearchResults {
+        let movies = self
+            .results
+            .into_iter()
+            .map(|movie| MovieDisplay {
+                movie_id: MovieSource::Tmdb.canonical_id(&movie.id.to_string()),
+                movie_year: tmdb_year(&movie.release_date),
+                movie_images: ImageData {
+                    url: tmdb_poster(&movie.poster_path),
+                    width: 0,
+                    height: 0,
+                },
+                movie_stars: String::new(),
+                source: MovieSource::Tmdb,
+                movie_title: movie.title,
+            })
+            .collect::<Vec<_>>();
+        SearchResults {
+            movies,
+            exhaustive: false,
+        }
+    }
+}
+
+impl TmdbDetails {
+    pub fn into_details(self) -> MovieDetails {
+        let director = self
+            .credits
+            .crew
+            .iter()
+            .find(|member| member.job == "Director")
+            .map(|member| member.name.clone())
+            .unwrap_or_default();
+        MovieDetails {
+            movie_id: MovieSource::Tmdb.canonical_id(&self.id.to_string()),
+            source: MovieSource::Tmdb,
+            year: tmdb_year(&self.release_date),
+            plot: self.overview,
+            runtime: self
+                .runtime
+                .filter(|runtime| *runtime > 0)
+                .map(|runtime| format!("{} min", runtime))
+                .unwrap_or_default(),
+            genres: self.genres.into_iter().map(|genre| genre.name).collect(),
+            director,
+            actors: self
+                .credits
+                .cast
+                .into_iter()
+                .take(TMDB_MAX_ACTORS)
+                .map(|member| member.name)
+                .collect(),
+            poster: tmdb_poster(&self.poster_path),
+            // TMDB has no votes yet as 0.
+            rating: Some(self.vote_average).filter(|rating| *rating > 0.0),
+            ratings: Some(self.vote_average)
+                .filter(|rating| *rating > 0.0)
+                .map(|rating| MovieRating {
+                    source: String::from("TMDB"),
+                    value: format!("{:.1}/10", rating),
+                })
+                .into_iter()
+                .collect(),
+            imdb_id: self.imdb_id.filter(|imdb_id| !imdb_id.is_empty()),
+            title: self.title,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::db_structs::UserData;
+use crate::shared_structs::MovieDisplay;
+use crate::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchlistEntry {
+    pub movie: MovieDisplay,
+    #[serde(default)]
+    pub note: String,
+    #[serde(default)]
+    pub priority: Priority,
+    pub date_added: i64,
+}
+
+/// Sent to `watchlist_action`, the whole watchlist comes back.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum WatchlistAction {
+    Add(MovieDisplay),
+    Remove(String),
+    SetNote {
+        movie_id: String,
+        note: String,
+    },
+    SetPriority {
+        movie_id: String,
+        priority: Priority,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum WatchlistError {
+    AlreadyListed,
+    NotListed,
+    NoteTooLong,
+}
+
+/// Longer notes are turned down rather than cut short.
+pub const MAX_NOTE_LENGTH: usize = 500;
+
+impl UserData {
+    /// Applies the action and keeps the list in order, highest priority then oldest first.
+    pub fn apply_watchlist_action(
+        &mut self,
+        action: WatchlistAction,
+        now: i64,
+    ) -> Result<(), WatchlistError> {
+        match action {
+            WatchlistAction::Add(movie) => {
+                let movie = movie.canonical();
+                if self.watchlist_entry(&movie.movie_id).is_some() {
+                    return Err(WatchlistError::AlreadyListed);
+                }
+                self.watchlist.push(WatchlistEntry {
+                    movie,
+                    note: String::from(""),
+                    priority: Priority::default(),
+                    date_added: now,
+                });
+            }
+            WatchlistAction::Remove(movie_id) => {
+                self.watchlist_entry(&movie_id)
+                    .ok_or(WatchlistError::NotListed)?;
+                self.watchlist
+                    .retain(|entry| entry.movie.movie_id != movie_id);
+            }
+            WatchlistAction::SetNote { movie_id, note } => {
+                if note.chars().count() > MAX_NOTE_LENGTH {
+                    return Err(WatchlistError::NoteTooLong);
+                }
+                self.watchlist_entry(&movie_id)
+                    .ok_or(WatchlistError::NotListed)?
+                    .note = note;
+            }
+            WatchlistAction::SetPriority { movie_id, priority } => {
+                self.watchlist_entry(&movie_id)
+                    .ok_or(WatchlistError::NotListed)?
+                    .priority = priority;
+            }
+        }
+        self.watchlist.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.date_added.cmp(&b.date_added))
+        });
+        Ok(())
+    }
+
+    fn watchlist_entry(&mut self, movie_id: &str) -> Option<&mut WatchlistEntry> {
+        self.watchlist
+            .iter_mut()
+            .find(|entry| entry.movie.movie_id == movie_id)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::event_structs::{EventKind, GroupEvent};
+use crate::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBWebhook {
+    pub id: String,
+    pub group_id: String,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBDelivery {
+    pub id: i64,
+    pub webhook_id: String,
+    pub date: i64,
+    pub data: String,
+}
+
+/// Kept out of `GroupData` so the secret never goes out with the group.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Webhook {
+    pub id: String,
+    pub group_id: String,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub date_created: i64,
+}
+
+/// What the owner sends to register a webhook.
+#[derive(Debug, Serialize, Deserialize, Validate, Clone, PartialEq)]
+pub struct WebhookForm {
+    #[validate(url)]
+    pub url: String,
+    #[validate(length(min = 8))]
+    pub secret: String,
+    #[validate(length(min = 1))]
+    pub events: Vec<WebhookEvent>,
+}
+
+/// A webhook as the owner sees it, without the secret.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WebhookInfo {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub date_created: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookEvent {
+    RoundStarted,
+    VetoCast,
+    RoundFinished,
+    MemberJoined,
+    /// Only sent by the test-ping endpoint.
+    Ping,
+}
+
+/// The JSON body of every delivery. The `X-Webhook-Signature` header is the hex
+/// HMAC-SHA256 of it, keyed with the webhook's secret.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WebhookPayload {
+    pub delivery_id: String,
+    pub group_id: String,
+    pub event: WebhookEvent,
+    pub date: i64,
+    /// `None` for pings.
+    pub data: Option<GroupEvent>,
+}
+
+/// One delivery, however many attempts it took.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DeliveryRecord {
+    pub delivery_id: String,
+    pub event: WebhookEvent,
+    pub date: i64,
+    pub attempts: u32,
+    /// The last response's status, `None` if it never got one.
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+impl From<&Webhook> for WebhookInfo {
+    fn from(webhook: &Webhook) -> Self {
+        WebhookInfo {
+            id: webhook.id.clone(),
+            url: webhook.url.clone(),
+            events: webhook.events.clone(),
+            date_created: webhook.date_created,
+        }
+    }
+}
+
+impl WebhookEvent {
+    /// Secret vetoes are left out, so a hook can't see them before the reveal.
+    pub fn from_event(kind: &EventKind) -> Option<WebhookEvent> {
+        match kind {
+            EventKind::RoundStarted { .. } => Some(WebhookEvent::RoundStarted),
+            EventKind::VetoCast(_) => Some(WebhookEvent::VetoCast),
+            EventKind::RoundFinished { .. } => Some(WebhookEvent::RoundFinished),
+            EventKind::MemberAdded { .. } => Some(WebhookEvent::MemberJoined),
+            _ => None,
+        }
+    }
+}
+
+impl Webhook {
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        event == WebhookEvent::Ping || self.events.contains(&event)
+    }
+}
+
+impl DeliveryRecord {
+    pub fn succeeded(&self) -> bool {
+        matches!(self.status, Some(200..=299))
+    }
+}
@@ -0,0 +1,147 @@
+use shared_stuff::history_structs::{Rating, RatingsSummary, RoundHistory, VetoRecord};
+use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::provider_structs::MovieSource;
+use shared_stuff::selection_structs::SelectionMode;
+use shared_stuff::shared_structs::YewMovieDisplay;
+use shared_stuff::stats_structs::GroupStats;
+use std::collections::HashMap;
+
+fn test_movie(id: &str, added_by: &str) -> YewMovieDisplay {
+    YewMovieDisplay {
+        movie_id: format!("tt{}", id),
+        movie_title: format!("title {}", id),
+        movie_year: 1999,
+        movie_images: ImageData {
+            url: "https://m.media-amazon.com/images/test.jpg".to_string(),
+            width: 100,
+            height: 100,
+        },
+        movie_stars: String::from(""),
+        source: MovieSource::Imdb,
+        added_by: added_by.to_string(),
+        rewatch: false,
+    }
+}
+
+fn test_round(id: &str, added_by: &str, date_finished: i64, scores: &[(&str, u8)]) -> RoundHistory {
+    let winner = test_movie(id, added_by);
+    let ratings = scores
+        .iter()
+        .map(|(member, score)| {
+            let rating = Rating {
+                score: *score,
+                comment: None,
+                date: date_finished,
+            };
+            (member.to_string(), rating)
+        })
+        .collect::<HashMap<String, Rating>>();
+    RoundHistory {
+        id: id.to_string(),
+        added_by: added_by.to_string(),
+        candidates: vec![winner.clone()],
+        selection_mode: SelectionMode::Veto,
+        winner,
+        date_started: date_finished - 100,
+        date_finished,
+        participants: vec!["indiana".to_string(), "marion".to_string()],
+        ratings,
+        vetoes: Vec::new(),
+        draw: None,
+        runoff: None,
+        score: None,
+        bracket: None,
+        discussions: HashMap::new(),
+    }
+}
+
+#[test]
+// Rounds nobody rated are left out, the rest are averaged in the order they were watched.
+fn ratings_summary() {
+    let history = vec![
+        test_round("2", "marion", 200, &[("indiana", 4), ("marion", 8)]),
+        test_round("1", "indiana", 100, &[("indiana", 10), ("marion", 8)]),
+        test_round("3", "indiana", 300, &[]),
+    ];
+    let summary = RatingsSummary::from_history(&history);
+    assert_eq!(summary.ratings_count, 4);
+    assert_eq!(summary.average, Some(7.5));
+    assert_eq!(summary.over_time.len(), 2);
+    assert_eq!(summary.over_time[0].round_id, "1");
+    assert_eq!(summary.over_time[0].average, 9.0);
+    assert_eq!(summary.over_time[1].running_average, 7.5);
+    assert_eq!(summary.by_added_by.len(), 2);
+    assert_eq!(summary.by_added_by[0].added_by, "indiana");
+    assert_eq!(summary.by_added_by[0].average, 9.0);
+    assert_eq!(summary.by_added_by[1].average, 6.0);
+}
+
+#[test]
+// Two rounds, marion goes first in both, and indiana vetoes both of marion's other picks.
+fn group_stats() {
+    let mut round_1 = test_round("1", "indiana", 1000, &[]);
+    round_1.participants = vec!["marion".to_string(), "indiana".to_string()];
+    round_1.candidates = vec![
+        test_movie("1", "indiana"),
+        test_movie("2", "marion"),
+        test_movie("3", "indiana"),
+    ];
+    round_1.vetoes = vec![
+        VetoRecord {
+            username: "marion".to_string(),
+            movie: test_movie("3", "indiana"),
+            date: 950,
+        },
+        VetoRecord {
+            username: "indiana".to_string(),
+            movie: test_movie("2", "marion"),
+            date: 980,
+        },
+    ];
+    let mut round_2 = test_round("4", "marion", 2000, &[]);
+    round_2.participants = vec!["marion".to_string(), "indiana".to_string()];
+    round_2.candidates = vec![test_movie("4", "marion"), test_movie("5", "marion")];
+    round_2.vetoes = vec![
+        VetoRecord {
+            username: "indiana".to_string(),
+            movie: test_movie("5", "marion"),
+            date: 1950,
+        },
+        // The server's, when someone's turn ran out. It isn't anyone's.
+        VetoRecord {
+            username: "".to_string(),
+            movie: test_movie("6", "marion"),
+            date: 1960,
+        },
+    ];
+
+    let stats = GroupStats::from_history(&[round_1.clone(), round_2.clone()]);
+    assert_eq!(stats.rounds_played, 2);
+    assert_eq!(stats.average_candidates, 2.5);
+    assert_eq!(stats.average_round_duration, 100.0);
+
+    let indiana = &stats.members[0];
+    assert_eq!(indiana.username, "indiana");
+    assert_eq!(indiana.wins, 1);
+    assert_eq!(indiana.vetoes_cast, 2);
+    assert_eq!(indiana.movies_vetoed, 1);
+    let marion = &stats.members[1];
+    assert_eq!(marion.movies_added, 3);
+    assert_eq!(marion.movies_vetoed, 2);
+
+    assert_eq!(stats.veto_counts.len(), 2);
+    assert_eq!(stats.veto_counts[0].vetoed_by, "indiana");
+    assert_eq!(stats.veto_counts[0].added_by, "marion");
+    assert_eq!(stats.veto_counts[0].count, 2);
+
+    assert_eq!(stats.turn_positions[0].rounds, 2);
+    assert_eq!(stats.turn_positions[0].wins, 1);
+    assert_eq!(stats.turn_positions[1].win_rate, 0.5);
+
+    // Nobody takes turns in a score round, so it's left out of the turn positions.
+    let mut round_3 = test_round("6", "indiana", 3000, &[]);
+    round_3.selection_mode = SelectionMode::Score { approval: false };
+    let with_score_round = GroupStats::from_history(&[round_1, round_2, round_3]);
+    assert_eq!(with_score_round.rounds_played, 3);
+    assert_eq!(with_score_round.turn_positions, stats.turn_positions);
+}
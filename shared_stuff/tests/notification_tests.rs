@@ -0,0 +1,66 @@
+use shared_stuff::db_structs::GroupData;
+use shared_stuff::event_structs::{EventKind, GroupEvent};
+use shared_stuff::group_structs::GroupUserData;
+use shared_stuff::notification_structs::NotificationKind;
+
+fn event(actor: &str, kind: EventKind) -> GroupEvent {
+    GroupEvent {
+        actor: actor.to_string(),
+        date: 10,
+        kind,
+    }
+}
+
+#[test]
+// Members hear about what the others do, never about their own moves.
+fn notifications_skip_the_actor() {
+    let mut group_data = GroupData::new_empty();
+    group_data.group_name = String::from("movie club");
+    for member in ["indiana", "marion"] {
+        group_data
+            .members
+            .insert(member.to_string(), GroupUserData::default());
+    }
+
+    let added = event(
+        "indiana",
+        EventKind::MemberAdded {
+            username: String::from("marion"),
+        },
+    );
+    let notifications = group_data.notifications_for("group", &added);
+    assert_eq!(notifications.len(), 2);
+    assert!(notifications
+        .iter()
+        .all(|(username, _)| username == "marion"));
+    assert_eq!(
+        notifications[0].1.message(),
+        "indiana invited you to movie club"
+    );
+    assert_eq!(
+        notifications[1].1.message(),
+        "indiana added you to movie club"
+    );
+
+    let started = event(
+        "indiana",
+        EventKind::RoundStarted {
+            order: vec![String::from("marion"), String::from("indiana")],
+            seed: None,
+        },
+    );
+    let notifications = group_data.notifications_for("group", &started);
+    assert_eq!(notifications.len(), 1);
+    assert!(matches!(
+        notifications[0].1,
+        NotificationKind::RoundStarted { .. }
+    ));
+
+    let own_turn = event(
+        "marion",
+        EventKind::TurnPassed {
+            to: String::from("marion"),
+        },
+    );
+    assert!(group_data.notifications_for("group", &own_turn).is_empty());
+}
@@ -0,0 +1,166 @@
+use shared_stuff::selection_structs::{
+    instant_runoff, score_winner, secret_veto_removals, turn_order, Bracket, TurnOrder,
+};
+use std::collections::{HashMap, VecDeque};
+
+fn ballot(ranking: &[&str]) -> Vec<String> {
+    ranking
+        .iter()
+        .map(|movie_id| movie_id.to_string())
+        .collect()
+}
+
+#[test]
+// Nobody has a majority at first, so the last place movie's votes move on.
+fn runoff_transfers_votes() {
+    let candidates = ballot(&["tt1", "tt2", "tt3"]);
+    let ballots = vec![
+        ballot(&["tt1", "tt2", "tt3"]),
+        ballot(&["tt1", "tt2", "tt3"]),
+        ballot(&["tt2", "tt3", "tt1"]),
+        ballot(&["tt2", "tt3", "tt1"]),
+        ballot(&["tt3", "tt2", "tt1"]),
+    ];
+    let (rounds, winner) = instant_runoff(&candidates, &ballots, 7);
+    assert_eq!(winner, Some("tt2".to_string()));
+    assert_eq!(rounds.len(), 2);
+    assert_eq!(rounds[0].eliminated, Some("tt3".to_string()));
+    assert_eq!(rounds[1].counts[1].votes, 3);
+    assert_eq!(rounds[1].eliminated, None);
+}
+
+#[test]
+// Last place ties go to the lower Borda count first, then the seed.
+fn runoff_tie_breaks() {
+    let candidates = ballot(&["tt1", "tt2", "tt3"]);
+    let ballots = vec![
+        ballot(&["tt1", "tt3", "tt2"]),
+        ballot(&["tt2", "tt3", "tt1"]),
+        ballot(&["tt3", "tt1", "tt2"]),
+    ];
+    // Everyone has one vote, tt3 has the best Borda count, tt1 and tt2 are level.
+    let (rounds, winner) = instant_runoff(&candidates, &ballots, 7);
+    let eliminated = rounds[0].eliminated.clone().unwrap();
+    assert_ne!(eliminated, "tt3");
+    assert_eq!(rounds, instant_runoff(&candidates, &ballots, 7).0);
+    assert!(winner.is_some());
+    assert_ne!(winner, Some(eliminated));
+}
+
+fn scores(scores: &[(&str, u8)]) -> HashMap<String, u8> {
+    scores
+        .iter()
+        .map(|(movie_id, score)| (movie_id.to_string(), *score))
+        .collect()
+}
+
+#[test]
+// tt1 and tt2 both total 5, but tt2 got a zero so tt1 wins.
+fn score_ties_go_to_fewest_zeros() {
+    let candidates = ballot(&["tt1", "tt2", "tt3"]);
+    let ballots = vec![
+        scores(&[("tt1", 3), ("tt2", 5), ("tt3", 1)]),
+        scores(&[("tt1", 2), ("tt2", 0), ("tt3", 1)]),
+    ];
+    let (totals, winner) = score_winner(&candidates, &ballots, 7);
+    assert_eq!(winner, Some("tt1".to_string()));
+    assert_eq!(totals[0].total, 5);
+    assert_eq!(totals[1].zeros, 1);
+    assert_eq!(totals[2].movie_id, "tt3");
+}
+
+#[test]
+// A complete tie is settled by the seed, the same way every time.
+fn score_ties_drawn_with_seed() {
+    let candidates = ballot(&["tt1", "tt2"]);
+    let ballots = vec![scores(&[("tt1", 1), ("tt2", 1)])];
+    let (_, winner) = score_winner(&candidates, &ballots, 7);
+    assert!(winner.is_some());
+    assert_eq!(winner, score_winner(&candidates, &ballots, 7).1);
+}
+
+#[test]
+// Five movies: two match-ups and a bye, then one and a bye for someone else, then the final.
+fn bracket_byes_and_advancing() {
+    let movie_ids = ballot(&["tt1", "tt2", "tt3", "tt4", "tt5"]);
+    let mut bracket = Bracket::new(&movie_ids, 7);
+    assert_eq!(bracket, Bracket::new(&movie_ids, 7));
+    let first_round = bracket.current_round().unwrap().clone();
+    assert_eq!(first_round.len(), 3);
+    assert_eq!(first_round[2].second, None);
+    assert_eq!(first_round[2].winner, Some(first_round[2].first.clone()));
+
+    bracket.decide(0, first_round[0].first.clone());
+    assert_eq!(bracket.rounds.len(), 1);
+    bracket.decide(1, first_round[1].first.clone());
+    assert_eq!(bracket.rounds.len(), 2);
+    let second_round = bracket.current_round().unwrap().clone();
+    assert_eq!(second_round.len(), 2);
+    assert_eq!(second_round[0].second, Some(first_round[2].first.clone()));
+    assert_eq!(second_round[1].second, None);
+    assert_ne!(second_round[1].first, first_round[2].first);
+    assert_eq!(bracket.champion(), None);
+
+    bracket.decide(0, first_round[2].first.clone());
+    assert_eq!(bracket.current_round().unwrap().len(), 1);
+    bracket.decide(0, first_round[2].first.clone());
+    assert_eq!(bracket.champion(), Some(first_round[2].first.clone()));
+}
+
+#[test]
+// Everything vetoed goes, unless that would leave nothing.
+fn secret_veto_ties() {
+    let candidates = ballot(&["tt1", "tt2", "tt3"]);
+    assert_eq!(
+        secret_veto_removals(&candidates, &ballot(&["tt1", "tt1", "tt2"]), 7),
+        ballot(&["tt1", "tt2"])
+    );
+    assert_eq!(
+        secret_veto_removals(&candidates, &ballot(&["tt1", "tt1", "tt2", "tt3"]), 7),
+        ballot(&["tt1"])
+    );
+    let removed = secret_veto_removals(&candidates, &ballot(&["tt1", "tt2", "tt3"]), 7);
+    assert_eq!(removed.len(), 2);
+    // A lone candidate survives however many vetoes it gets.
+    assert_eq!(
+        secret_veto_removals(&ballot(&["tt1"]), &ballot(&["tt1", "tt1"]), 7),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+// Every policy gives the same order for the same inputs, whatever order the members come in.
+fn turn_order_policies() {
+    let members = ballot(&["marion", "sallah", "indiana"]);
+    let reversed = ballot(&["indiana", "sallah", "marion"]);
+    let recent_winners = VecDeque::from(ballot(&["marion", "indiana"]));
+    for policy in [
+        TurnOrder::Shuffled,
+        TurnOrder::Fixed(ballot(&["sallah"])),
+        TurnOrder::LoserFirst,
+        TurnOrder::RoundRobin,
+    ] {
+        assert_eq!(
+            turn_order(&policy, &members, &recent_winners, 4, 9),
+            turn_order(&policy, &reversed, &recent_winners, 4, 9)
+        );
+    }
+    assert_eq!(
+        turn_order(
+            &TurnOrder::Fixed(ballot(&["sallah"])),
+            &members,
+            &recent_winners,
+            0,
+            0
+        ),
+        ballot(&["sallah", "indiana", "marion"])
+    );
+    assert_eq!(
+        turn_order(&TurnOrder::LoserFirst, &members, &recent_winners, 0, 0),
+        ballot(&["sallah", "indiana", "marion"])
+    );
+    assert_eq!(
+        turn_order(&TurnOrder::RoundRobin, &members, &recent_winners, 4, 0),
+        ballot(&["marion", "sallah", "indiana"])
+    );
+}
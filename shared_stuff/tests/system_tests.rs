@@ -0,0 +1,1051 @@
+use shared_stuff::backlog_structs::{BacklogAction, BacklogPick};
+use shared_stuff::db_structs::GroupData;
+use shared_stuff::discussion_structs::export_discussions;
+use shared_stuff::event_structs::{EventKind, GroupEvent};
+use shared_stuff::group_structs::{GroupRules, GroupUserData, TimeoutAction};
+use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::provider_structs::MovieSource;
+use shared_stuff::selection_structs::{draw_winner, SelectionMode, TurnOrder};
+use shared_stuff::shared_structs::{MovieDisplay, SystemState};
+use shared_stuff::system_structs::{SystemAction, SystemError};
+use std::collections::HashMap;
+
+fn test_movie(movie_id: &str) -> MovieDisplay {
+    MovieDisplay {
+        movie_id: movie_id.to_string(),
+        movie_title: format!("title {}", movie_id),
+        movie_year: 1999,
+        movie_images: ImageData {
+            url: "https://m.media-amazon.com/images/test.jpg".to_string(),
+            width: 100,
+            height: 100,
+        },
+        movie_stars: String::from(""),
+        source: MovieSource::Imdb,
+    }
+}
+
+fn test_group(members: &[&str]) -> GroupData {
+    let mut group_data = GroupData::new_empty();
+    for member in members {
+        group_data
+            .members
+            .insert(member.to_string(), GroupUserData::default());
+    }
+    group_data
+}
+
+#[test]
+// Play a full round between two members and check it ends up in the history.
+fn full_round_is_recorded() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("marion", "tt3")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    group_data
+        .apply_action("indiana", SystemAction::SetReady, 20)
+        .unwrap();
+    assert!(group_data.system_state == SystemState::AddingMovies);
+    group_data
+        .apply_action("marion", SystemAction::SetReady, 30)
+        .unwrap();
+    assert!(group_data.system_state == SystemState::SystemStarted);
+
+    let first = group_data.turn.clone();
+    let second = group_data.system_order.front().unwrap().clone();
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    assert_eq!(
+        group_data.apply_action(&second, SystemAction::Veto(tt1.clone()), 40),
+        Err(SystemError::NotYourTurn)
+    );
+    group_data
+        .apply_action(&first, SystemAction::Veto(tt1), 40)
+        .unwrap();
+    let tt2 = group_data.find_movie("tt2").unwrap();
+    group_data
+        .apply_action(&second, SystemAction::Veto(tt2), 50)
+        .unwrap();
+    assert!(group_data.system_state == SystemState::Finished);
+
+    let (history, _) = group_data.finish_round("round".to_string(), 60).unwrap();
+    assert_eq!(history.winner.movie_id, "tt3");
+    assert_eq!(history.added_by, "marion");
+    assert_eq!(history.candidates.len(), 3);
+    assert_eq!(history.date_started, 30);
+    // When the last veto went in, not when it was settled.
+    assert_eq!(history.date_finished, 50);
+    assert_eq!(history.vetoes.len(), 2);
+    assert_eq!(history.vetoes[0].username, first);
+    assert!(group_data.movies_watched.contains("tt3"));
+    assert!(group_data.current_movies.is_empty());
+    assert!(group_data.system_state == SystemState::AddingMovies);
+    assert!(group_data.members.values().all(|user| !user.ready_status));
+}
+
+#[test]
+// Members can only remove their own movies, and nothing changes once they're ready.
+fn delete_movie_rules() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data
+        .apply_action("indiana", SystemAction::AddMovie(test_movie("tt1")), 10)
+        .unwrap();
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    assert_eq!(
+        group_data.apply_action("marion", SystemAction::DeleteMovie(tt1.clone()), 20),
+        Err(SystemError::NotYourMovie)
+    );
+    assert_eq!(
+        group_data.apply_action("belloq", SystemAction::DeleteMovie(tt1.clone()), 20),
+        Err(SystemError::NotInGroup)
+    );
+    group_data
+        .apply_action("indiana", SystemAction::SetReady, 20)
+        .unwrap();
+    assert_eq!(
+        group_data.apply_action("indiana", SystemAction::DeleteMovie(tt1), 30),
+        Err(SystemError::AlreadyReady)
+    );
+}
+
+#[test]
+// The group built from the event log should match the one the actions were applied to.
+fn replay_matches_live_state() {
+    let mut events = vec![
+        GroupEvent {
+            actor: "indiana".to_string(),
+            date: 1,
+            kind: EventKind::GroupCreated {
+                group_name: "raiders".to_string(),
+            },
+        },
+        GroupEvent {
+            actor: "indiana".to_string(),
+            date: 2,
+            kind: EventKind::MemberAdded {
+                username: "marion".to_string(),
+            },
+        },
+    ];
+    let mut group_data = GroupData::replay(&events);
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("marion", "tt3")] {
+        events.extend(
+            group_data
+                .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+                .unwrap(),
+        );
+    }
+    for member in ["indiana", "marion"] {
+        events.extend(
+            group_data
+                .apply_action(member, SystemAction::SetReady, 20)
+                .unwrap(),
+        );
+    }
+    assert_eq!(GroupData::replay(&events), group_data);
+
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    let first = group_data.turn.clone();
+    let veto_events = group_data
+        .apply_action(&first, SystemAction::Veto(tt1), 30)
+        .unwrap();
+    assert!(matches!(veto_events[0].kind, EventKind::VetoCast(_)));
+    assert!(matches!(veto_events[1].kind, EventKind::TurnPassed { .. }));
+    events.extend(veto_events);
+    let tt2 = group_data.find_movie("tt2").unwrap();
+    let second = group_data.turn.clone();
+    events.extend(
+        group_data
+            .apply_action(&second, SystemAction::Veto(tt2), 40)
+            .unwrap(),
+    );
+    let (_, finished) = group_data.finish_round("round".to_string(), 50).unwrap();
+    events.push(finished);
+
+    let replayed = GroupData::replay(&events);
+    assert_eq!(replayed, group_data);
+    assert_eq!(replayed.group_name, "raiders");
+    assert!(replayed.movies_watched.contains("tt3"));
+}
+
+#[test]
+// Undoing a veto puts the movie and the turn back, including for the veto that ended the round.
+fn undo_veto() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("marion", "tt3")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    let before_veto = group_data.clone();
+    let first = group_data.turn.clone();
+    let second = group_data.system_order.front().unwrap().clone();
+    assert_eq!(
+        group_data.undo_veto(&first, 25, 10),
+        Err(SystemError::NothingToUndo)
+    );
+
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    group_data
+        .apply_action(&first, SystemAction::Veto(tt1), 30)
+        .unwrap();
+    assert_eq!(
+        group_data.undo_veto(&second, 35, 10),
+        Err(SystemError::NotYourVeto)
+    );
+    assert_eq!(
+        group_data.clone().undo_veto(&first, 41, 10),
+        Err(SystemError::UndoExpired)
+    );
+    group_data.undo_veto(&first, 35, 10).unwrap();
+    assert_eq!(group_data.current_movies, before_veto.current_movies);
+    assert_eq!(group_data.system_order, before_veto.system_order);
+    assert_eq!(group_data.turn, first);
+    assert!(group_data.round.vetoes.is_empty());
+
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    group_data
+        .apply_action(&first, SystemAction::Veto(tt1), 40)
+        .unwrap();
+    let tt2 = group_data.find_movie("tt2").unwrap();
+    group_data
+        .apply_action(&second, SystemAction::Veto(tt2), 50)
+        .unwrap();
+    assert!(group_data.system_state == SystemState::Finished);
+    assert!(group_data.can_undo(55, 10));
+    assert_eq!(group_data.undo_deadline(10), Some(60));
+    assert_eq!(
+        group_data
+            .clone()
+            .into_overview("group", &[], 10)
+            .undo_deadline,
+        Some(60)
+    );
+    group_data.undo_veto(&second, 55, 10).unwrap();
+    assert!(group_data.system_state == SystemState::SystemStarted);
+    assert_eq!(group_data.turn, second);
+    assert_eq!(group_data.current_movies.len(), 2);
+}
+
+#[test]
+// The server draws the winner from the seed, and the draw can be checked afterwards.
+fn random_draw() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data.recent_winners.push_back("marion".to_string());
+    group_data
+        .apply_action(
+            "indiana",
+            SystemAction::SetMode(SelectionMode::RandomDraw { weighted: true }),
+            5,
+        )
+        .unwrap();
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("marion", "tt3")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    assert!(group_data.system_state == SystemState::SystemStarted);
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    assert_eq!(
+        group_data.apply_action("indiana", SystemAction::Veto(tt1), 25),
+        Err(SystemError::WrongMode)
+    );
+
+    let events = group_data.resolve_round(42, 30);
+    assert_eq!(events.len(), 1);
+    assert!(group_data.system_state == SystemState::Finished);
+    assert_eq!(group_data.current_movies.len(), 1);
+
+    let draw = group_data.round.draw.clone().unwrap();
+    assert_eq!(draw.seed, 42);
+    let weights = draw
+        .weights
+        .iter()
+        .map(|entry| entry.weight)
+        .collect::<Vec<_>>();
+    assert_eq!(weights, vec![6, 1, 1]);
+    let winner = draw_winner(&draw.weights, draw.seed).unwrap();
+    assert_eq!(draw.weights[winner].movie_id, draw.winner.movie_id);
+
+    let (history, _) = group_data.finish_round("round".to_string(), 40).unwrap();
+    assert_eq!(history.draw, Some(draw));
+    assert_eq!(group_data.recent_winners.front(), Some(&history.added_by));
+}
+
+#[test]
+// The count only happens once every participant has sent a full ranking.
+fn ranked_choice_round() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data
+        .apply_action(
+            "indiana",
+            SystemAction::SetMode(SelectionMode::RankedChoice),
+            5,
+        )
+        .unwrap();
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    let ranking = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        group_data.apply_action(
+            "indiana",
+            SystemAction::SubmitRanking(ranking(&["tt1"])),
+            30
+        ),
+        Err(SystemError::InvalidBallot)
+    );
+    group_data
+        .apply_action(
+            "indiana",
+            SystemAction::SubmitRanking(ranking(&["tt1", "tt2"])),
+            30,
+        )
+        .unwrap();
+    assert!(group_data.resolve_round(1, 35).is_empty());
+    group_data
+        .apply_action(
+            "marion",
+            SystemAction::SubmitRanking(ranking(&["tt1", "tt2"])),
+            40,
+        )
+        .unwrap();
+    group_data.resolve_round(1, 45);
+    assert!(group_data.system_state == SystemState::Finished);
+    let (history, _) = group_data.finish_round("round".to_string(), 50).unwrap();
+    assert_eq!(history.winner.movie_id, "tt1");
+    assert_eq!(history.runoff.unwrap().rounds.len(), 1);
+}
+
+#[test]
+// Ready means the ballot is in during score voting, and the round ends when everyone is ready.
+fn score_round_uses_ready_status() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data
+        .apply_action(
+            "marion",
+            SystemAction::SetMode(SelectionMode::Score { approval: true }),
+            5,
+        )
+        .unwrap();
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    assert!(group_data.members.values().all(|user| !user.ready_status));
+
+    let ballot = |tt1: u8, tt2: u8| {
+        [("tt1".to_string(), tt1), ("tt2".to_string(), tt2)]
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    };
+    assert_eq!(
+        group_data.apply_action("indiana", SystemAction::SubmitScores(ballot(1, 2)), 30),
+        Err(SystemError::InvalidBallot)
+    );
+    group_data
+        .apply_action("indiana", SystemAction::SubmitScores(ballot(1, 1)), 30)
+        .unwrap();
+    assert!(group_data.members["indiana"].ready_status);
+    assert!(group_data.resolve_round(1, 35).is_empty());
+    group_data
+        .apply_action("marion", SystemAction::SubmitScores(ballot(0, 1)), 40)
+        .unwrap();
+    group_data.resolve_round(1, 45);
+    let (history, _) = group_data.finish_round("round".to_string(), 50).unwrap();
+    assert_eq!(history.winner.movie_id, "tt2");
+    assert_eq!(history.score.unwrap().ballots.len(), 2);
+}
+
+/// A started bracket round with three movies, seeded with 3.
+fn bracket_group() -> GroupData {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data
+        .apply_action("marion", SystemAction::SetMode(SelectionMode::Bracket), 5)
+        .unwrap();
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("marion", "tt3")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    group_data.resolve_round(3, 20);
+    group_data
+}
+
+#[test]
+// Everyone votes on each match-up and the champion ends the round.
+fn bracket_round() {
+    let mut group_data = bracket_group();
+
+    let mut now = 30;
+    while group_data.system_state == SystemState::SystemStarted {
+        let round = group_data
+            .round
+            .bracket
+            .as_ref()
+            .unwrap()
+            .current_round()
+            .unwrap()
+            .clone();
+        for (matchup, current) in round.iter().enumerate() {
+            if current.winner.is_some() {
+                continue;
+            }
+            // Both always vote for the movie with the lower id.
+            let movie_id = std::cmp::min(current.first.clone(), current.second.clone().unwrap());
+            for member in ["indiana", "marion"] {
+                group_data
+                    .apply_action(
+                        member,
+                        SystemAction::VoteMatchup {
+                            matchup,
+                            movie_id: movie_id.clone(),
+                        },
+                        now,
+                    )
+                    .unwrap();
+            }
+        }
+        group_data.resolve_round(3, now);
+        now += 10;
+    }
+    let (history, _) = group_data.finish_round("round".to_string(), now).unwrap();
+    assert_eq!(history.winner.movie_id, "tt1");
+    assert_eq!(history.bracket.unwrap().rounds.len(), 2);
+}
+
+#[test]
+// Split votes are drawn with the bracket's seed, so the seeds later requests bring don't
+// change the result and the bracket in the history is enough to redo it.
+fn bracket_ties_use_the_bracket_seed() {
+    let play = |request_seed: u64| {
+        let mut group_data = bracket_group();
+        let mut now = 30;
+        while group_data.system_state == SystemState::SystemStarted {
+            let round = group_data
+                .round
+                .bracket
+                .as_ref()
+                .unwrap()
+                .current_round()
+                .unwrap()
+                .clone();
+            for (matchup, current) in round.iter().enumerate() {
+                if current.winner.is_some() {
+                    continue;
+                }
+                let votes = [
+                    ("indiana", current.first.clone()),
+                    ("marion", current.second.clone().unwrap()),
+                ];
+                for (member, movie_id) in votes {
+                    group_data
+                        .apply_action(member, SystemAction::VoteMatchup { matchup, movie_id }, now)
+                        .unwrap();
+                }
+            }
+            group_data.resolve_round(request_seed + now as u64, now);
+            now += 10;
+        }
+        group_data.finish_round("round".to_string(), now).unwrap().0
+    };
+    let history = play(100);
+    let replayed = play(200);
+    assert_eq!(history.winner, replayed.winner);
+    assert_eq!(history.bracket, replayed.bracket);
+    assert_eq!(history.bracket.unwrap().seed, 3);
+}
+
+#[test]
+// Nobody sees anyone else's secret veto, and they all go at once.
+fn secret_veto_round() {
+    let mut group_data = test_group(&["indiana", "marion", "sallah"]);
+    group_data
+        .apply_action(
+            "sallah",
+            SystemAction::SetMode(SelectionMode::SecretVeto),
+            5,
+        )
+        .unwrap();
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("sallah", "tt3")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    for member in ["indiana", "marion", "sallah"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    let tt1 = group_data.find_movie("tt1").unwrap();
+    let tt2 = group_data.find_movie("tt2").unwrap();
+    let mut cast = group_data
+        .apply_action("indiana", SystemAction::SecretVeto(tt2), 30)
+        .unwrap();
+    cast[0].hide_secret();
+    assert_eq!(cast[0].kind, EventKind::SecretVetoCast { movie: None });
+    group_data
+        .apply_action("marion", SystemAction::SecretVeto(tt1.clone()), 30)
+        .unwrap();
+    assert!(group_data.resolve_round(1, 35).is_empty());
+
+    let mut seen_by_marion = group_data.clone();
+    seen_by_marion.hide_secret_vetoes("marion");
+    assert_eq!(seen_by_marion.round.secret_vetoes["marion"], "tt1");
+    assert_eq!(seen_by_marion.round.secret_vetoes["indiana"], "");
+
+    group_data
+        .apply_action("sallah", SystemAction::SecretVeto(tt1), 40)
+        .unwrap();
+    group_data.resolve_round(1, 45);
+    assert!(group_data.system_state == SystemState::Finished);
+    assert_eq!(group_data.round.vetoes.len(), 3);
+    assert!(group_data.find_movie("tt3").is_some());
+    assert_eq!(
+        group_data.undo_veto("sallah", 50, 10),
+        Err(SystemError::WrongMode)
+    );
+}
+
+#[test]
+// Quotas are checked when adding and readying, and a turn lasts as many vetoes as the rules say.
+fn group_rules() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    let rules = GroupRules {
+        min_movies_per_member: 1,
+        max_movies_per_member: Some(2),
+        veto_own_movie: false,
+        vetoes_per_turn: 2,
+        start_before_all_added: false,
+        ..GroupRules::default()
+    };
+    assert_eq!(
+        group_data.apply_action(
+            "indiana",
+            SystemAction::SetRules(GroupRules {
+                max_movies_per_member: Some(0),
+                ..rules.clone()
+            }),
+            5
+        ),
+        Err(SystemError::InvalidRules)
+    );
+    group_data
+        .apply_action("indiana", SystemAction::SetRules(rules.clone()), 5)
+        .unwrap();
+    assert_eq!(group_data.rules, rules);
+    assert_eq!(
+        group_data.apply_action("indiana", SystemAction::SetReady, 10),
+        Err(SystemError::NotEnoughMovies)
+    );
+    for (member, movie_id) in [
+        ("indiana", "tt1"),
+        ("indiana", "tt2"),
+        ("marion", "tt3"),
+        ("marion", "tt4"),
+    ] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    assert_eq!(
+        group_data.apply_action("indiana", SystemAction::AddMovie(test_movie("tt5")), 10),
+        Err(SystemError::TooManyMovies)
+    );
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    let first = group_data.turn.clone();
+    let own_movie = group_data
+        .current_movies
+        .iter()
+        .find(|movie| movie.added_by == first)
+        .unwrap()
+        .clone();
+    let others = group_data
+        .current_movies
+        .iter()
+        .filter(|movie| movie.added_by != first)
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        group_data.apply_action(&first, SystemAction::Veto(own_movie), 30),
+        Err(SystemError::OwnMovie)
+    );
+    group_data
+        .apply_action(&first, SystemAction::Veto(others[0].clone()), 30)
+        .unwrap();
+    assert_eq!(group_data.turn, first);
+    group_data.clone().undo_veto(&first, 35, 10).unwrap();
+    assert_eq!(group_data.turn, first);
+    group_data
+        .apply_action(&first, SystemAction::Veto(others[1].clone()), 40)
+        .unwrap();
+    assert_ne!(group_data.turn, first);
+    let mut undone = group_data.clone();
+    undone.undo_veto(&first, 45, 10).unwrap();
+    assert_eq!(undone.turn, first);
+    assert_eq!(undone.current_movies.len(), 3);
+
+    // Rules stored with no vetoes per turn count as one.
+    let mut zero = undone.clone();
+    zero.rules.vetoes_per_turn = 0;
+    zero.apply_action(&first, SystemAction::Veto(others[1].clone()), 46)
+        .unwrap();
+    assert_ne!(zero.turn, first);
+    zero.undo_veto(&first, 47, 10).unwrap();
+    assert_eq!(zero.turn, first);
+
+    // Only the first member's movies are left, so they're fair game now.
+    let second = group_data.turn.clone();
+    let last_own = group_data.current_movies.iter().next().unwrap().clone();
+    group_data
+        .apply_action(&second, SystemAction::Veto(last_own), 50)
+        .unwrap();
+    assert!(group_data.system_state == SystemState::Finished);
+}
+
+#[test]
+// A movie the group has seen needs the rewatch override, and is marked when added with it.
+fn rewatch_override() {
+    let mut group_data = test_group(&["indiana"]);
+    group_data.movies_watched.insert("tt1".to_string());
+    assert_eq!(
+        group_data.apply_action("indiana", SystemAction::AddMovie(test_movie("tt1")), 10),
+        Err(SystemError::AlreadyWatched)
+    );
+    group_data
+        .apply_action("indiana", SystemAction::AddRewatch(test_movie("tt1")), 10)
+        .unwrap();
+    group_data
+        .apply_action("indiana", SystemAction::AddRewatch(test_movie("tt2")), 10)
+        .unwrap();
+    assert!(group_data.find_movie("tt1").unwrap().rewatch);
+    assert!(!group_data.find_movie("tt2").unwrap().rewatch);
+}
+
+#[test]
+// The same id from two sources is two movies, and the same movie is one however its id was sent.
+fn movies_are_told_apart_by_source() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    let tmdb_movie = |movie_id: &str| MovieDisplay {
+        source: MovieSource::Tmdb,
+        ..test_movie(movie_id)
+    };
+    group_data.movies_watched.insert("348".to_string());
+    group_data
+        .apply_action("indiana", SystemAction::AddMovie(tmdb_movie("348")), 10)
+        .unwrap();
+    let added = group_data.find_movie("tmdb:348").unwrap();
+    assert!(!added.rewatch);
+    assert_eq!(
+        group_data.apply_action("marion", SystemAction::AddMovie(tmdb_movie("tmdb:348")), 10),
+        Err(SystemError::AlreadyAdded)
+    );
+    group_data
+        .apply_action("marion", SystemAction::AddRewatch(test_movie("348")), 10)
+        .unwrap();
+    assert!(group_data.find_movie("348").unwrap().rewatch);
+
+    group_data
+        .apply_action("indiana", SystemAction::DeleteMovie(added), 20)
+        .unwrap();
+    assert!(group_data.find_movie("tmdb:348").is_none());
+    assert!(group_data.find_movie("348").is_some());
+
+    group_data.movies_watched.insert("tmdb:348".to_string());
+    assert_eq!(
+        group_data.apply_backlog_action("indiana", BacklogAction::Add(tmdb_movie("348")), 0, 30),
+        Err(SystemError::AlreadyWatched)
+    );
+}
+
+#[test]
+// A late adding phase starts the round, and a late turn vetoes someone else's movie.
+fn deadlines() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data.rules = GroupRules {
+        adding_deadline_secs: Some(60),
+        turn_deadline_secs: Some(30),
+        on_turn_timeout: TimeoutAction::AutoVeto,
+        ..GroupRules::default()
+    };
+    assert_eq!(group_data.deadline(), None);
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2"), ("marion", "tt3")] {
+        group_data
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+    }
+    group_data
+        .apply_action("indiana", SystemAction::SetReady, 20)
+        .unwrap();
+    assert_eq!(group_data.deadline(), Some(70));
+    assert!(group_data.enforce_deadline(1, 69).is_empty());
+
+    let events = group_data.enforce_deadline(1, 70);
+    assert_eq!(
+        events[0].kind,
+        EventKind::DeadlinePassed {
+            missed_by: vec!["marion".to_string()]
+        }
+    );
+    assert!(group_data.system_state == SystemState::SystemStarted);
+    assert_eq!(group_data.deadline(), Some(100));
+
+    let late = group_data.turn.clone();
+    group_data.enforce_deadline(7, 100);
+    let veto = group_data.round.vetoes.last().unwrap();
+    assert!(veto.is_auto_veto());
+    assert_ne!(veto.movie.added_by, late);
+    assert_ne!(group_data.turn, late);
+
+    let mut skipping = test_group(&["indiana", "marion"]);
+    skipping.rules.turn_deadline_secs = Some(30);
+    for (member, movie_id) in [("indiana", "tt1"), ("marion", "tt2")] {
+        skipping
+            .apply_action(member, SystemAction::AddMovie(test_movie(movie_id)), 10)
+            .unwrap();
+        skipping
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    let late = skipping.turn.clone();
+    skipping.enforce_deadline(7, 50);
+    assert!(skipping.round.vetoes.is_empty());
+    assert_ne!(skipping.turn, late);
+    assert_eq!(skipping.deadline(), Some(80));
+}
+
+#[test]
+// Nobody gets a turn back once a deadline has moved it on: the late member can't undo the
+// server's veto, and a turn skipped halfway can't be undone either.
+fn timed_out_turns_cant_be_undone() {
+    let members = ["indiana", "marion", "sallah"];
+    let mut group_data = test_group(&members);
+    group_data.rules = GroupRules {
+        vetoes_per_turn: 2,
+        turn_deadline_secs: Some(30),
+        on_turn_timeout: TimeoutAction::AutoVeto,
+        ..GroupRules::default()
+    };
+    for (index, member) in members.iter().enumerate() {
+        for movie_id in [index * 2 + 1, index * 2 + 2] {
+            let movie = test_movie(&format!("tt{}", movie_id));
+            group_data
+                .apply_action(member, SystemAction::AddMovie(movie), 10)
+                .unwrap();
+        }
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    let late = group_data.turn.clone();
+    let next = group_data.system_order.front().unwrap().clone();
+    let last = members
+        .iter()
+        .find(|member| **member != late && **member != next)
+        .unwrap()
+        .to_string();
+
+    group_data.enforce_deadline(7, 50);
+    assert!(group_data.round.vetoes[0].is_auto_veto());
+    assert!(!group_data.can_undo(55, 10));
+    assert_eq!(group_data.undo_deadline(10), None);
+    assert_eq!(
+        group_data.undo_veto(&late, 55, 10),
+        Err(SystemError::NotYourVeto)
+    );
+    assert_eq!(group_data.turn, next);
+    for now in [56, 57] {
+        let movie = group_data.current_movies.iter().next().unwrap().clone();
+        group_data
+            .apply_action(&next, SystemAction::Veto(movie), now)
+            .unwrap();
+    }
+    assert_eq!(group_data.turn, last);
+
+    group_data.rules.on_turn_timeout = TimeoutAction::Skip;
+    let movie = group_data.current_movies.iter().next().unwrap().clone();
+    group_data
+        .apply_action(&last, SystemAction::Veto(movie), 60)
+        .unwrap();
+    group_data.enforce_deadline(7, 87);
+    assert_eq!(group_data.turn, late);
+    assert_eq!(
+        group_data.undo_veto(&last, 88, 30),
+        Err(SystemError::UndoExpired)
+    );
+    assert_eq!(group_data.current_movies.len(), 2);
+}
+
+#[test]
+// A shuffled order waits for the server's seed, which is kept with the round.
+fn shuffled_turn_order() {
+    let mut group_data = test_group(&["indiana", "marion", "sallah"]);
+    group_data.owner = "indiana".to_string();
+    let fixed = GroupRules {
+        turn_order: TurnOrder::Fixed(vec!["sallah".to_string()]),
+        ..GroupRules::default()
+    };
+    assert_eq!(
+        group_data.apply_action("marion", SystemAction::SetRules(fixed.clone()), 5),
+        Err(SystemError::NotOwner)
+    );
+    // Nobody can fix the order in a group without an owner.
+    let mut ownerless = test_group(&["indiana"]);
+    assert_eq!(
+        ownerless.apply_action("indiana", SystemAction::SetRules(fixed), 5),
+        Err(SystemError::NotOwner)
+    );
+    let shuffled = GroupRules {
+        turn_order: TurnOrder::Shuffled,
+        ..GroupRules::default()
+    };
+    group_data
+        .apply_action("marion", SystemAction::SetRules(shuffled), 5)
+        .unwrap();
+    group_data
+        .apply_action("indiana", SystemAction::AddMovie(test_movie("tt1")), 10)
+        .unwrap();
+    group_data
+        .apply_action("indiana", SystemAction::AddMovie(test_movie("tt2")), 10)
+        .unwrap();
+    for member in ["indiana", "marion", "sallah"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 20)
+            .unwrap();
+    }
+    assert!(group_data.system_state == SystemState::AddingMovies);
+
+    let mut again = group_data.clone();
+    let events = group_data.resolve_round(42, 20);
+    assert!(matches!(
+        events[0].kind,
+        EventKind::RoundStarted { seed: Some(42), .. }
+    ));
+    assert!(group_data.system_state == SystemState::SystemStarted);
+    assert_eq!(group_data.round.order_seed, Some(42));
+    again.resolve_round(42, 20);
+    assert_eq!(again.round.participants, group_data.round.participants);
+    assert_eq!(group_data.turn, group_data.round.participants[0]);
+}
+
+#[test]
+// Candidates added to a planned round carry over once the active round finishes,
+// apart from any that have been watched since.
+fn planned_round_becomes_active() {
+    let mut group_data = test_group(&["indiana"]);
+    assert!(group_data.open_round("first".to_string(), 1).is_some());
+    assert!(group_data.open_round("other".to_string(), 1).is_none());
+    group_data
+        .plan_round("indiana", "second".to_string(), 2)
+        .unwrap();
+    group_data
+        .apply_round_action(
+            "first",
+            "indiana",
+            SystemAction::AddMovie(test_movie("tt1")),
+            3,
+        )
+        .unwrap();
+    for movie_id in ["tt1", "tt2"] {
+        group_data
+            .apply_round_action(
+                "second",
+                "indiana",
+                SystemAction::AddRewatch(test_movie(movie_id)),
+                4,
+            )
+            .unwrap();
+    }
+    assert_eq!(
+        group_data.apply_round_action("second", "indiana", SystemAction::SetReady, 5),
+        Err(SystemError::WrongState)
+    );
+    assert_eq!(
+        group_data.apply_round_action("third", "indiana", SystemAction::SetReady, 5),
+        Err(SystemError::RoundNotFound)
+    );
+    group_data
+        .apply_round_action("first", "indiana", SystemAction::SetReady, 5)
+        .unwrap();
+    let (history, _) = group_data
+        .finish_round(group_data.round_id.clone(), 6)
+        .unwrap();
+    assert_eq!(history.id, "first");
+    assert_eq!(group_data.round_id, "second");
+    assert!(group_data.planned_rounds.is_empty());
+    // tt1 just won, so it's gone from the planned round.
+    assert!(group_data.find_movie("tt1").is_none());
+    assert_eq!(group_data.active_round_info().candidates.len(), 1);
+}
+
+#[test]
+// Losing candidates land on the backlog and can fill the next round, oldest first.
+fn losers_fill_next_round_from_backlog() {
+    let mut group_data = test_group(&["indiana"]);
+    group_data.open_round("first".to_string(), 1);
+    group_data
+        .apply_backlog_action("indiana", BacklogAction::Add(test_movie("tt4")), 0, 2)
+        .unwrap();
+    assert_eq!(
+        group_data.apply_backlog_action("indiana", BacklogAction::Add(test_movie("tt4")), 0, 2),
+        Err(SystemError::AlreadyAdded)
+    );
+    for movie_id in ["tt1", "tt2", "tt3"] {
+        group_data
+            .apply_action("indiana", SystemAction::AddMovie(test_movie(movie_id)), 3)
+            .unwrap();
+    }
+    group_data
+        .apply_action("indiana", SystemAction::SetReady, 4)
+        .unwrap();
+    for movie_id in ["tt2", "tt3"] {
+        let movie = group_data.find_movie(movie_id).unwrap();
+        group_data
+            .apply_action("indiana", SystemAction::Veto(movie), 5)
+            .unwrap();
+    }
+    group_data.finish_round("first".to_string(), 6).unwrap();
+    group_data.open_round("second".to_string(), 7);
+    let backlog = group_data
+        .backlog
+        .iter()
+        .map(|entry| entry.movie.movie_id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(backlog, vec!["tt4", "tt2", "tt3"]);
+    assert_eq!(group_data.backlog[1].from_round, Some("first".to_string()));
+
+    let fill = BacklogAction::Fill {
+        round_id: "second".to_string(),
+        count: 2,
+        pick: BacklogPick::Oldest,
+    };
+    group_data
+        .apply_backlog_action("indiana", fill, 0, 8)
+        .unwrap();
+    assert!(group_data.find_movie("tt4").is_some());
+    assert_eq!(group_data.current_movies.len(), 2);
+    assert_eq!(group_data.backlog.len(), 1);
+    assert_eq!(
+        group_data.apply_backlog_action("indiana", BacklogAction::Add(test_movie("tt1")), 0, 9),
+        Err(SystemError::AlreadyWatched)
+    );
+}
+
+#[test]
+// Pitches are for whoever added the movie, comments for anyone, and both go into the history.
+fn discussions_are_kept_with_history() {
+    let mut group_data = test_group(&["indiana", "marion"]);
+    group_data
+        .apply_action("indiana", SystemAction::AddMovie(test_movie("tt1")), 1)
+        .unwrap();
+    group_data
+        .apply_action("marion", SystemAction::AddMovie(test_movie("tt2")), 1)
+        .unwrap();
+    let pitch = |pitch: &str| SystemAction::SetPitch {
+        movie_id: "tt1".to_string(),
+        pitch: Some(pitch.to_string()),
+    };
+    let comment = |movie_id: &str, text: &str| SystemAction::Comment {
+        movie_id: movie_id.to_string(),
+        text: text.to_string(),
+    };
+    assert_eq!(
+        group_data.apply_action("marion", pitch("it has snakes"), 2),
+        Err(SystemError::NotYourMovie)
+    );
+    group_data
+        .apply_action("indiana", pitch("  it has snakes "), 2)
+        .unwrap();
+    group_data
+        .apply_action("marion", comment("tt1", "why did it have to be snakes"), 3)
+        .unwrap();
+    assert_eq!(
+        group_data.apply_action("marion", comment("tt1", "   "), 3),
+        Err(SystemError::InvalidComment)
+    );
+    assert_eq!(
+        group_data.apply_action("marion", comment("tt1", &"a".repeat(501)), 3),
+        Err(SystemError::InvalidComment)
+    );
+    assert_eq!(
+        group_data.apply_action("marion", comment("tt9", "which one?"), 3),
+        Err(SystemError::MovieNotFound)
+    );
+    group_data
+        .apply_action("marion", comment("tt2", "gone soon"), 3)
+        .unwrap();
+    let tt2 = group_data.find_movie("tt2").unwrap();
+    group_data
+        .apply_action("marion", SystemAction::DeleteMovie(tt2), 4)
+        .unwrap();
+    assert!(!group_data.discussions.contains_key("tt2"));
+
+    for member in ["indiana", "marion"] {
+        group_data
+            .apply_action(member, SystemAction::SetReady, 5)
+            .unwrap();
+    }
+    assert_eq!(group_data.system_state, SystemState::Finished);
+    assert_eq!(
+        group_data.apply_action("indiana", pitch("too late"), 6),
+        Err(SystemError::WrongState)
+    );
+    let (history, _) = group_data.finish_round("round".to_string(), 7).unwrap();
+    let discussion = &history.discussions["tt1"];
+    assert_eq!(discussion.pitch, Some("it has snakes".to_string()));
+    assert_eq!(discussion.comments.len(), 1);
+    assert_eq!(discussion.comments[0].username, "marion");
+    assert_eq!(discussion.comments[0].date, 3);
+    assert!(group_data.discussions.is_empty());
+
+    // Each member's export only has what they wrote.
+    let export = |username: &str| {
+        export_discussions(
+            &history.id,
+            &history.discussions,
+            &history.candidates,
+            username,
+        )
+    };
+    let (comments, pitches) = export("marion");
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].text, "why did it have to be snakes");
+    assert!(pitches.is_empty());
+    let (comments, pitches) = export("indiana");
+    assert!(comments.is_empty());
+    assert_eq!(pitches[0].pitch, "it has snakes");
+    assert_eq!(pitches[0].round_id, "round");
+}
@@ -0,0 +1,79 @@
+use shared_stuff::db_structs::UserData;
+use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::notification_structs::EmailPrefs;
+use shared_stuff::provider_structs::MovieSource;
+use shared_stuff::shared_structs::MovieDisplay;
+use shared_stuff::watchlist_structs::{Priority, WatchlistAction, WatchlistError};
+use std::collections::HashSet;
+
+fn test_movie(movie_id: &str) -> MovieDisplay {
+    MovieDisplay {
+        movie_id: movie_id.to_string(),
+        movie_title: format!("title {}", movie_id),
+        movie_year: 1999,
+        movie_images: ImageData {
+            url: "https://m.media-amazon.com/images/test.jpg".to_string(),
+            width: 100,
+            height: 100,
+        },
+        movie_stars: String::from(""),
+        source: MovieSource::Imdb,
+    }
+}
+
+#[test]
+// Entries stay sorted by priority, then by when they were added.
+fn watchlist_order_and_errors() {
+    let mut user_data = UserData {
+        id: "id".to_string(),
+        hashed_password: String::from(""),
+        salt: String::from(""),
+        groups: HashSet::new(),
+        date_created: 0,
+        date_modified: 0,
+        login_history: Vec::new(),
+        watchlist: Vec::new(),
+        email_prefs: EmailPrefs::default(),
+    };
+    for (movie_id, now) in [("tt1", 10), ("tt2", 20), ("tt3", 30)] {
+        user_data
+            .apply_watchlist_action(WatchlistAction::Add(test_movie(movie_id)), now)
+            .unwrap();
+    }
+    assert_eq!(
+        user_data.apply_watchlist_action(WatchlistAction::Add(test_movie("tt1")), 40),
+        Err(WatchlistError::AlreadyListed)
+    );
+    user_data
+        .apply_watchlist_action(
+            WatchlistAction::SetPriority {
+                movie_id: "tt3".to_string(),
+                priority: Priority::High,
+            },
+            40,
+        )
+        .unwrap();
+    user_data
+        .apply_watchlist_action(
+            WatchlistAction::SetNote {
+                movie_id: "tt2".to_string(),
+                note: "for a rainy day".to_string(),
+            },
+            40,
+        )
+        .unwrap();
+    user_data
+        .apply_watchlist_action(WatchlistAction::Remove("tt1".to_string()), 50)
+        .unwrap();
+    let order = user_data
+        .watchlist
+        .iter()
+        .map(|entry| entry.movie.movie_id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(order, ["tt3", "tt2"]);
+    assert_eq!(user_data.watchlist[1].note, "for a rainy day");
+    assert_eq!(
+        user_data.apply_watchlist_action(WatchlistAction::Remove("tt1".to_string()), 60),
+        Err(WatchlistError::NotListed)
+    );
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use shared_stuff::provider_structs::{
+    MovieDetails, MovieProvider, MovieSource, ProviderError, SearchResults,
+};
+use shared_stuff::tmdb_structs::{TmdbDetails, TmdbSearch};
+
+pub const TMDB_URL: &str = "https://api.themoviedb.org/3";
+
+/// The Movie Database. Its own ids are numbers, but `movie/{id}` takes IMDb ids too.
+#[derive(Debug, Clone)]
+pub struct TmdbProvider {
+    api_key: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl TmdbProvider {
+    pub fn new(api_key: String, base_url: String) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        TmdbProvider {
+            api_key,
+            base_url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn get(&self, path: &str, params: &[(&str, &str)]) -> Result<reqwest::Response> {
+        let url = format!("{}/{}", self.base_url, path);
+        log::info!("query is: {:?}", &url);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("api_key", self.api_key.as_str())])
+            .query(params)
+            .send()
+            .await?;
+        Ok(response)
+    }
+}
+
+fn upstream(e: impl ToString) -> ProviderError {
+    ProviderError::Upstream(e.to_string())
+}
+
+#[async_trait]
+impl MovieProvider for TmdbProvider {
+    fn source(&self) -> MovieSource {
+        MovieSource::Tmdb
+    }
+
+    async fn search(&self, query: &str) -> Result<SearchResults, ProviderError> {
+        let response = self
+            .get("search/movie", &[("query", query)])
+            .await
+            .map_err(upstream)?
+            .error_for_status()
+            .map_err(upstream)?;
+        let search: TmdbSearch = response.json().await.map_err(upstream)?;
+        log::info!("search is: {:?}", &search);
+        Ok(search.into_results())
+    }
+
+    async fn details(&self, movie_id: &str) -> Result<MovieDetails, ProviderError> {
+        let response = self
+            .get(
+                &format!("movie/{}", MovieSource::Tmdb.source_id(movie_id)),
+                &[("append_to_response", "credits")],
+            )
+            .await
+            .map_err(upstream)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ProviderError::NotFound);
+        }
+        let details: TmdbDetails = response
+            .error_for_status()
+            .map_err(upstream)?
+            .json()
+            .await
+            .map_err(upstream)?;
+        Ok(details.into_details())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared_stuff::provider_structs::MovieSource;
+    use shared_stuff::tmdb_structs::{TmdbDetails, TmdbSearch, TMDB_POSTER_URL};
+
+    #[test]
+    fn search_from_tmdb() {
+        let body = r#"{"page":1,"total_results":2,"results":[
+            {"id":348,"title":"Alien","release_date":"1979-05-25","poster_path":"/alien.jpg"},
+            {"id":1,"title":"Alien Someday","release_date":"","poster_path":null}]}"#;
+        let search: TmdbSearch = serde_json::from_str(body).unwrap();
+        let results = search.into_results();
+        assert!(!results.exhaustive);
+        assert_eq!(results.movies[0].movie_id, "tmdb:348");
+        assert_eq!(results.movies[0].movie_year, 1979);
+        assert_eq!(
+            results.movies[0].movie_images.url,
+            format!("{}/alien.jpg", TMDB_POSTER_URL)
+        );
+        assert_eq!(results.movies[1].movie_year, 0);
+        assert_eq!(results.movies[1].movie_images.url, "");
+        assert_eq!(results.movies[1].source, MovieSource::Tmdb);
+    }
+
+    #[test]
+    fn details_from_tmdb() {
+        let body = r#"{"id":348,"imdb_id":"tt0078748","title":"Alien",
+            "release_date":"1979-05-25","overview":"In space.","runtime":117,
+            "genres":[{"id":27,"name":"Horror"},{"id":878,"name":"Science Fiction"}],
+            "poster_path":"/alien.jpg","vote_average":8.1,
+            "credits":{"cast":[{"name":"Sigourney Weaver"},{"name":"Tom Skerritt"}],
+            "crew":[{"name":"Gordon Carroll","job":"Producer"},{"name":"Ridley Scott","job":"Director"}]}}"#;
+        let details: TmdbDetails = serde_json::from_str(body).unwrap();
+        let details = details.into_details();
+        assert_eq!(details.movie_id, "tmdb:348");
+        assert_eq!(details.runtime, "117 min");
+        assert_eq!(details.director, "Ridley Scott");
+        assert_eq!(details.genres, vec!["Horror", "Science Fiction"]);
+        assert_eq!(details.actors, vec!["Sigourney Weaver", "Tom Skerritt"]);
+        assert_eq!(details.imdb_id, Some("tt0078748".to_string()));
+        assert_eq!(details.rating, Some(8.1));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::new_db_stuff::{db_enforce_deadline, db_get_all_group_ids};
+use crate::DEADLINE_CHECK_SECS;
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+/// Runs for as long as the server does, checking every group's deadlines every
+/// `DEADLINE_CHECK_SECS` and settling rounds nobody has written to since they finished.
+/// Errors are logged and the group is tried again next time.
+pub async fn run_deadline_task(db: SqlitePool) {
+    let mut interval = tokio::time::interval(Duration::from_secs(*DEADLINE_CHECK_SECS));
+    loop {
+        interval.tick().await;
+        let group_ids = match db_get_all_group_ids(&db).await {
+            Ok(group_ids) => group_ids,
+            Err(e) => {
+                log::error!("deadline task couldn't list groups: {:?}", e);
+                continue;
+            }
+        };
+        for group_id in group_ids {
+            if let Err(e) = db_enforce_deadline(&db, &group_id).await {
+                log::error!("deadline task failed for group {}: {:?}", &group_id, e);
+            }
+        }
+    }
+}
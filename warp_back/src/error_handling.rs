@@ -19,12 +19,17 @@ pub enum WarpRejections {
     UuidError(String),
     EnvError(String),
     AutocompleteError(String),
+    MovieDetailsError(String),
     AuthError(String),
     SqlxError(String),
     GroupNotExist(String),
     UserNotInGroup(String),
     UserNotExist(String),
     UserNotAuthorized(String),
+    SystemActionError(String),
+    GroupWriteConflict(String),
+    WatchlistError(String),
+    ValidationError(String),
     Other(String),
 }
 
@@ -3,17 +3,26 @@ use crate::error_handling::WarpRejections;
 use dotenv::dotenv;
 use dotenv::var;
 use lazy_static::lazy_static;
+use providers::ProviderConfig;
+use search_cache::{SearchCache, SearchCacheConfig};
+use shared_stuff::provider_structs::MovieProvider;
 use sqlx::SqlitePool;
+use std::sync::Arc;
 
 use warp::cors::Cors;
 use warp::reject::custom;
 
 pub mod auth;
+pub mod deadlines;
 //pub mod db_stuff;
 pub mod error_handling;
 pub mod new_db_stuff;
+pub mod notifier;
+pub mod providers;
 pub mod routes;
+pub mod search_cache;
 pub mod test_stuff;
+pub mod webhooks;
 
 lazy_static! {
     static ref CORS_ORIGIN: String = {
@@ -38,19 +47,62 @@ lazy_static! {
             .parse::<i64>()
             .expect("parse error")
     };
+    pub static ref VETO_UNDO_SECS: i64 = {
+        dotenv().ok();
+        dotenv::var("VETO_UNDO_SECS")
+            .expect("env error")
+            .parse::<i64>()
+            .expect("parse error")
+    };
+    pub static ref DEADLINE_CHECK_SECS: u64 = {
+        dotenv().ok();
+        dotenv::var("DEADLINE_CHECK_SECS")
+            .expect("env error")
+            .parse::<u64>()
+            .expect("parse error")
+    };
 }
 
 #[derive(Clone)]
 pub struct State {
     pub db: SqlitePool,
     pub cors: Cors,
+    pub search_cache: Arc<SearchCache>,
+    /// Every provider from `MOVIE_PROVIDERS`, chained.
+    pub provider: Arc<dyn MovieProvider>,
+    /// The same, with OMDb first if there's a key for it.
+    pub details: Arc<dyn MovieProvider>,
 }
 
 impl State {
     pub async fn init() -> Result<Self> {
         let db = make_db_pool().await?;
         let cors = make_cors();
-        Ok(Self { db, cors })
+        let provider_config = ProviderConfig::from_env();
+        let provider = provider_config
+            .build()
+            .map_err(|e| custom(WarpRejections::EnvError(format!("{} {}", e, err_info!()))))?;
+        let details = provider_config
+            .build_details()
+            .map_err(|e| custom(WarpRejections::EnvError(format!("{} {}", e, err_info!()))))?;
+        log::info!("movie providers: {:?}", provider.sources());
+        log::info!("movie details from: {:?}", details.sources());
+        let search_cache = SearchCache::new(
+            SearchCacheConfig::from_env(),
+            Some(db.clone()),
+            &provider.sources(),
+        );
+        let loaded = search_cache
+            .load(sqlx::types::chrono::Utc::now().timestamp())
+            .await?;
+        log::info!("search cache loaded {} entries", loaded);
+        Ok(Self {
+            db,
+            cors,
+            search_cache: Arc::new(search_cache),
+            provider: Arc::new(provider),
+            details: Arc::new(details),
+        })
     }
 }
 
@@ -65,7 +117,7 @@ pub fn make_cors() -> Cors {
         ])
         .allow_origin(CORS_ORIGIN.as_str())
         .allow_credentials(true)
-        .expose_header("authorization")
+        .expose_headers(vec!["authorization", "content-disposition"])
         .build()
 }
 
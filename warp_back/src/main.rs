@@ -1,10 +1,18 @@
+#![recursion_limit = "256"]
+
 use warp::Filter;
 
 use warp_back::error_handling::handle_rejection;
 use warp_back::error_handling::Result;
 
+use warp_back::deadlines::run_deadline_task;
 use warp_back::routes::{
-    add_user_to_group, create_group, get_all_groups, get_group_data, leave_group, update_group_data, get_user_profile
+    add_user_to_group, add_webhook, backlog_action, create_group, delete_webhook, export_user_data,
+    get_all_groups, get_email_prefs, get_group_data, get_group_events, get_group_history,
+    get_group_ratings, get_group_stats, get_movie_details, get_notifications, get_user_profile,
+    get_watchlist, get_webhook_deliveries, get_webhooks, leave_group, mark_notifications_read,
+    ping_webhook, plan_round, rate_round, set_email_prefs, system_action, undo_veto,
+    watchlist_action,
 };
 use warp_back::routes::{authorize_access, authorize_refresh, login, register, search};
 use warp_back::State;
@@ -21,6 +29,7 @@ async fn main() -> Result<()> {
     log::info!("checking new compile times...");
 
     let state = State::init().await?;
+    tokio::spawn(run_deadline_task(state.db.clone()));
 
     let routes = search(&state)
         .or(register(&state))
@@ -32,8 +41,29 @@ async fn main() -> Result<()> {
         .or(get_all_groups(&state))
         .or(add_user_to_group(&state))
         .or(get_group_data(&state))
-        .or(update_group_data(&state))
+        .or(system_action(&state))
+        .or(plan_round(&state))
+        .or(undo_veto(&state))
+        .or(get_group_events(&state))
+        .or(get_group_history(&state))
+        .or(rate_round(&state))
+        .or(get_group_ratings(&state))
+        .or(get_group_stats(&state))
         .or(get_user_profile(&state))
+        .or(export_user_data(&state))
+        .or(get_watchlist(&state))
+        .or(watchlist_action(&state))
+        .or(backlog_action(&state))
+        .or(get_notifications(&state))
+        .or(mark_notifications_read(&state))
+        .or(get_email_prefs(&state))
+        .or(set_email_prefs(&state))
+        .or(get_webhooks(&state))
+        .or(add_webhook(&state))
+        .or(delete_webhook(&state))
+        .or(get_webhook_deliveries(&state))
+        .or(ping_webhook(&state))
+        .or(get_movie_details(&state))
         .recover(handle_rejection)
         .with(&state.cors);
 
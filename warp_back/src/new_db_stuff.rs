@@ -1,19 +1,49 @@
 use crate::auth::{hasher, verify_pass};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
 
 use crate::err_info;
 use crate::error_handling::{Result, WarpRejections};
+use crate::notifier::{queue_message, render_notification, NOTIFIER};
+use crate::webhooks::{queue_webhooks, resolve_url, WEBHOOK_ALLOW_PRIVATE};
+use crate::{CORS_ORIGIN, VETO_UNDO_SECS};
 use shared_stuff::auth_structs::UserInfo;
+use shared_stuff::backlog_structs::BacklogAction;
 use shared_stuff::db_structs::{DBGroup, DBGroupStruct, DBUser, DBUserStruct, GroupData, UserData};
-use shared_stuff::group_structs::{GroupForm, GroupInfo, GroupUserData};
+use shared_stuff::discussion_structs::export_discussions;
+use shared_stuff::event_structs::{DBEvent, EventKind, EventPage, GroupEvent};
+use shared_stuff::group_structs::{
+    GroupExport, GroupForm, GroupInfo, GroupRules, GroupUserData, RatingExport, UserExport,
+    UserProfile,
+};
+use shared_stuff::history_structs::{DBHistory, HistoryPage, Rating, RatingForm, RoundHistory};
+use shared_stuff::notification_structs::{
+    DBNotification, EmailPrefs, Inbox, MarkRead, Notification, NotificationKind,
+};
+use shared_stuff::selection_structs::SelectionMode;
 use shared_stuff::shared_structs::SystemState;
+use shared_stuff::system_structs::{RoundData, SystemAction};
+use shared_stuff::watchlist_structs::{WatchlistAction, WatchlistEntry};
+use shared_stuff::webhook_structs::{DBDelivery, DBWebhook, DeliveryRecord, Webhook, WebhookForm};
 use sqlx::pool::PoolConnection;
 use sqlx::types::uuid::Uuid;
 use sqlx::Sqlite;
 use sqlx::{query, query_as, SqlitePool};
 use std::collections::{HashMap, HashSet, VecDeque};
+use validator::Validate;
 //use uuid::Uuid;
 use warp::reject::custom;
 
+pub const HISTORY_PAGE_SIZE: i64 = 10;
+pub const EVENTS_PAGE_SIZE: i64 = 25;
+/// Newest deliveries shown for a webhook.
+pub const DELIVERIES_PAGE_SIZE: i64 = 50;
+/// How many notifications the inbox shows, the unread count covers all of them.
+pub const INBOX_SIZE: i64 = 30;
+/// How many times a group is read again when someone else keeps writing it first.
+/// Every attempt lets at least one writer through, so this is how many can be at it at once.
+pub const GROUP_WRITE_ATTEMPTS: usize = 20;
+
+// Reads don't settle finished rounds, that's left to the writes and the deadline task.
 pub async fn db_verify_group_member(
     group_id: String,
     username: String,
@@ -28,18 +58,42 @@ pub async fn db_verify_group_member(
     }
 }
 
-pub async fn db_add_user_to_group(group_id: &str, new_member: &str, db: &SqlitePool) -> Result<()> {
-    let mut group_struct = db_get_group(db, group_id).await?;
+/// Webhooks are for the owner only, so groups made before owners were kept can't have any.
+pub async fn db_verify_group_owner(
+    group_id: &str,
+    username: &str,
+    db: &SqlitePool,
+) -> Result<DBGroupStruct> {
+    let group_struct =
+        db_verify_group_member(group_id.to_string(), username.to_string(), db).await?;
+    if group_struct.group_data.is_owner(username) {
+        Ok(group_struct)
+    } else {
+        Err(custom(WarpRejections::UserNotAuthorized(err_info!())))
+    }
+}
 
+pub async fn db_add_user_to_group(
+    group_id: &str,
+    new_member: &str,
+    added_by: &str,
+    db: &SqlitePool,
+) -> Result<()> {
     // Needs to fail if the user doesn't exist. This handles it, but the order matters.
-    let mut user_struct = db_get_user(db, &new_member).await?;
-    let group_user_data = GroupUserData::default();
-    group_struct
-        .group_data
-        .members
-        .insert(new_member.to_string(), group_user_data);
-
-    db_update_group(db, &group_struct).await?;
+    let mut user_struct = db_get_user(db, new_member).await?;
+    let (group_struct, events) = db_change_group(db, group_id, |group_data, now| {
+        let event = GroupEvent {
+            actor: added_by.to_string(),
+            date: now,
+            kind: EventKind::MemberAdded {
+                username: new_member.to_string(),
+            },
+        };
+        group_data.apply_event(&event);
+        Ok(vec![event])
+    })
+    .await?;
+    db_insert_events(db, group_id, &events).await?;
     let group_info = GroupInfo {
         uuid: group_id.to_string(),
         name: group_struct.group_data.group_name.clone(),
@@ -50,8 +104,19 @@ pub async fn db_add_user_to_group(group_id: &str, new_member: &str, db: &SqliteP
 }
 
 pub async fn db_user_leave_group(db: &SqlitePool, username: &str, group_id: &str) -> Result<()> {
-    let mut group_struct = db_get_group(db, group_id).await?;
-    let mut user_struct = db_get_user(db, &username).await?;
+    let mut user_struct = db_get_user(db, username).await?;
+    let (group_struct, events) = db_change_group(db, group_id, |group_data, now| {
+        let event = GroupEvent {
+            actor: username.to_string(),
+            date: now,
+            kind: EventKind::MemberLeft {
+                username: username.to_string(),
+            },
+        };
+        group_data.apply_event(&event);
+        Ok(vec![event])
+    })
+    .await?;
     let group_name = &group_struct.group_data.group_name;
 
     let remove_group = GroupInfo {
@@ -61,19 +126,148 @@ pub async fn db_user_leave_group(db: &SqlitePool, username: &str, group_id: &str
 
     // These return bools, so can match on them if you want to handle errors removing.
     user_struct.user_data.groups.remove(&remove_group);
-    group_struct
-        .group_data
-        .members
-        .remove(&username.to_string());
     db_update_user(db, user_struct).await?;
 
     match group_struct.group_data.members.is_empty() {
-        true => db_delete_group(db, &group_id).await?,
-        false => db_update_group(db, &group_struct).await?,
+        true => db_delete_group(db, group_id).await?,
+        false => db_insert_events(db, group_id, &events).await?,
     }
     Ok(())
 }
 
+pub async fn db_record_login(db: &SqlitePool, username: &str) -> Result<()> {
+    let mut user_struct = db_get_user(db, username).await?;
+    let now = sqlx::types::chrono::Utc::now().timestamp();
+    user_struct.user_data.login_history.push(now);
+    db_update_user(db, user_struct).await?;
+    Ok(())
+}
+
+pub async fn db_export_user_data(db: &SqlitePool, username: &str) -> Result<UserExport> {
+    let user_struct = db_get_user(db, username).await?;
+    let mut groups = Vec::new();
+    let mut unreadable_groups = Vec::new();
+    for group_info in user_struct.user_data.groups.iter() {
+        match db_export_group(db, group_info, username).await {
+            Ok(group_export) => groups.push(group_export),
+            // One broken group shouldn't cost the user everything else.
+            Err(e) => {
+                log::error!("couldn't export {} for {}: {:?}", group_info, username, e);
+                unreadable_groups.push(group_info.clone());
+            }
+        }
+    }
+    let user_export = UserExport {
+        profile: UserProfile {
+            username: user_struct.username,
+            groups: user_struct.user_data.groups,
+            date_created: user_struct.user_data.date_created,
+            date_modified: user_struct.user_data.date_modified,
+        },
+        groups,
+        login_history: user_struct.user_data.login_history,
+        watchlist: user_struct.user_data.watchlist,
+        notifications: db_get_all_notifications(db, username).await?,
+        unreadable_groups,
+        date_exported: sqlx::types::chrono::Utc::now().timestamp(),
+    };
+    Ok(user_export)
+}
+
+/// Only copies out what this user added, the rest of the group belongs to the other members.
+async fn db_export_group(
+    db: &SqlitePool,
+    group_info: &GroupInfo,
+    username: &str,
+) -> Result<GroupExport> {
+    let group_data = db_get_group(db, &group_info.uuid).await?.group_data;
+    // Movies vetoed this round are only left in its candidates.
+    let mut round_movies = group_data
+        .current_movies
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    for movie in group_data.round.candidates.iter() {
+        if !round_movies
+            .iter()
+            .any(|added| added.movie_id == movie.movie_id)
+        {
+            round_movies.push(movie.clone());
+        }
+    }
+    let (mut comments, mut pitches) = export_discussions(
+        &group_data.round_id,
+        &group_data.discussions,
+        &round_movies,
+        username,
+    );
+    let mut movies_added = round_movies
+        .into_iter()
+        .filter(|movie| movie.added_by == username)
+        .collect::<Vec<_>>();
+    let mut vetoes = group_data
+        .round
+        .vetoes
+        .into_iter()
+        .filter(|veto| veto.username == username)
+        .collect::<Vec<_>>();
+    let backlog = group_data
+        .backlog
+        .into_iter()
+        .filter(|entry| entry.movie.added_by == username)
+        .collect();
+    let planned_rounds = group_data
+        .planned_rounds
+        .into_iter()
+        .filter_map(|mut planned_round| {
+            planned_round
+                .candidates
+                .retain(|movie| movie.added_by == username);
+            Some(planned_round).filter(|planned_round| !planned_round.candidates.is_empty())
+        })
+        .collect();
+    let mut ratings = Vec::new();
+    for history in db_get_all_group_history(db, &group_info.uuid).await? {
+        if let Some(rating) = history.ratings.get(username) {
+            ratings.push(RatingExport {
+                round_id: history.id.clone(),
+                movie: history.winner.clone(),
+                rating: rating.clone(),
+            });
+        }
+        let (round_comments, round_pitches) = export_discussions(
+            &history.id,
+            &history.discussions,
+            &history.candidates,
+            username,
+        );
+        comments.extend(round_comments);
+        pitches.extend(round_pitches);
+        movies_added.extend(
+            history
+                .candidates
+                .into_iter()
+                .filter(|movie| movie.added_by == username),
+        );
+        vetoes.extend(
+            history
+                .vetoes
+                .into_iter()
+                .filter(|veto| veto.username == username),
+        );
+    }
+    Ok(GroupExport {
+        group: group_info.clone(),
+        movies_added,
+        vetoes,
+        ratings,
+        comments,
+        pitches,
+        backlog,
+        planned_rounds,
+    })
+}
+
 pub async fn create_user_data(user_info: UserInfo) -> Result<DBUserStruct> {
     let id = Uuid::new_v4().to_string();
     let username = user_info.username;
@@ -87,6 +281,9 @@ pub async fn create_user_data(user_info: UserInfo) -> Result<DBUserStruct> {
         groups,
         date_created: now,
         date_modified: now,
+        login_history: Vec::new(),
+        watchlist: Vec::new(),
+        email_prefs: EmailPrefs::default(),
     };
     let user_struct = DBUserStruct {
         username,
@@ -95,9 +292,10 @@ pub async fn create_user_data(user_info: UserInfo) -> Result<DBUserStruct> {
     Ok(user_struct)
 }
 
-pub fn create_group_data(input: &GroupForm) -> GroupData {
+/// The group starts with just its owner, whatever the form says.
+pub fn create_group_data(input: &GroupForm, owner: &str) -> GroupData {
     let mut members = HashMap::new();
-    members.insert(input.username.clone(), GroupUserData::default());
+    members.insert(owner.to_string(), GroupUserData::default());
     let now = sqlx::types::chrono::Utc::now().timestamp();
     let turn = String::from("");
     GroupData {
@@ -110,6 +308,18 @@ pub fn create_group_data(input: &GroupForm) -> GroupData {
         turn,
         date_created: now,
         date_modified: now,
+        round: RoundData::default(),
+        selection_mode: SelectionMode::default(),
+        recent_winners: VecDeque::new(),
+        rules: GroupRules::default(),
+        deadline_from: 0,
+        owner: owner.to_string(),
+        rounds_played: 0,
+        round_id: String::from(""),
+        round_date_created: 0,
+        planned_rounds: Vec::new(),
+        backlog: Vec::new(),
+        discussions: HashMap::new(),
     }
 }
 
@@ -171,6 +381,33 @@ pub async fn db_insert_user(db: &SqlitePool, user_struct: DBUserStruct) -> Resul
     Ok(())
 }
 
+pub async fn db_set_email_prefs(
+    db: &SqlitePool,
+    username: &str,
+    email_prefs: EmailPrefs,
+) -> Result<EmailPrefs> {
+    let mut user_struct = db_get_user(db, username).await?;
+    user_struct.user_data.email_prefs = email_prefs.clone();
+    db_update_user(db, user_struct).await?;
+    Ok(email_prefs)
+}
+
+pub async fn db_apply_watchlist_action(
+    db: &SqlitePool,
+    username: &str,
+    action: WatchlistAction,
+) -> Result<Vec<WatchlistEntry>> {
+    let mut user_struct = db_get_user(db, username).await?;
+    let now = sqlx::types::chrono::Utc::now().timestamp();
+    user_struct
+        .user_data
+        .apply_watchlist_action(action, now)
+        .map_err(|e| custom(WarpRejections::WatchlistError(format!("{:?}", e))))?;
+    let watchlist = user_struct.user_data.watchlist.clone();
+    db_update_user(db, user_struct).await?;
+    Ok(watchlist)
+}
+
 pub async fn db_update_user(db: &SqlitePool, user_struct: DBUserStruct) -> Result<()> {
     let mut conn = acquire_db(db).await?;
     let serialized_user_data =
@@ -206,6 +443,13 @@ pub async fn db_delete_user(db: &SqlitePool, username: &str) -> Result<()> {
 
 pub async fn db_get_group(db: &SqlitePool, group_id: &str) -> Result<DBGroupStruct> {
     log::info!("inside db_get_group. group_id is: {:?}", &group_id);
+    let db_group = db_get_group_row(db, group_id).await?;
+    let group_data = db_get_group_data(db_group)?;
+    Ok(group_data)
+}
+
+/// The group as it's stored, which `db_swap_group` compares against.
+async fn db_get_group_row(db: &SqlitePool, group_id: &str) -> Result<DBGroup> {
     let mut conn = acquire_db(db).await?;
     let db_group = query_as!(
         DBGroup,
@@ -220,26 +464,32 @@ pub async fn db_get_group(db: &SqlitePool, group_id: &str) -> Result<DBGroupStru
     .await
     .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
 
-    let group_data = db_get_group_data(db_group)?;
-    Ok(group_data)
+    Ok(db_group)
 }
 
-pub async fn db_update_group(db: &SqlitePool, group_struct: &DBGroupStruct) -> Result<()> {
+/// Writes the group only if it's still stored as `read_data`, false if someone else wrote
+/// it first.
+async fn db_swap_group(
+    db: &SqlitePool,
+    group_struct: &DBGroupStruct,
+    read_data: &str,
+) -> Result<bool> {
     let mut conn = acquire_db(db).await?;
     let serialized_group_data =
         serde_json::to_string(&group_struct.group_data).expect("serialization error");
-    query!(
+    let result = query!(
         r#"
-            update groups set data=$1 where id=$2
+            update groups set data=$1 where id=$2 and data=$3
         "#,
         serialized_group_data,
         group_struct.id,
+        read_data,
     )
     .execute(&mut conn)
     .await
     .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
 
-    Ok(())
+    Ok(result.rows_affected() == 1)
 }
 
 pub fn db_get_group_data(db_group: DBGroup) -> Result<DBGroupStruct> {
@@ -286,6 +536,594 @@ pub async fn db_delete_group(db: &SqlitePool, group_id: &str) -> Result<()> {
     Ok(())
 }
 
+pub async fn db_apply_system_action(
+    db: &SqlitePool,
+    group_id: &str,
+    round_id: &str,
+    username: &str,
+    action: SystemAction,
+) -> Result<DBGroupStruct> {
+    let (group_struct, events) = db_change_group(db, group_id, |group_data, now| {
+        let mut events = group_data
+            .apply_round_action(round_id, username, action.clone(), now)
+            .map_err(|e| custom(WarpRejections::SystemActionError(format!("{:?}", e))))?;
+        events.extend(group_data.resolve_round(OsRng.next_u64(), now));
+        Ok(events)
+    })
+    .await?;
+    db_insert_events(db, group_id, &events).await?;
+    Ok(group_struct)
+}
+
+pub async fn db_plan_round(
+    db: &SqlitePool,
+    group_id: &str,
+    username: &str,
+) -> Result<DBGroupStruct> {
+    let (group_struct, events) = db_change_group(db, group_id, |group_data, now| {
+        let round_id = Uuid::new_v4().to_string();
+        group_data
+            .plan_round(username, round_id, now)
+            .map_err(|e| custom(WarpRejections::SystemActionError(format!("{:?}", e))))
+    })
+    .await?;
+    db_insert_events(db, group_id, &events).await?;
+    Ok(group_struct)
+}
+
+pub async fn db_apply_backlog_action(
+    db: &SqlitePool,
+    group_id: &str,
+    username: &str,
+    action: BacklogAction,
+) -> Result<DBGroupStruct> {
+    let (group_struct, events) = db_change_group(db, group_id, |group_data, now| {
+        group_data
+            .apply_backlog_action(username, action.clone(), OsRng.next_u64(), now)
+            .map_err(|e| custom(WarpRejections::SystemActionError(format!("{:?}", e))))
+    })
+    .await?;
+    db_insert_events(db, group_id, &events).await?;
+    Ok(group_struct)
+}
+
+pub async fn db_undo_veto(
+    db: &SqlitePool,
+    group_id: &str,
+    username: &str,
+) -> Result<DBGroupStruct> {
+    let (group_struct, events) = db_change_group(db, group_id, |group_data, now| {
+        group_data
+            .undo_veto(username, now, *VETO_UNDO_SECS)
+            .map_err(|e| custom(WarpRejections::SystemActionError(format!("{:?}", e))))
+    })
+    .await?;
+    db_insert_events(db, group_id, &events).await?;
+    Ok(group_struct)
+}
+
+/// Applies whatever's due if the group's adding phase or veto turn has run out,
+/// and settles a finished round once it can't be undone.
+pub async fn db_enforce_deadline(db: &SqlitePool, group_id: &str) -> Result<()> {
+    let (_, events) = db_change_group(db, group_id, |group_data, now| {
+        let mut events = group_data.enforce_deadline(OsRng.next_u64(), now);
+        if !events.is_empty() {
+            events.extend(group_data.resolve_round(OsRng.next_u64(), now));
+        }
+        Ok(events)
+    })
+    .await?;
+    if !events.is_empty() {
+        db_insert_events(db, group_id, &events).await?;
+    }
+    Ok(())
+}
+
+pub async fn db_get_all_group_ids(db: &SqlitePool) -> Result<Vec<String>> {
+    let mut conn = acquire_db(db).await?;
+    let group_ids = query!(
+        r#"
+            select id
+            from groups
+        "#
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?
+    .into_iter()
+    .map(|row| row.id)
+    .collect();
+    Ok(group_ids)
+}
+
+/// Every change to a group goes through here. `change` is run on the group as it is in the
+/// db, with finished rounds settled before and after, and the result is only written if
+/// nobody else wrote the group in between. If they did, it starts again from their version,
+/// so `change` may run more than once and shouldn't do anything but return events.
+/// The events aren't inserted, that's left to the caller.
+pub async fn db_change_group<F>(
+    db: &SqlitePool,
+    group_id: &str,
+    mut change: F,
+) -> Result<(DBGroupStruct, Vec<GroupEvent>)>
+where
+    F: FnMut(&mut GroupData, i64) -> Result<Vec<GroupEvent>>,
+{
+    for _ in 0..GROUP_WRITE_ATTEMPTS {
+        let db_group = db_get_group_row(db, group_id).await?;
+        let read_data = db_group.data.clone();
+        let mut group_struct = db_get_group_data(db_group)?;
+        let now = sqlx::types::chrono::Utc::now().timestamp();
+        let mut events = Vec::new();
+        let mut histories = Vec::new();
+        settle_round(&mut group_struct, &mut events, &mut histories, now);
+        events.extend(change(&mut group_struct.group_data, now)?);
+        settle_round(&mut group_struct, &mut events, &mut histories, now);
+        if events.is_empty() {
+            return Ok((group_struct, events));
+        }
+        if db_swap_group(db, &group_struct, &read_data).await? {
+            for history in &histories {
+                db_insert_history(db, group_id, history).await?;
+            }
+            return Ok((group_struct, events));
+        }
+        log::info!("group {} changed while writing it, trying again", group_id);
+    }
+    Err(custom(WarpRejections::GroupWriteConflict(err_info!())))
+}
+
+/// Moves a finished round into the history if it can't be undone any more, and opens a new
+/// round if there isn't one.
+fn settle_round(
+    group_struct: &mut DBGroupStruct,
+    events: &mut Vec<GroupEvent>,
+    histories: &mut Vec<RoundHistory>,
+    now: i64,
+) {
+    if !group_struct.group_data.can_undo(now, *VETO_UNDO_SECS) {
+        // Groups from before rounds had ids get a new one for their history.
+        let history_id = match group_struct.group_data.round_id.is_empty() {
+            true => Uuid::new_v4().to_string(),
+            false => group_struct.group_data.round_id.clone(),
+        };
+        if let Some((history, event)) = group_struct.group_data.finish_round(history_id, now) {
+            histories.push(history);
+            events.push(event);
+        }
+    }
+    let round_id = Uuid::new_v4().to_string();
+    if let Some(event) = group_struct.group_data.open_round(round_id, now) {
+        events.push(event);
+    }
+}
+
+pub async fn db_insert_events(
+    db: &SqlitePool,
+    group_id: &str,
+    events: &[GroupEvent],
+) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    for event in events {
+        let serialized_event = serde_json::to_string(event).expect("serialization error");
+        query!(
+            r#"
+                insert into events (group_id, date, data)
+                values ($1, $2, $3);
+            "#,
+            group_id,
+            event.date,
+            serialized_event,
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    }
+    db_notify_events(db, group_id, events).await?;
+    queue_webhooks(db.clone(), group_id.to_string(), events.to_vec());
+    Ok(())
+}
+
+/// Every event goes through `db_insert_events`, so this is where members hear about them.
+/// Emails are queued, not sent, so the request doesn't wait on the mail server.
+async fn db_notify_events(db: &SqlitePool, group_id: &str, events: &[GroupEvent]) -> Result<()> {
+    let group_struct = db_get_group(db, group_id).await?;
+    for event in events {
+        for (username, kind) in group_struct.group_data.notifications_for(group_id, event) {
+            db_insert_notification(db, &username, event.date, &kind).await?;
+            let wants_email = match db_get_user(db, &username).await {
+                Ok(user_struct) => user_struct.user_data.email_prefs.wants(&kind),
+                Err(_) => false,
+            };
+            if wants_email {
+                let message = render_notification(&username, &kind, &CORS_ORIGIN);
+                queue_message(NOTIFIER.clone(), message);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn db_insert_notification(
+    db: &SqlitePool,
+    username: &str,
+    date: i64,
+    kind: &NotificationKind,
+) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    let serialized_kind = serde_json::to_string(kind).expect("serialization error");
+    query!(
+        r#"
+            insert into notifications (username, date, data)
+            values ($1, $2, $3);
+        "#,
+        username,
+        date,
+        serialized_kind,
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    Ok(())
+}
+
+pub fn db_get_notification_data(db_notification: DBNotification) -> Result<Notification> {
+    let kind: NotificationKind = serde_json::from_str(&db_notification.data)
+        .map_err(|_| custom(WarpRejections::SerializationError(err_info!())))?;
+    Ok(Notification {
+        id: db_notification.id,
+        date: db_notification.date,
+        read: db_notification.is_read,
+        kind,
+    })
+}
+
+/// Newest first.
+pub async fn db_get_inbox(db: &SqlitePool, username: &str) -> Result<Inbox> {
+    let mut conn = acquire_db(db).await?;
+    let unread = query!(
+        r#"
+            select count(*) as "count: i64"
+            from notifications
+            where username = $1 and is_read = 0
+        "#,
+        username
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?
+    .count;
+
+    let db_notifications = query_as!(
+        DBNotification,
+        r#"
+            select id, username, date, is_read as "is_read: bool", data
+            from notifications
+            where username = $1
+            order by id desc
+            limit $2
+        "#,
+        username,
+        INBOX_SIZE
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    let notifications = db_notifications
+        .into_iter()
+        .map(db_get_notification_data)
+        .collect::<Result<Vec<Notification>>>()?;
+    Ok(Inbox {
+        notifications,
+        unread: unread as u32,
+    })
+}
+
+/// Oldest first, for the data export.
+pub async fn db_get_all_notifications(
+    db: &SqlitePool,
+    username: &str,
+) -> Result<Vec<Notification>> {
+    let mut conn = acquire_db(db).await?;
+    let db_notifications = query_as!(
+        DBNotification,
+        r#"
+            select id, username, date, is_read as "is_read: bool", data
+            from notifications
+            where username = $1
+            order by id
+        "#,
+        username
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    db_notifications
+        .into_iter()
+        .map(db_get_notification_data)
+        .collect()
+}
+
+/// Only ever touches the user's own notifications, whatever ids are sent.
+pub async fn db_mark_read(db: &SqlitePool, username: &str, mark: MarkRead) -> Result<Inbox> {
+    let mut conn = acquire_db(db).await?;
+    let ids = match mark {
+        MarkRead::All => {
+            query!(
+                r#"
+                    update notifications
+                    set is_read = 1
+                    where username = $1
+                "#,
+                username
+            )
+            .execute(&mut conn)
+            .await
+            .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+            Vec::new()
+        }
+        MarkRead::Ids(ids) => ids,
+    };
+    for id in ids {
+        query!(
+            r#"
+                update notifications
+                set is_read = 1
+                where username = $1 and id = $2
+            "#,
+            username,
+            id
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    }
+    db_get_inbox(db, username).await
+}
+
+pub fn db_get_event_data(db_event: DBEvent) -> Result<GroupEvent> {
+    let event: GroupEvent = serde_json::from_str(&db_event.data)
+        .map_err(|_| custom(WarpRejections::SerializationError(err_info!())))?;
+    Ok(event)
+}
+
+/// Newest first, for the activity feed.
+pub async fn db_get_group_events(db: &SqlitePool, group_id: &str, page: u32) -> Result<EventPage> {
+    let mut conn = acquire_db(db).await?;
+    let count = query!(
+        r#"
+            select count(*) as "count: i64"
+            from events
+            where group_id = $1
+        "#,
+        group_id
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?
+    .count;
+
+    let offset = page as i64 * EVENTS_PAGE_SIZE;
+    let db_events = query_as!(
+        DBEvent,
+        r#"
+            select *
+            from events
+            where group_id = $1
+            order by id desc
+            limit $2 offset $3
+        "#,
+        group_id,
+        EVENTS_PAGE_SIZE,
+        offset
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    let entries = db_events
+        .into_iter()
+        .map(db_get_event_data)
+        .collect::<Result<Vec<GroupEvent>>>()?;
+    let total_pages = ((count + EVENTS_PAGE_SIZE - 1) / EVENTS_PAGE_SIZE) as u32;
+    Ok(EventPage {
+        entries,
+        page,
+        total_pages,
+    })
+}
+
+/// Oldest first, in the order they need to be replayed.
+pub async fn db_get_all_group_events(db: &SqlitePool, group_id: &str) -> Result<Vec<GroupEvent>> {
+    let mut conn = acquire_db(db).await?;
+    let db_events = query_as!(
+        DBEvent,
+        r#"
+            select *
+            from events
+            where group_id = $1
+            order by id asc
+        "#,
+        group_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    db_events.into_iter().map(db_get_event_data).collect()
+}
+
+/// The history is keyed by round id, so settling the same round twice only keeps it once.
+pub async fn db_insert_history(
+    db: &SqlitePool,
+    group_id: &str,
+    history: &RoundHistory,
+) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    let serialized_history = serde_json::to_string(history).expect("serialization error");
+    query!(
+        r#"
+            insert or ignore into history (id, group_id, date, data)
+            values ($1, $2, $3, $4);
+        "#,
+        history.id,
+        group_id,
+        history.date_finished,
+        serialized_history,
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    Ok(())
+}
+
+pub fn db_get_history_data(db_history: DBHistory) -> Result<RoundHistory> {
+    let history: RoundHistory = serde_json::from_str(&db_history.data)
+        .map_err(|_| custom(WarpRejections::SerializationError(err_info!())))?;
+    Ok(history)
+}
+
+pub async fn db_get_history(
+    db: &SqlitePool,
+    group_id: &str,
+    history_id: &str,
+) -> Result<RoundHistory> {
+    let mut conn = acquire_db(db).await?;
+    let db_history = query_as!(
+        DBHistory,
+        r#"
+            select *
+            from history
+            where id = $1 and group_id = $2
+        "#,
+        history_id,
+        group_id
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    db_get_history_data(db_history)
+}
+
+pub async fn db_update_history(db: &SqlitePool, history: &RoundHistory) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    let serialized_history = serde_json::to_string(history).expect("serialization error");
+    query!(
+        r#"
+            update history set data=$1 where id=$2
+        "#,
+        serialized_history,
+        history.id,
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    Ok(())
+}
+
+// Rating again just replaces the old one, people change their minds.
+pub async fn db_rate_round(
+    db: &SqlitePool,
+    group_id: &str,
+    history_id: &str,
+    username: &str,
+    rating_form: RatingForm,
+) -> Result<RoundHistory> {
+    rating_form
+        .validate()
+        .map_err(|_| custom(WarpRejections::ValidationError(err_info!())))?;
+    let mut history = db_get_history(db, group_id, history_id).await?;
+    if !history.participants.iter().any(|member| member == username) {
+        return Err(custom(WarpRejections::UserNotAuthorized(err_info!())));
+    }
+    let rating = Rating {
+        score: rating_form.score,
+        comment: rating_form
+            .comment
+            .filter(|comment| !comment.trim().is_empty()),
+        date: sqlx::types::chrono::Utc::now().timestamp(),
+    };
+    history.ratings.insert(username.to_string(), rating);
+    db_update_history(db, &history).await?;
+    Ok(history)
+}
+
+pub async fn db_get_group_history(
+    db: &SqlitePool,
+    group_id: &str,
+    page: u32,
+) -> Result<HistoryPage> {
+    let mut conn = acquire_db(db).await?;
+    let count = query!(
+        r#"
+            select count(*) as "count: i64"
+            from history
+            where group_id = $1
+        "#,
+        group_id
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?
+    .count;
+
+    let offset = page as i64 * HISTORY_PAGE_SIZE;
+    let db_history = query_as!(
+        DBHistory,
+        r#"
+            select *
+            from history
+            where group_id = $1
+            order by date desc
+            limit $2 offset $3
+        "#,
+        group_id,
+        HISTORY_PAGE_SIZE,
+        offset
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    let entries = db_history
+        .into_iter()
+        .map(db_get_history_data)
+        .collect::<Result<Vec<RoundHistory>>>()?;
+    let total_pages = ((count + HISTORY_PAGE_SIZE - 1) / HISTORY_PAGE_SIZE) as u32;
+    Ok(HistoryPage {
+        entries,
+        page,
+        total_pages,
+    })
+}
+
+pub async fn db_get_all_group_history(
+    db: &SqlitePool,
+    group_id: &str,
+) -> Result<Vec<RoundHistory>> {
+    let mut conn = acquire_db(db).await?;
+    let db_history = query_as!(
+        DBHistory,
+        r#"
+            select *
+            from history
+            where group_id = $1
+            order by date asc
+        "#,
+        group_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+
+    db_history.into_iter().map(db_get_history_data).collect()
+}
+
 //Only used for tests currently
 pub async fn db_update_password(
     db: &SqlitePool,
@@ -338,3 +1176,162 @@ pub async fn db_update_username(
     }
     Ok(())
 }
+
+pub async fn db_insert_webhook(
+    db: &SqlitePool,
+    group_id: &str,
+    webhook_form: WebhookForm,
+) -> Result<Webhook> {
+    webhook_form
+        .validate()
+        .map_err(|_| custom(WarpRejections::ValidationError(err_info!())))?;
+    if let Err(e) = resolve_url(&webhook_form.url, *WEBHOOK_ALLOW_PRIVATE).await {
+        log::info!("refused webhook url {}: {}", &webhook_form.url, e);
+        return Err(custom(WarpRejections::ValidationError(err_info!())));
+    }
+    let webhook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        group_id: group_id.to_string(),
+        url: webhook_form.url,
+        secret: webhook_form.secret,
+        events: webhook_form.events,
+        date_created: sqlx::types::chrono::Utc::now().timestamp(),
+    };
+    let mut conn = acquire_db(db).await?;
+    let serialized_webhook = serde_json::to_string(&webhook).expect("serialization error");
+    query!(
+        r#"
+            insert into webhooks (id, group_id, data)
+            values ($1, $2, $3);
+        "#,
+        webhook.id,
+        webhook.group_id,
+        serialized_webhook,
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    Ok(webhook)
+}
+
+pub fn db_get_webhook_data(db_webhook: DBWebhook) -> Result<Webhook> {
+    let webhook: Webhook = serde_json::from_str(&db_webhook.data)
+        .map_err(|_| custom(WarpRejections::SerializationError(err_info!())))?;
+    Ok(webhook)
+}
+
+pub async fn db_get_webhooks(db: &SqlitePool, group_id: &str) -> Result<Vec<Webhook>> {
+    let mut conn = acquire_db(db).await?;
+    let db_webhooks = query_as!(
+        DBWebhook,
+        r#"
+            select *
+            from webhooks
+            where group_id = $1
+        "#,
+        group_id
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    let mut webhooks = db_webhooks
+        .into_iter()
+        .map(db_get_webhook_data)
+        .collect::<Result<Vec<Webhook>>>()?;
+    webhooks.sort_by_key(|webhook| webhook.date_created);
+    Ok(webhooks)
+}
+
+/// Checks the webhook belongs to the group, so an id from another group can't be used.
+pub async fn db_get_webhook(db: &SqlitePool, group_id: &str, webhook_id: &str) -> Result<Webhook> {
+    let mut conn = acquire_db(db).await?;
+    let db_webhook = query_as!(
+        DBWebhook,
+        r#"
+            select *
+            from webhooks
+            where id = $1 and group_id = $2
+        "#,
+        webhook_id,
+        group_id
+    )
+    .fetch_one(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    db_get_webhook_data(db_webhook)
+}
+
+pub async fn db_delete_webhook(db: &SqlitePool, group_id: &str, webhook_id: &str) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    query!(
+        r#"
+            delete from webhooks
+            where id = $1 and group_id = $2
+        "#,
+        webhook_id,
+        group_id
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    query!(
+        r#"
+            delete from webhook_deliveries
+            where webhook_id = $1
+        "#,
+        webhook_id
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    Ok(())
+}
+
+pub async fn db_insert_delivery(
+    db: &SqlitePool,
+    webhook_id: &str,
+    record: &DeliveryRecord,
+) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    let serialized_record = serde_json::to_string(record).expect("serialization error");
+    query!(
+        r#"
+            insert into webhook_deliveries (webhook_id, date, data)
+            values ($1, $2, $3);
+        "#,
+        webhook_id,
+        record.date,
+        serialized_record,
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    Ok(())
+}
+
+/// Newest first.
+pub async fn db_get_deliveries(db: &SqlitePool, webhook_id: &str) -> Result<Vec<DeliveryRecord>> {
+    let mut conn = acquire_db(db).await?;
+    let db_deliveries = query_as!(
+        DBDelivery,
+        r#"
+            select *
+            from webhook_deliveries
+            where webhook_id = $1
+            order by id desc
+            limit $2
+        "#,
+        webhook_id,
+        DELIVERIES_PAGE_SIZE
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    db_deliveries
+        .into_iter()
+        .map(|db_delivery| {
+            serde_json::from_str(&db_delivery.data)
+                .map_err(|_| custom(WarpRejections::SerializationError(err_info!())))
+        })
+        .collect()
+}
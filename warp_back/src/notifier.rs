@@ -0,0 +1,227 @@
+use async_trait::async_trait;
+use dotenv::dotenv;
+use lazy_static::lazy_static;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use shared_stuff::notification_structs::NotificationKind;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Counting the first try. The wait between tries starts at `FIRST_RETRY` and doubles.
+pub const MAX_ATTEMPTS: u32 = 5;
+pub const FIRST_RETRY: Duration = Duration::from_secs(2);
+
+const TEXT_TEMPLATE: &str = include_str!("../templates/notification.txt");
+const HTML_TEMPLATE: &str = include_str!("../templates/notification.html");
+
+lazy_static! {
+    /// Picked with `NOTIFIER` in the env, `smtp` or `file`.
+    pub static ref NOTIFIER: Arc<dyn Notifier> = {
+        dotenv().ok();
+        match dotenv::var("NOTIFIER").expect("env error").as_str() {
+            "smtp" => Arc::new(SmtpNotifier::new(&SmtpConfig::from_env()).expect("smtp error")),
+            _ => Arc::new(FileNotifier::new(dotenv::var("NOTIFIER_FILE").ok().map(PathBuf::from))),
+        }
+    };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundMessage {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotifierError(pub String);
+
+/// Somewhere notifications go outside the site.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, message: &OutboundMessage) -> Result<(), NotifierError>;
+}
+
+/// Fills both templates in, with the message escaped for the HTML one.
+/// `base_url` is where the site is, the link goes to the group's page on it.
+pub fn render_notification(to: &str, kind: &NotificationKind, base_url: &str) -> OutboundMessage {
+    let message = kind.message();
+    let link = format!("{}/system/{}", base_url, kind.group().uuid);
+    let fill = |template: &str, message: &str, link: &str| {
+        template
+            .replace("{{message}}", message)
+            .replace("{{link}}", link)
+    };
+    OutboundMessage {
+        to: to.to_string(),
+        subject: message.clone(),
+        text: fill(TEXT_TEMPLATE, &message, &link),
+        html: fill(HTML_TEMPLATE, &escape_html(&message), &escape_html(&link)),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Hands the message off to a task of its own, so whoever sent it never waits on delivery.
+pub fn queue_message(notifier: Arc<dyn Notifier>, message: OutboundMessage) {
+    tokio::spawn(async move {
+        if let Err(e) = deliver_with_retry(notifier.as_ref(), &message, FIRST_RETRY).await {
+            log::error!("gave up sending to {}: {:?}", &message.to, e);
+        }
+    });
+}
+
+/// Returns how many attempts it took, or the last error once `MAX_ATTEMPTS` are used up.
+pub async fn deliver_with_retry(
+    notifier: &dyn Notifier,
+    message: &OutboundMessage,
+    first_retry: Duration,
+) -> Result<u32, NotifierError> {
+    let mut wait = first_retry;
+    let mut attempt = 1;
+    loop {
+        match notifier.send(message).await {
+            Ok(()) => return Ok(attempt),
+            Err(e) if attempt >= MAX_ATTEMPTS => return Err(e),
+            Err(e) => {
+                log::warn!(
+                    "send to {} failed, attempt {}: {:?}",
+                    &message.to,
+                    attempt,
+                    e
+                );
+                tokio::time::sleep(wait).await;
+                wait *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    /// Empty for servers that don't ask for a login.
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    /// Off for a local catch-all, which usually only speaks plain SMTP.
+    pub tls: bool,
+}
+
+impl SmtpConfig {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        SmtpConfig {
+            host: dotenv::var("SMTP_HOST").expect("env error"),
+            port: dotenv::var("SMTP_PORT")
+                .expect("env error")
+                .parse::<u16>()
+                .expect("parse error"),
+            username: dotenv::var("SMTP_USERNAME").unwrap_or_default(),
+            password: dotenv::var("SMTP_PASSWORD").unwrap_or_default(),
+            from: dotenv::var("SMTP_FROM").expect("env error"),
+            tls: dotenv::var("SMTP_TLS")
+                .expect("env error")
+                .parse::<bool>()
+                .expect("parse error"),
+        }
+    }
+}
+
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    pub fn new(config: &SmtpConfig) -> Result<Self, NotifierError> {
+        let mut builder = match config.tls {
+            true => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                .map_err(|e| NotifierError(e.to_string()))?,
+            false => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        }
+        .port(config.port);
+        if !config.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.username.clone(),
+                config.password.clone(),
+            ));
+        }
+        let from = config
+            .from
+            .parse::<Mailbox>()
+            .map_err(|e| NotifierError(e.to_string()))?;
+        Ok(SmtpNotifier {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn send(&self, message: &OutboundMessage) -> Result<(), NotifierError> {
+        let to = message
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| NotifierError(e.to_string()))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject.clone())
+            .multipart(MultiPart::alternative_plain_html(
+                message.text.clone(),
+                message.html.clone(),
+            ))
+            .map_err(|e| NotifierError(e.to_string()))?;
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| NotifierError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// For development, logs every message and appends it to a file if there's one set.
+pub struct FileNotifier {
+    path: Option<PathBuf>,
+}
+
+impl FileNotifier {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        FileNotifier { path }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, message: &OutboundMessage) -> Result<(), NotifierError> {
+        log::info!("notification to {}: {}", &message.to, &message.subject);
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| NotifierError(e.to_string()))?;
+            writeln!(
+                file,
+                "To: {}\nSubject: {}\n\n{}\n----",
+                &message.to, &message.subject, &message.text
+            )
+            .map_err(|e| NotifierError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use dotenv::dotenv;
+use imdb_autocomplete::provider::ImdbProvider;
+use omdb_info::{OmdbClient, OmdbConfig};
+use shared_stuff::provider_structs::{MovieProvider, MovieSource, ProviderChain};
+use std::sync::Arc;
+use tmdb_info::TmdbProvider;
+
+/// Which providers to ask, in order, and what they need.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderConfig {
+    pub sources: Vec<MovieSource>,
+    pub omdb_api_key: Option<String>,
+    pub omdb_url: String,
+    pub tmdb_api_key: Option<String>,
+    pub tmdb_url: String,
+}
+
+impl ProviderConfig {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        let sources = dotenv::var("MOVIE_PROVIDERS").expect("env error");
+        ProviderConfig {
+            sources: parse_sources(&sources).expect("parse error"),
+            omdb_api_key: optional_var("OMDB_API_KEY"),
+            omdb_url: optional_var("OMDB_URL").unwrap_or_else(|| omdb_info::OMDB_URL.to_string()),
+            tmdb_api_key: optional_var("TMDB_API_KEY"),
+            tmdb_url: optional_var("TMDB_URL").unwrap_or_else(|| tmdb_info::TMDB_URL.to_string()),
+        }
+    }
+
+    /// Fails if a provider is asked for without its API key.
+    pub fn build(&self) -> Result<ProviderChain, String> {
+        let mut providers: Vec<Arc<dyn MovieProvider>> = vec![];
+        for source in &self.sources {
+            match source {
+                MovieSource::Imdb => providers.push(Arc::new(ImdbProvider)),
+                MovieSource::Omdb => {
+                    let api_key = self.omdb_api_key.clone().ok_or("OMDB_API_KEY isn't set")?;
+                    let config = OmdbConfig {
+                        api_key,
+                        base_url: self.omdb_url.clone(),
+                    };
+                    providers.push(Arc::new(OmdbClient::new(config)));
+                }
+                MovieSource::Tmdb => {
+                    let api_key = self.tmdb_api_key.clone().ok_or("TMDB_API_KEY isn't set")?;
+                    let tmdb_url = self.tmdb_url.clone();
+                    providers.push(Arc::new(TmdbProvider::new(api_key, tmdb_url)));
+                }
+            }
+        }
+        Ok(ProviderChain::new(providers))
+    }
+
+    /// For `/movie/{imdb_id}`. OMDb goes first whenever its key is set, even if it
+    /// isn't in `MOVIE_PROVIDERS`, since it has the most to say about a movie.
+    pub fn build_details(&self) -> Result<ProviderChain, String> {
+        let mut config = self.clone();
+        if config.omdb_api_key.is_some() {
+            config.sources.retain(|source| *source != MovieSource::Omdb);
+            config.sources.insert(0, MovieSource::Omdb);
+        }
+        config.build()
+    }
+}
+
+/// Set and not empty.
+fn optional_var(key: &str) -> Option<String> {
+    dotenv::var(key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// A comma separated list like "tmdb, imdb". Each can only be in it once.
+pub fn parse_sources(sources: &str) -> Result<Vec<MovieSource>, String> {
+    let mut parsed = vec![];
+    for source in sources
+        .split(',')
+        .map(|source| source.trim().to_lowercase())
+    {
+        let source = match source.as_str() {
+            "imdb" => MovieSource::Imdb,
+            "omdb" => MovieSource::Omdb,
+            "tmdb" => MovieSource::Tmdb,
+            other => return Err(format!("unknown movie provider: {:?}", other)),
+        };
+        if parsed.contains(&source) {
+            return Err(format!("{:?} is in MOVIE_PROVIDERS twice", source));
+        }
+        parsed.push(source);
+    }
+    Ok(parsed)
+}
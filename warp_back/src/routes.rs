@@ -1,25 +1,41 @@
 use crate::auth::{verify_pass, verify_token, with_auth};
 use crate::err_info;
 use crate::error_handling::WarpRejections;
-use crate::State;
+use crate::{State, VETO_UNDO_SECS};
 use http::status::StatusCode;
-use imdb_autocomplete::autocomplete_func;
 use shared_stuff::auth_structs::{ErrorMessage, Token, UserInfo};
+use shared_stuff::backlog_structs::BacklogAction;
 use shared_stuff::db_structs::DBGroupStruct;
+use shared_stuff::event_structs::{EventKind, GroupEvent};
 use shared_stuff::group_structs::{AddUser, GroupForm, GroupInfo, UserProfile};
+use shared_stuff::history_structs::{RatingForm, RatingsSummary};
 use shared_stuff::imdb_structs::ImdbQuery;
+use shared_stuff::notification_structs::{EmailPrefs, MarkRead};
+use shared_stuff::provider_structs::MovieProvider;
+use shared_stuff::stats_structs::GroupStats;
+use shared_stuff::system_structs::SystemAction;
+use shared_stuff::watchlist_structs::WatchlistAction;
+use shared_stuff::webhook_structs::{WebhookEvent, WebhookForm, WebhookInfo};
 use sqlx::types::uuid::Uuid;
 use sqlx::SqlitePool;
 use warp::reject::custom;
 
 use crate::auth::generate_tokens;
+use crate::search_cache::{CachedSearch, SearchCache};
+use crate::webhooks::{deliver, make_payload, FIRST_RETRY};
+use std::sync::Arc;
 
 use warp::reply::json;
 use warp::Filter;
 
 use crate::new_db_stuff::{
-    create_group_data, create_user_data, db_add_user_to_group, db_get_user, db_insert_group,
-    db_insert_user, db_update_group, db_update_user, db_user_leave_group, db_verify_group_member,
+    create_group_data, create_user_data, db_add_user_to_group, db_apply_backlog_action,
+    db_apply_system_action, db_apply_watchlist_action, db_delete_webhook, db_export_user_data,
+    db_get_all_group_history, db_get_deliveries, db_get_group_events, db_get_group_history,
+    db_get_inbox, db_get_user, db_get_webhook, db_get_webhooks, db_insert_delivery,
+    db_insert_events, db_insert_group, db_insert_user, db_insert_webhook, db_mark_read,
+    db_plan_round, db_rate_round, db_record_login, db_set_email_prefs, db_undo_veto,
+    db_update_user, db_user_leave_group, db_verify_group_member, db_verify_group_owner,
 };
 
 pub fn get_user_profile(
@@ -54,6 +70,117 @@ pub fn get_user_profile(
         )
 }
 
+pub fn get_watchlist(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_watchlist")
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(|username: String, db: SqlitePool| async move {
+            match db_get_user(&db, &username).await {
+                Ok(user_struct) => Ok(json(&user_struct.user_data.watchlist)),
+                Err(e) => Err(e),
+            }
+        })
+}
+
+pub fn get_notifications(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_notifications")
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(|username: String, db: SqlitePool| async move {
+            match db_get_inbox(&db, &username).await {
+                Ok(inbox) => Ok(json(&inbox)),
+                Err(e) => Err(e),
+            }
+        })
+}
+
+pub fn mark_notifications_read(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("mark_notifications_read")
+        .and(warp::body::json())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |mark: MarkRead, username: String, db: SqlitePool| async move {
+                match db_mark_read(&db, &username, mark).await {
+                    Ok(inbox) => Ok(json(&inbox)),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn get_email_prefs(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_email_prefs")
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(|username: String, db: SqlitePool| async move {
+            match db_get_user(&db, &username).await {
+                Ok(user_struct) => Ok(json(&user_struct.user_data.email_prefs)),
+                Err(e) => Err(e),
+            }
+        })
+}
+
+pub fn set_email_prefs(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("set_email_prefs")
+        .and(warp::body::json())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |email_prefs: EmailPrefs, username: String, db: SqlitePool| async move {
+                match db_set_email_prefs(&db, &username, email_prefs).await {
+                    Ok(email_prefs) => Ok(json(&email_prefs)),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn watchlist_action(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("watchlist_action")
+        .and(warp::body::json())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |action: WatchlistAction, username: String, db: SqlitePool| async move {
+                match db_apply_watchlist_action(&db, &username, action).await {
+                    Ok(watchlist) => Ok(json(&watchlist)),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn export_user_data(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("export_user_data")
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(|username: String, db: SqlitePool| async move {
+            match db_export_user_data(&db, &username).await {
+                Ok(user_export) => Ok(warp::reply::with_header(
+                    json(&user_export),
+                    "content-disposition",
+                    "attachment; filename=\"movie_system_export.json\"",
+                )),
+                Err(e) => Err(e),
+            }
+        })
+}
+
 pub fn get_group_data(
     state: &State,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -64,9 +191,16 @@ pub fn get_group_data(
         .and_then(
             |group_id: String, username: String, db: SqlitePool| async move {
                 log::info!("group_id: {:?}", &group_id);
-                match db_verify_group_member(group_id, username, &db).await {
-                    Ok(group_struct) => {
-                        let json_resp = serde_json::to_string(&group_struct)
+                match db_verify_group_member(group_id, username.clone(), &db).await {
+                    Ok(mut group_struct) => {
+                        group_struct.group_data.hide_secret_vetoes(&username);
+                        let history = db_get_all_group_history(&db, &group_struct.id).await?;
+                        let overview = group_struct.group_data.into_overview(
+                            &group_struct.id,
+                            &history,
+                            *VETO_UNDO_SECS,
+                        );
+                        let json_resp = serde_json::to_string(&overview)
                             .map_err(|_| custom(WarpRejections::SerializationError(err_info!())))?;
                         Ok(json_resp)
                     }
@@ -85,9 +219,9 @@ pub fn add_user_to_group(
         .and(with_auth())
         .and(with_db(state.db.clone()))
         .and_then(
-            |group_id: String, add_user: AddUser, _username: String, db: SqlitePool| async move {
+            |group_id: String, add_user: AddUser, username: String, db: SqlitePool| async move {
                 //let add_username = add_user.username;
-                match db_add_user_to_group(&group_id, &add_user.username, &db).await {
+                match db_add_user_to_group(&group_id, &add_user.username, &username, &db).await {
                     Ok(_) => Ok(warp::reply()),
                     Err(e) => Err(e),
                 }
@@ -112,22 +246,299 @@ pub fn leave_group(
         )
 }
 
-pub fn update_group_data(
+pub fn system_action(
     state: &State,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    warp::path("update_group_data")
+    warp::path("system_action")
+        .and(warp::path::param())
+        .and(warp::path::param())
         .and(warp::body::json())
+        .and(with_auth())
         .and(with_db(state.db.clone()))
-        .and_then(|group_struct: DBGroupStruct, db: SqlitePool| async move {
-            match db_update_group(&db, &group_struct).await {
-                Ok(_) => Ok(warp::reply()),
-                Err(e) => {
-                    log::info!("error is: {:?}", &e);
-                    Err(e)
+        .and_then(
+            |group_id: String,
+             round_id: String,
+             action: SystemAction,
+             username: String,
+             db: SqlitePool| async move {
+                log::info!("system action from {:?}: {:?}", &username, &action);
+                match db_apply_system_action(&db, &group_id, &round_id, &username, action).await {
+                    Ok(mut group_struct) => {
+                        group_struct.group_data.hide_secret_vetoes(&username);
+                        Ok(json(&group_struct))
+                    }
+                    Err(e) => {
+                        log::info!("error is: {:?}", &e);
+                        Err(e)
+                    }
                 }
-            }
-        })
+            },
+        )
 }
+
+pub fn plan_round(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("plan_round")
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, username: String, db: SqlitePool| async move {
+                match db_plan_round(&db, &group_id, &username).await {
+                    Ok(mut group_struct) => {
+                        group_struct.group_data.hide_secret_vetoes(&username);
+                        Ok(json(&group_struct))
+                    }
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn backlog_action(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("backlog_action")
+        .and(warp::path::param())
+        .and(warp::body::json())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, action: BacklogAction, username: String, db: SqlitePool| async move {
+                match db_apply_backlog_action(&db, &group_id, &username, action).await {
+                    Ok(mut group_struct) => {
+                        group_struct.group_data.hide_secret_vetoes(&username);
+                        Ok(json(&group_struct))
+                    }
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+async fn webhook_infos(
+    db: &SqlitePool,
+    group_id: &str,
+) -> Result<Vec<WebhookInfo>, warp::Rejection> {
+    let webhooks = db_get_webhooks(db, group_id).await?;
+    Ok(webhooks.iter().map(WebhookInfo::from).collect())
+}
+
+pub fn get_webhooks(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_webhooks")
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, username: String, db: SqlitePool| async move {
+                db_verify_group_owner(&group_id, &username, &db).await?;
+                let webhooks = webhook_infos(&db, &group_id).await?;
+                Ok::<_, warp::Rejection>(json(&webhooks))
+            },
+        )
+}
+
+pub fn add_webhook(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("add_webhook")
+        .and(warp::path::param())
+        .and(warp::body::json())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, webhook_form: WebhookForm, username: String, db: SqlitePool| async move {
+                db_verify_group_owner(&group_id, &username, &db).await?;
+                db_insert_webhook(&db, &group_id, webhook_form).await?;
+                let webhooks = webhook_infos(&db, &group_id).await?;
+                Ok::<_, warp::Rejection>(json(&webhooks))
+            },
+        )
+}
+
+pub fn delete_webhook(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("delete_webhook")
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, webhook_id: String, username: String, db: SqlitePool| async move {
+                db_verify_group_owner(&group_id, &username, &db).await?;
+                db_delete_webhook(&db, &group_id, &webhook_id).await?;
+                let webhooks = webhook_infos(&db, &group_id).await?;
+                Ok::<_, warp::Rejection>(json(&webhooks))
+            },
+        )
+}
+
+pub fn get_webhook_deliveries(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_webhook_deliveries")
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, webhook_id: String, username: String, db: SqlitePool| async move {
+                db_verify_group_owner(&group_id, &username, &db).await?;
+                let webhook = db_get_webhook(&db, &group_id, &webhook_id).await?;
+                let deliveries = db_get_deliveries(&db, &webhook.id).await?;
+                Ok::<_, warp::Rejection>(json(&deliveries))
+            },
+        )
+}
+
+/// Sends a ping straight away and answers with how it went, one try and no retries.
+pub fn ping_webhook(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("ping_webhook")
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, webhook_id: String, username: String, db: SqlitePool| async move {
+                db_verify_group_owner(&group_id, &username, &db).await?;
+                let webhook = db_get_webhook(&db, &group_id, &webhook_id).await?;
+                let payload = make_payload(&group_id, WebhookEvent::Ping, None);
+                let record = deliver(&webhook, &payload, 1, FIRST_RETRY).await;
+                db_insert_delivery(&db, &webhook.id, &record).await?;
+                Ok::<_, warp::Rejection>(json(&record))
+            },
+        )
+}
+
+pub fn undo_veto(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("undo_veto")
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, username: String, db: SqlitePool| async move {
+                match db_undo_veto(&db, &group_id, &username).await {
+                    Ok(mut group_struct) => {
+                        group_struct.group_data.hide_secret_vetoes(&username);
+                        Ok(json(&group_struct))
+                    }
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn get_group_events(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_group_events")
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, page: u32, username: String, db: SqlitePool| async move {
+                db_verify_group_member(group_id.clone(), username, &db).await?;
+                match db_get_group_events(&db, &group_id, page).await {
+                    Ok(mut event_page) => {
+                        event_page
+                            .entries
+                            .iter_mut()
+                            .for_each(GroupEvent::hide_secret);
+                        Ok(json(&event_page))
+                    }
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn get_group_history(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_group_history")
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, page: u32, username: String, db: SqlitePool| async move {
+                db_verify_group_member(group_id.clone(), username, &db).await?;
+                match db_get_group_history(&db, &group_id, page).await {
+                    Ok(history_page) => Ok(json(&history_page)),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn rate_round(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("rate_round")
+        .and(warp::path::param())
+        .and(warp::path::param())
+        .and(warp::body::json())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String,
+             history_id: String,
+             rating_form: RatingForm,
+             username: String,
+             db: SqlitePool| async move {
+                match db_rate_round(&db, &group_id, &history_id, &username, rating_form).await {
+                    Ok(history) => Ok(json(&history)),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn get_group_ratings(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_group_ratings")
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, username: String, db: SqlitePool| async move {
+                db_verify_group_member(group_id.clone(), username, &db).await?;
+                match db_get_all_group_history(&db, &group_id).await {
+                    Ok(history) => Ok(json(&RatingsSummary::from_history(&history))),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
+pub fn get_group_stats(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("get_group_stats")
+        .and(warp::path::param())
+        .and(with_auth())
+        .and(with_db(state.db.clone()))
+        .and_then(
+            |group_id: String, username: String, db: SqlitePool| async move {
+                db_verify_group_member(group_id.clone(), username, &db).await?;
+                match db_get_all_group_history(&db, &group_id).await {
+                    Ok(history) => Ok(json(&GroupStats::from_history(&history))),
+                    Err(e) => Err(e),
+                }
+            },
+        )
+}
+
 pub fn get_all_groups(
     state: &State,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -154,16 +565,24 @@ pub fn create_group(
         .and(with_auth())
         .and(with_db(state.db.clone()))
         .and_then(
-            |group_form: GroupForm, _username: String, db: SqlitePool| async move {
+            |group_form: GroupForm, username: String, db: SqlitePool| async move {
                 let uuid_string = Uuid::new_v4().to_string();
-                let group_data = create_group_data(&group_form);
+                let group_data = create_group_data(&group_form, &username);
+                let event = GroupEvent {
+                    actor: username.clone(),
+                    date: group_data.date_created,
+                    kind: EventKind::GroupCreated {
+                        group_name: group_form.group_name.clone(),
+                    },
+                };
                 let group_struct = DBGroupStruct {
                     id: uuid_string.clone(),
                     group_data,
                 };
                 match db_insert_group(&db, group_struct).await {
                     Ok(_) => {
-                        let mut user_struct = db_get_user(&db, &group_form.username).await?;
+                        db_insert_events(&db, &uuid_string, &[event]).await?;
+                        let mut user_struct = db_get_user(&db, &username).await?;
                         let group_info = GroupInfo {
                             uuid: uuid_string,
                             name: group_form.group_name,
@@ -236,21 +655,68 @@ pub fn authorize_access(
 pub fn search(
     state: &State,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let search_cache = state.search_cache.clone();
+    let provider = state.provider.clone();
     warp::path("search")
         .and(warp::body::json())
-        .and_then(|query: ImdbQuery| async move {
-            match autocomplete_func(query).await {
-                Ok(movie_vec) => {
-                    log::info!("{:?}", &movie_vec);
-                    let json_res = json(&movie_vec);
-                    Ok(json_res)
+        .and(warp::any().map(move || search_cache.clone()))
+        .and(warp::any().map(move || provider.clone()))
+        .and_then(
+            |query: ImdbQuery,
+             search_cache: Arc<SearchCache>,
+             provider: Arc<dyn MovieProvider>| async move {
+                let now = sqlx::types::chrono::Utc::now().timestamp();
+                let fetch = |term: String| async move {
+                    provider
+                        .search(&term)
+                        .await
+                        .map(|results| CachedSearch {
+                            movies: results.movies,
+                            exhaustive: results.exhaustive,
+                        })
+                        .map_err(|e| format!("{:?}", e))
+                };
+                match search_cache.search(&query.query, now, fetch).await {
+                    Ok(movie_vec) => {
+                        log::info!("{:?}", &movie_vec);
+                        let json_res = json(&movie_vec);
+                        Ok(json_res)
+                    }
+                    Err(_e) => Err(custom(WarpRejections::AutocompleteError(err_info!()))),
                 }
-                Err(_e) => Err(custom(WarpRejections::AutocompleteError(err_info!()))),
-            }
-        })
+            },
+        )
         .with(&state.cors)
 }
 
+/// Plot, runtime, genres, director, cast and ratings for one movie.
+pub fn get_movie_details(
+    state: &State,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let details = state.details.clone();
+    warp::path("movie")
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(with_auth())
+        .and(warp::any().map(move || details.clone()))
+        .and_then(
+            |movie_id: String, _username: String, details: Arc<dyn MovieProvider>| async move {
+                // It ends up in the provider's url.
+                if movie_id.is_empty() || !movie_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(custom(WarpRejections::ValidationError(err_info!())));
+                }
+                match details.details(&movie_id).await {
+                    Ok(movie_details) => Ok(json(&movie_details)),
+                    Err(e) => Err(custom(WarpRejections::MovieDetailsError(format!(
+                        "{:?} {}",
+                        e,
+                        err_info!()
+                    )))),
+                }
+            },
+        )
+}
+
 pub fn register(
     state: &State,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -282,7 +748,10 @@ pub fn login(
                 user_struct.user_data.salt,
                 user_struct.user_data.hashed_password,
             )? {
-                true => Ok(json(&token_response)),
+                true => {
+                    db_record_login(&db, &user.username).await?;
+                    Ok(json(&token_response))
+                }
                 false => Err(custom(WarpRejections::AuthError(err_info!()))),
             }
         })
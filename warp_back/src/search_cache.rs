@@ -0,0 +1,327 @@
+use dotenv::dotenv;
+use shared_stuff::provider_structs::MovieSource;
+use shared_stuff::shared_structs::MovieDisplay;
+use shared_stuff::{Deserialize, Serialize};
+use sqlx::{query, SqlitePool};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+use crate::err_info;
+use crate::error_handling::{Result, WarpRejections};
+use crate::new_db_stuff::acquire_db;
+use warp::reject::custom;
+
+/// What one upstream search gave back.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CachedSearch {
+    pub movies: Vec<MovieDisplay>,
+    /// Upstream had nothing more to suggest, so the results for any longer term are
+    /// among these and can be picked out without asking again.
+    pub exhaustive: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchCacheConfig {
+    pub capacity: usize,
+    pub ttl_secs: i64,
+    /// Keeps the cache in the `search_cache` table, so it's still warm after a restart.
+    pub persist: bool,
+}
+
+impl SearchCacheConfig {
+    pub fn from_env() -> Self {
+        dotenv().ok();
+        SearchCacheConfig {
+            capacity: dotenv::var("SEARCH_CACHE_SIZE")
+                .expect("env error")
+                .parse::<usize>()
+                .expect("parse error"),
+            ttl_secs: dotenv::var("SEARCH_CACHE_TTL_SECS")
+                .expect("env error")
+                .parse::<i64>()
+                .expect("parse error"),
+            persist: dotenv::var("SEARCH_CACHE_PERSIST")
+                .expect("env error")
+                .parse::<bool>()
+                .expect("parse error"),
+        }
+    }
+}
+
+struct CacheEntry {
+    search: CachedSearch,
+    date: i64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, CacheEntry>,
+    clock: u64,
+}
+
+type Flight = Arc<OnceCell<std::result::Result<CachedSearch, String>>>;
+
+/// An LRU with a time limit in front of the autocomplete. The locks are never held
+/// across an await.
+pub struct SearchCache {
+    config: SearchCacheConfig,
+    db: Option<SqlitePool>,
+    /// Like "imdb,omdb:", in front of every persisted term so nothing found with
+    /// other providers is loaded after they're changed.
+    key_prefix: String,
+    entries: Mutex<Entries>,
+    /// Searches waiting on upstream, so the same term is only asked for once at a time.
+    in_flight: Mutex<HashMap<String, Flight>>,
+}
+
+/// Lowercased, with the whitespace collapsed and anything before the first letter or
+/// number dropped, the same as IMDb does. `None` if there's nothing left.
+pub fn normalize_term(term: &str) -> Option<String> {
+    let term = term
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    Some(term).filter(|term| !term.is_empty())
+}
+
+/// IMDb matches each word of the term against the start of a word in the title.
+fn title_matches(movie: &MovieDisplay, term: &str) -> bool {
+    let title = movie.movie_title.to_lowercase();
+    let title_words = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    term.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .all(|word| {
+            title_words
+                .iter()
+                .any(|title_word| title_word.starts_with(word))
+        })
+}
+
+impl SearchCache {
+    /// `sources` are the providers the searches go to, in order.
+    pub fn new(config: SearchCacheConfig, db: Option<SqlitePool>, sources: &[MovieSource]) -> Self {
+        let db = db.filter(|_| config.persist);
+        let key_prefix = format!(
+            "{}:",
+            sources
+                .iter()
+                .map(|source| format!("{:?}", source).to_lowercase())
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        SearchCache {
+            config,
+            db,
+            key_prefix,
+            entries: Mutex::new(Entries::default()),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Fills the cache from the table with whatever hasn't expired, newest first,
+    /// and clears out the rest, along with anything from other providers.
+    /// Does nothing if it isn't persisted.
+    pub async fn load(&self, now: i64) -> Result<usize> {
+        let db = match &self.db {
+            Some(db) => db,
+            None => return Ok(0),
+        };
+        let mut conn = acquire_db(db).await?;
+        let oldest = now - self.config.ttl_secs;
+        let key_prefix = &self.key_prefix;
+        query!(
+            r#"
+                delete from search_cache
+                where date <= $1 or substr(term, 1, length($2)) != $2
+            "#,
+            oldest,
+            key_prefix
+        )
+        .execute(&mut conn)
+        .await
+        .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+        let capacity = self.config.capacity as i64;
+        let rows = query!(
+            r#"
+                select term, date, data
+                from search_cache
+                order by date desc
+                limit $1
+            "#,
+            capacity
+        )
+        .fetch_all(&mut conn)
+        .await
+        .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+        let mut entries = self.entries.lock().expect("lock error");
+        let mut loaded = 0;
+        // Oldest first, so the newest end up the most recently used.
+        for row in rows.into_iter().rev() {
+            let term = match row.term.strip_prefix(key_prefix) {
+                Some(term) => term.to_string(),
+                None => continue,
+            };
+            if let Ok(search) = serde_json::from_str::<CachedSearch>(&row.data) {
+                entries.clock += 1;
+                let entry = CacheEntry {
+                    search,
+                    date: row.date,
+                    last_used: entries.clock,
+                };
+                entries.map.insert(term, entry);
+                loaded += 1;
+            }
+        }
+        Ok(loaded)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("lock error").map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cached results for the term, or picked out of an exhaustive search for
+    /// one of its prefixes. Takes a normalized term.
+    pub fn lookup(&self, term: &str, now: i64) -> Option<Vec<MovieDisplay>> {
+        let oldest = now - self.config.ttl_secs;
+        let mut entries = self.entries.lock().expect("lock error");
+        entries.map.retain(|_, entry| entry.date > oldest);
+        entries.clock += 1;
+        let clock = entries.clock;
+        if let Some(entry) = entries.map.get_mut(term) {
+            entry.last_used = clock;
+            return Some(entry.search.movies.clone());
+        }
+        // Longest first, it's the one with the fewest movies to pick through.
+        let prefixes = term
+            .char_indices()
+            .skip(1)
+            .map(|(index, _)| &term[..index])
+            .collect::<Vec<_>>();
+        for prefix in prefixes.into_iter().rev() {
+            if let Some(entry) = entries.map.get_mut(prefix) {
+                if entry.search.exhaustive {
+                    entry.last_used = clock;
+                    let movies = entry
+                        .search
+                        .movies
+                        .iter()
+                        .filter(|movie| title_matches(movie, term))
+                        .cloned()
+                        .collect();
+                    return Some(movies);
+                }
+            }
+        }
+        None
+    }
+
+    async fn insert(&self, term: &str, search: &CachedSearch, now: i64) {
+        {
+            let mut entries = self.entries.lock().expect("lock error");
+            entries.clock += 1;
+            let entry = CacheEntry {
+                search: search.clone(),
+                date: now,
+                last_used: entries.clock,
+            };
+            entries.map.insert(term.to_string(), entry);
+            while entries.map.len() > self.config.capacity {
+                let least_used = entries
+                    .map
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(term, _)| term.clone());
+                match least_used {
+                    Some(least_used) => entries.map.remove(&least_used),
+                    None => break,
+                };
+            }
+        }
+        if let Some(db) = &self.db {
+            let key = format!("{}{}", self.key_prefix, term);
+            if let Err(e) = db_insert_search(db, &key, search, now).await {
+                log::error!("couldn't persist search for {}: {:?}", term, e);
+            }
+        }
+    }
+
+    /// Answers from the cache if it can, otherwise calls `fetch` with the normalized
+    /// term. Everyone searching the same term meanwhile waits on that one call.
+    /// Failed searches aren't cached.
+    pub async fn search<F, Fut>(
+        &self,
+        term: &str,
+        now: i64,
+        fetch: F,
+    ) -> std::result::Result<Vec<MovieDisplay>, String>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = std::result::Result<CachedSearch, String>>,
+    {
+        let term = normalize_term(term).ok_or_else(|| String::from("empty search term"))?;
+        if let Some(movies) = self.lookup(&term, now) {
+            log::info!("search cache hit: {}", &term);
+            return Ok(movies);
+        }
+        let flight = self
+            .in_flight
+            .lock()
+            .expect("lock error")
+            .entry(term.clone())
+            .or_default()
+            .clone();
+        let result = flight
+            .get_or_init(|| async {
+                let result = fetch(term.clone()).await;
+                if let Ok(search) = &result {
+                    self.insert(&term, search, now).await;
+                }
+                result
+            })
+            .await
+            .clone();
+        let mut in_flight = self.in_flight.lock().expect("lock error");
+        if in_flight
+            .get(&term)
+            .is_some_and(|current| Arc::ptr_eq(current, &flight))
+        {
+            in_flight.remove(&term);
+        }
+        result.map(|search| search.movies)
+    }
+}
+
+async fn db_insert_search(
+    db: &SqlitePool,
+    term: &str,
+    search: &CachedSearch,
+    now: i64,
+) -> Result<()> {
+    let mut conn = acquire_db(db).await?;
+    let serialized_search = serde_json::to_string(search).expect("serialization error");
+    query!(
+        r#"
+            insert or replace into search_cache (term, date, data)
+            values ($1, $2, $3);
+        "#,
+        term,
+        now,
+        serialized_search,
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
+    Ok(())
+}
@@ -1,19 +1,35 @@
 use crate::err_info;
 use crate::error_handling::Result;
 use crate::error_handling::WarpRejections;
+use crate::search_cache::{SearchCache, SearchCacheConfig};
 use crate::{make_cors, State};
+use imdb_autocomplete::provider::ImdbProvider;
+use shared_stuff::provider_structs::MovieSource;
 use sqlx::migrate::MigrateDatabase;
 use sqlx::query;
 use sqlx::Sqlite;
 use sqlx::SqlitePool;
 use std::fs::remove_file;
+use std::sync::Arc;
 use warp::reject::custom;
 
 impl State {
     pub async fn test_init(db_name: &str) -> Result<Self> {
         let db = setup_new_db(db_name).await?;
         let cors = make_cors();
-        Ok(Self { db, cors })
+        let config = SearchCacheConfig {
+            capacity: 100,
+            ttl_secs: 60,
+            persist: false,
+        };
+        let search_cache = Arc::new(SearchCache::new(config, None, &[MovieSource::Imdb]));
+        Ok(Self {
+            db,
+            cors,
+            search_cache,
+            provider: Arc::new(ImdbProvider),
+            details: Arc::new(ImdbProvider),
+        })
     }
 }
 
@@ -67,7 +83,7 @@ pub async fn setup_new_db(db_name: &str) -> Result<SqlitePool> {
     {
         delete_db(db_name)?;
     }
-    let _new_db = Sqlite::create_database(&db_str)
+    Sqlite::create_database(&db_str)
         .await
         .map_err(|_| custom(WarpRejections::SqlxError(err_info!())))?;
     let pool = SqlitePool::connect(&db_str)
@@ -0,0 +1,225 @@
+use dotenv::dotenv;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use reqwest::redirect::Policy;
+use reqwest::{Client, Url};
+use sha2::Sha256;
+use shared_stuff::event_structs::GroupEvent;
+use shared_stuff::webhook_structs::{DeliveryRecord, Webhook, WebhookEvent, WebhookPayload};
+use sqlx::types::uuid::Uuid;
+use sqlx::SqlitePool;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use url::Host;
+
+use crate::new_db_stuff::{db_get_webhooks, db_insert_delivery};
+
+/// Counting the first try. The wait between tries starts at `FIRST_RETRY` and doubles.
+pub const MAX_ATTEMPTS: u32 = 5;
+pub const FIRST_RETRY: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+lazy_static! {
+    /// Hooks can only reach public addresses unless this is `true`, which is for trying
+    /// them out against a receiver on the same machine.
+    pub static ref WEBHOOK_ALLOW_PRIVATE: bool = {
+        dotenv().ok();
+        dotenv::var("WEBHOOK_ALLOW_PRIVATE").is_ok_and(|allow| allow == "true")
+    };
+}
+
+/// Anywhere on the internet, as opposed to this machine or the network it's on.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || first == 0
+                // Carrier-grade NAT, 100.64.0.0/10.
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            // Unique local fc00::/7 and link-local fe80::/10.
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.segments()[0] & 0xfe00 == 0xfc00
+                    || ip.segments()[0] & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Checks the url is http or https and looks up its host, refusing it if any of the
+/// addresses isn't public. The request has to go to the addresses handed back, looking
+/// the name up again would let it change in between.
+pub async fn resolve_url(url: &str, allow_private: bool) -> Result<(Url, Vec<SocketAddr>), String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("{} urls can't be webhooks", url.scheme()));
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs = match url.host() {
+        Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|e| format!("couldn't look up {}: {}", domain, e))?
+            .collect::<Vec<_>>(),
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        None => return Err(String::from("the url has no host")),
+    };
+    if addrs.is_empty() {
+        return Err(format!("{} has no addresses", url));
+    }
+    if !allow_private {
+        if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            return Err(format!("{} isn't a public address", addr.ip()));
+        }
+    }
+    Ok((url, addrs))
+}
+
+/// Hex HMAC-SHA256 of the body, sent as `sha256=<hex>` so receivers can tell what it is.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+pub fn make_payload(
+    group_id: &str,
+    event: WebhookEvent,
+    data: Option<GroupEvent>,
+) -> WebhookPayload {
+    WebhookPayload {
+        delivery_id: Uuid::new_v4().to_string(),
+        group_id: group_id.to_string(),
+        event,
+        date: sqlx::types::chrono::Utc::now().timestamp(),
+        data,
+    }
+}
+
+/// One try, giving back the status if there was a response and the error if it failed.
+async fn post_payload(
+    webhook: &Webhook,
+    payload: &WebhookPayload,
+) -> (Option<u16>, Option<String>) {
+    // Checked again on every try, the name could point somewhere else since it was added.
+    let (url, addrs) = match resolve_url(&webhook.url, *WEBHOOK_ALLOW_PRIVATE).await {
+        Ok(resolved) => resolved,
+        Err(e) => return (None, Some(e)),
+    };
+    // Redirects aren't followed, they could lead anywhere.
+    let mut client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(Policy::none());
+    if let Some(Host::Domain(domain)) = url.host() {
+        client = client.resolve_to_addrs(domain, &addrs);
+    }
+    let client = client.build().expect("client error");
+    let body = serde_json::to_vec(payload).expect("serialization error");
+    let resp = client
+        .post(url)
+        .header("content-type", "application/json")
+        .header(SIGNATURE_HEADER, sign(&webhook.secret, &body))
+        .header(EVENT_HEADER, format!("{:?}", payload.event))
+        .header(DELIVERY_HEADER, &payload.delivery_id)
+        .body(body)
+        .send()
+        .await;
+    match resp {
+        Ok(resp) if resp.status().is_success() => (Some(resp.status().as_u16()), None),
+        Ok(resp) => (
+            Some(resp.status().as_u16()),
+            Some(format!("receiver answered {}", resp.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Tries up to `attempts` times. A 4xx other than 429 means the receiver won't take it,
+/// so that isn't retried.
+pub async fn deliver(
+    webhook: &Webhook,
+    payload: &WebhookPayload,
+    attempts: u32,
+    first_retry: Duration,
+) -> DeliveryRecord {
+    let mut wait = first_retry;
+    let mut attempt = 1;
+    loop {
+        let (status, error) = post_payload(webhook, payload).await;
+        let give_up = match status {
+            Some(status) => {
+                (200..300).contains(&status) || ((400..500).contains(&status) && status != 429)
+            }
+            None => false,
+        };
+        if give_up || attempt >= attempts {
+            return DeliveryRecord {
+                delivery_id: payload.delivery_id.clone(),
+                event: payload.event,
+                date: payload.date,
+                attempts: attempt,
+                status,
+                error,
+            };
+        }
+        tokio::time::sleep(wait).await;
+        wait *= 2;
+        attempt += 1;
+    }
+}
+
+/// Sends the events to every hook on the group that wants them, off on a task of its own
+/// so the request that made them doesn't wait. Each delivery ends up in the log.
+pub fn queue_webhooks(db: SqlitePool, group_id: String, events: Vec<GroupEvent>) {
+    let wanted = events
+        .into_iter()
+        .filter_map(|event| WebhookEvent::from_event(&event.kind).map(|kind| (kind, event)))
+        .collect::<Vec<_>>();
+    if wanted.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let webhooks = match db_get_webhooks(&db, &group_id).await {
+            Ok(webhooks) => webhooks,
+            Err(e) => {
+                log::error!("couldn't load webhooks for group {}: {:?}", &group_id, e);
+                return;
+            }
+        };
+        for webhook in webhooks {
+            let events = wanted
+                .iter()
+                .filter(|(kind, _)| webhook.wants(*kind))
+                .map(|(kind, event)| make_payload(&group_id, *kind, Some(event.clone())))
+                .collect::<Vec<_>>();
+            if events.is_empty() {
+                continue;
+            }
+            let db = db.clone();
+            // In order for each hook, a slow one doesn't hold up the others.
+            tokio::spawn(async move {
+                for payload in events {
+                    let record = deliver(&webhook, &payload, MAX_ATTEMPTS, FIRST_RETRY).await;
+                    if let Err(e) = db_insert_delivery(&db, &webhook.id, &record).await {
+                        log::error!("couldn't log delivery {}: {:?}", &record.delivery_id, e);
+                    }
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,214 @@
+use shared_stuff::auth_structs::UserInfo;
+use shared_stuff::backlog_structs::BacklogAction;
+use shared_stuff::db_structs::DBGroupStruct;
+use shared_stuff::group_structs::{GroupForm, GroupInfo};
+use shared_stuff::history_structs::{Rating, RoundHistory};
+use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::provider_structs::MovieSource;
+use shared_stuff::selection_structs::SelectionMode;
+use shared_stuff::shared_structs::MovieDisplay;
+use shared_stuff::system_structs::SystemAction;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use warp_back::new_db_stuff::{
+    create_group_data, create_user_data, db_add_user_to_group, db_apply_backlog_action,
+    db_apply_system_action, db_export_user_data, db_get_group, db_get_user, db_insert_group,
+    db_insert_history, db_insert_user, db_plan_round, db_update_user,
+};
+
+fn test_movie(movie_id: &str, movie_title: &str) -> MovieDisplay {
+    MovieDisplay {
+        movie_id: movie_id.to_string(),
+        movie_title: movie_title.to_string(),
+        movie_year: 1981,
+        movie_images: ImageData {
+            url: String::from("https://m.media-amazon.com/images/test.jpg"),
+            width: 100,
+            height: 100,
+        },
+        movie_stars: String::from(""),
+        source: MovieSource::Imdb,
+    }
+}
+
+async fn test_db(name: &str) -> SqlitePool {
+    std::env::set_var("VETO_UNDO_SECS", "60");
+    std::env::set_var("CORS_ORIGIN", "http://localhost:8080");
+    std::env::set_var("NOTIFIER", "file");
+    let db_path = std::env::temp_dir().join(format!("{}_{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&db_path);
+    std::env::set_var("NOTIFIER_FILE", db_path.with_extension("log"));
+    let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
+    let db = SqlitePool::connect(&db_url).await.unwrap();
+    sqlx::query(include_str!("../../db/db_setup.sql"))
+        .execute(&db)
+        .await
+        .unwrap();
+    for username in ["indiana", "marion"] {
+        let user_info = UserInfo {
+            username: username.to_string(),
+            password: String::from("umm237@#"),
+        };
+        let user_struct = create_user_data(user_info).await.unwrap();
+        db_insert_user(&db, user_struct).await.unwrap();
+    }
+    db
+}
+
+async fn test_group(db: &SqlitePool, group_id: &str, owner: &str) {
+    let group_form = GroupForm {
+        username: owner.to_string(),
+        group_name: String::from("movie club"),
+    };
+    let group_struct = DBGroupStruct {
+        id: group_id.to_string(),
+        group_data: create_group_data(&group_form, owner),
+    };
+    db_insert_group(db, group_struct).await.unwrap();
+    let mut user_struct = db_get_user(db, owner).await.unwrap();
+    user_struct.user_data.groups.insert(GroupInfo {
+        uuid: group_id.to_string(),
+        name: String::from("movie club"),
+    });
+    db_update_user(db, user_struct).await.unwrap();
+}
+
+#[tokio::test]
+// Everything a user put into a group comes out, wherever it ended up, and a group that
+// can't be read is named instead of failing the export.
+async fn export_covers_the_whole_group() {
+    let db = test_db("export_covers_the_whole_group").await;
+    test_group(&db, "group", "indiana").await;
+    db_add_user_to_group("group", "marion", "indiana", &db)
+        .await
+        .unwrap();
+
+    // Planning a round opens the active one in front of it.
+    let group_data = db_plan_round(&db, "group", "indiana")
+        .await
+        .unwrap()
+        .group_data;
+    let round_id = group_data.round_id.clone();
+    let planned_id = group_data.planned_rounds[0].id.clone();
+    for (username, movie) in [
+        ("indiana", test_movie("tt1", "Raiders of the Lost Ark")),
+        ("indiana", test_movie("tt2", "Temple of Doom")),
+        ("marion", test_movie("tt3", "The Last Crusade")),
+    ] {
+        db_apply_system_action(
+            &db,
+            "group",
+            &round_id,
+            username,
+            SystemAction::AddMovie(movie),
+        )
+        .await
+        .unwrap();
+    }
+    for (username, movie) in [
+        ("indiana", test_movie("tt4", "Crystal Skull")),
+        ("marion", test_movie("tt5", "Dial of Destiny")),
+    ] {
+        db_apply_system_action(
+            &db,
+            "group",
+            &planned_id,
+            username,
+            SystemAction::AddMovie(movie),
+        )
+        .await
+        .unwrap();
+    }
+    for (username, movie) in [
+        ("indiana", test_movie("tt6", "Young Indiana Jones")),
+        ("marion", test_movie("tt7", "Romancing the Stone")),
+    ] {
+        db_apply_backlog_action(&db, "group", username, BacklogAction::Add(movie))
+            .await
+            .unwrap();
+    }
+    for username in ["indiana", "marion"] {
+        db_apply_system_action(&db, "group", &round_id, username, SystemAction::SetReady)
+            .await
+            .unwrap();
+    }
+    // Whoever goes first vetoes one of indiana's, and the round carries on.
+    let group_data = db_get_group(&db, "group").await.unwrap().group_data;
+    let vetoed = group_data
+        .round
+        .candidates
+        .iter()
+        .find(|movie| movie.movie_id == "tt1")
+        .unwrap()
+        .clone();
+    let turn = group_data.turn.clone();
+    db_apply_system_action(&db, "group", &round_id, &turn, SystemAction::Veto(vetoed))
+        .await
+        .unwrap();
+
+    let winner = test_movie("tt8", "Last Crusade").into_yew_display(String::from("indiana"));
+    let rating = Rating {
+        score: 9,
+        comment: None,
+        date: 200,
+    };
+    let history = RoundHistory {
+        id: String::from("old round"),
+        added_by: String::from("indiana"),
+        candidates: vec![winner.clone()],
+        selection_mode: SelectionMode::Veto,
+        winner,
+        date_started: 100,
+        date_finished: 200,
+        participants: vec![String::from("indiana"), String::from("marion")],
+        ratings: HashMap::from([(String::from("indiana"), rating)]),
+        vetoes: Vec::new(),
+        draw: None,
+        runoff: None,
+        score: None,
+        bracket: None,
+        discussions: HashMap::new(),
+    };
+    db_insert_history(&db, "group", &history).await.unwrap();
+
+    // A group whose data no longer parses.
+    sqlx::query("insert into groups (id, data) values ('broken', 'not json')")
+        .execute(&db)
+        .await
+        .unwrap();
+    let mut user_struct = db_get_user(&db, "indiana").await.unwrap();
+    let broken = GroupInfo {
+        uuid: String::from("broken"),
+        name: String::from("broken club"),
+    };
+    user_struct.user_data.groups.insert(broken.clone());
+    db_update_user(&db, user_struct).await.unwrap();
+
+    let export = db_export_user_data(&db, "indiana").await.unwrap();
+    assert_eq!(export.unreadable_groups, vec![broken]);
+    assert_eq!(export.groups.len(), 1);
+    let group_export = &export.groups[0];
+
+    let mut movies_added = group_export
+        .movies_added
+        .iter()
+        .map(|movie| movie.movie_id.as_str())
+        .collect::<Vec<_>>();
+    movies_added.sort_unstable();
+    assert_eq!(movies_added, ["tt1", "tt2", "tt8"]);
+    assert_eq!(group_export.ratings.len(), 1);
+    assert_eq!(group_export.ratings[0].round_id, "old round");
+    let vetoes = match turn.as_str() {
+        "indiana" => 1,
+        _ => 0,
+    };
+    assert_eq!(group_export.vetoes.len(), vetoes);
+
+    assert_eq!(group_export.backlog.len(), 1);
+    assert_eq!(group_export.backlog[0].movie.movie_id, "tt6");
+    assert_eq!(group_export.planned_rounds.len(), 1);
+    assert_eq!(group_export.planned_rounds[0].id, planned_id);
+    let planned = &group_export.planned_rounds[0].candidates;
+    assert_eq!(planned.len(), 1);
+    assert_eq!(planned[0].movie_id, "tt4");
+}
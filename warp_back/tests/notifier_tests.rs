@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use shared_stuff::group_structs::GroupInfo;
+use shared_stuff::notification_structs::NotificationKind;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use warp_back::notifier::{
+    deliver_with_retry, render_notification, FileNotifier, Notifier, NotifierError,
+    OutboundMessage, SmtpConfig, SmtpNotifier, MAX_ATTEMPTS,
+};
+
+// Fails the first `failures` sends, then works.
+struct FlakyNotifier {
+    failures: u32,
+    attempts: AtomicU32,
+}
+
+#[async_trait]
+impl Notifier for FlakyNotifier {
+    async fn send(&self, _message: &OutboundMessage) -> Result<(), NotifierError> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+        match attempt > self.failures {
+            true => Ok(()),
+            false => Err(NotifierError(format!("attempt {} failed", attempt))),
+        }
+    }
+}
+
+fn test_message() -> OutboundMessage {
+    let kind = NotificationKind::AddedToGroup {
+        group: GroupInfo {
+            name: String::from("<b>movie club</b>"),
+            uuid: String::from("group"),
+        },
+        added_by: String::from("indiana@test.com"),
+    };
+    render_notification("marion@test.com", &kind, "http://localhost:8080")
+}
+
+#[test]
+// The HTML version gets the group name escaped, the plain one keeps it as it is.
+fn templates_are_filled_in() {
+    let message = test_message();
+    assert_eq!(
+        message.subject,
+        "indiana@test.com added you to <b>movie club</b>"
+    );
+    assert!(message.text.contains("<b>movie club</b>"));
+    assert!(message.html.contains("&lt;b&gt;movie club&lt;/b&gt;"));
+    assert!(!message.html.contains("{{"));
+    assert!(message.text.contains("http://localhost:8080/system/group"));
+}
+
+#[tokio::test]
+// Retries until it goes through, and gives up after MAX_ATTEMPTS.
+async fn delivery_retries_with_backoff() {
+    let message = test_message();
+    let flaky = FlakyNotifier {
+        failures: 2,
+        attempts: AtomicU32::new(0),
+    };
+    let attempts = deliver_with_retry(&flaky, &message, Duration::from_millis(1)).await;
+    assert_eq!(attempts, Ok(3));
+
+    let broken = FlakyNotifier {
+        failures: u32::MAX,
+        attempts: AtomicU32::new(0),
+    };
+    assert!(
+        deliver_with_retry(&broken, &message, Duration::from_millis(1))
+            .await
+            .is_err()
+    );
+    assert_eq!(broken.attempts.load(Ordering::SeqCst), MAX_ATTEMPTS);
+}
+
+#[tokio::test]
+async fn file_notifier_appends() {
+    let path = std::env::temp_dir().join("file_notifier_appends.log");
+    let _ = std::fs::remove_file(&path);
+    let notifier = FileNotifier::new(Some(path.clone()));
+    notifier.send(&test_message()).await.unwrap();
+    notifier.send(&test_message()).await.unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(written.matches("To: marion@test.com").count(), 2);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+#[ignore]
+// Needs a catch-all like MailHog on localhost:1025, run with `cargo t -p warp_back -- --ignored`.
+async fn smtp_catch_all() {
+    let config = SmtpConfig {
+        host: String::from("localhost"),
+        port: 1025,
+        username: String::from(""),
+        password: String::from(""),
+        from: String::from("Movie Site <noreply@localhost>"),
+        tls: false,
+    };
+    let notifier = SmtpNotifier::new(&config).unwrap();
+    notifier.send(&test_message()).await.unwrap();
+}
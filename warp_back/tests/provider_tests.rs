@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use omdb_info::{OmdbClient, OmdbConfig};
+use serde_json::json;
+use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::provider_structs::{
+    MovieDetails, MovieProvider, MovieSource, ProviderChain, ProviderError, SearchResults,
+};
+use shared_stuff::shared_structs::MovieDisplay;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use warp::Filter;
+use warp_back::providers::{parse_sources, ProviderConfig};
+
+// Always answers with one movie, or always fails with the given error.
+struct FakeProvider {
+    source: MovieSource,
+    error: Option<ProviderError>,
+    calls: AtomicU32,
+}
+
+impl FakeProvider {
+    fn new(source: MovieSource, error: Option<ProviderError>) -> Arc<Self> {
+        Arc::new(FakeProvider {
+            source,
+            error,
+            calls: AtomicU32::new(0),
+        })
+    }
+
+    fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl MovieProvider for FakeProvider {
+    fn source(&self) -> MovieSource {
+        self.source
+    }
+
+    async fn search(&self, query: &str) -> Result<SearchResults, ProviderError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        let movie = MovieDisplay {
+            movie_id: String::from("tt1"),
+            movie_title: query.to_string(),
+            movie_year: 1981,
+            movie_images: ImageData {
+                url: String::from(""),
+                width: 0,
+                height: 0,
+            },
+            movie_stars: String::from(""),
+            source: self.source,
+        };
+        Ok(SearchResults {
+            movies: vec![movie],
+            exhaustive: true,
+        })
+    }
+
+    async fn details(&self, movie_id: &str) -> Result<MovieDetails, ProviderError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        Ok(MovieDetails {
+            movie_id: movie_id.to_string(),
+            source: self.source,
+            ..MovieDetails::default()
+        })
+    }
+}
+
+#[tokio::test]
+// A failing provider falls back to the next one, and the rest aren't asked.
+async fn chain_falls_back() {
+    let failing = FakeProvider::new(
+        MovieSource::Tmdb,
+        Some(ProviderError::Upstream(String::from("down"))),
+    );
+    let working = FakeProvider::new(MovieSource::Imdb, None);
+    let unused = FakeProvider::new(MovieSource::Omdb, None);
+    let chain = ProviderChain::new(vec![failing.clone(), working.clone(), unused.clone()]);
+    assert_eq!(chain.source(), MovieSource::Tmdb);
+
+    let results = chain.search("raiders").await.unwrap();
+    assert_eq!(results.movies[0].source, MovieSource::Imdb);
+    assert_eq!(
+        (failing.calls(), working.calls(), unused.calls()),
+        (1, 1, 0)
+    );
+
+    let chain = ProviderChain::new(vec![failing.clone()]);
+    assert_eq!(
+        chain.search("raiders").await,
+        Err(ProviderError::Upstream(String::from("down")))
+    );
+}
+
+#[tokio::test]
+// Providers without details are skipped, and only count if nobody else answers.
+async fn chain_details_skip_unsupported() {
+    let imdb = FakeProvider::new(MovieSource::Imdb, Some(ProviderError::Unsupported));
+    let omdb = FakeProvider::new(MovieSource::Omdb, None);
+    let chain = ProviderChain::new(vec![imdb.clone(), omdb.clone()]);
+    let details = chain.details("tt1").await.unwrap();
+    assert_eq!(details.source, MovieSource::Omdb);
+
+    let missing = FakeProvider::new(MovieSource::Tmdb, Some(ProviderError::NotFound));
+    let chain = ProviderChain::new(vec![missing, imdb]);
+    assert_eq!(chain.details("tt1").await, Err(ProviderError::NotFound));
+    assert_eq!(
+        ProviderChain::new(vec![]).details("tt1").await,
+        Err(ProviderError::Unsupported)
+    );
+}
+
+#[test]
+fn sources_are_parsed() {
+    assert_eq!(
+        parse_sources(" TMDB, imdb ").unwrap(),
+        vec![MovieSource::Tmdb, MovieSource::Imdb]
+    );
+    assert!(parse_sources("imdb,netflix").is_err());
+    assert!(parse_sources("imdb,imdb").is_err());
+    assert!(parse_sources("").is_err());
+}
+
+#[test]
+// A provider can't be used without its key.
+fn keys_are_required() {
+    let mut config = ProviderConfig {
+        sources: vec![MovieSource::Omdb, MovieSource::Imdb],
+        omdb_api_key: None,
+        omdb_url: String::from("http://localhost"),
+        tmdb_api_key: None,
+        tmdb_url: String::from("http://localhost"),
+    };
+    assert!(config.build().is_err());
+    config.omdb_api_key = Some(String::from("key"));
+    let chain = config.build().unwrap();
+    assert_eq!(chain.sources(), vec![MovieSource::Omdb, MovieSource::Imdb]);
+}
+
+#[test]
+// Details come from OMDb first once there's a key, whether it's a search provider or not.
+fn details_prefer_omdb() {
+    let mut config = ProviderConfig {
+        sources: vec![MovieSource::Imdb, MovieSource::Omdb],
+        omdb_api_key: Some(String::from("key")),
+        omdb_url: String::from("http://localhost"),
+        tmdb_api_key: None,
+        tmdb_url: String::from("http://localhost"),
+    };
+    let chain = config.build_details().unwrap();
+    assert_eq!(chain.sources(), vec![MovieSource::Omdb, MovieSource::Imdb]);
+    config.sources = vec![MovieSource::Imdb];
+    let chain = config.build_details().unwrap();
+    assert_eq!(chain.sources(), vec![MovieSource::Omdb, MovieSource::Imdb]);
+    config.omdb_api_key = None;
+    let chain = config.build_details().unwrap();
+    assert_eq!(chain.sources(), vec![MovieSource::Imdb]);
+}
+
+// Answers like OMDb does, for one title, and only with the right key.
+fn start_fake_omdb() -> String {
+    let route = warp::get()
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|params: HashMap<String, String>| {
+            let body = match (params.get("apikey"), params.get("i")) {
+                (Some(key), _) if key != "key" => {
+                    json!({"Response": "False", "Error": "Invalid API key!"})
+                }
+                (_, Some(id)) if id == "tt0084787" => json!({
+                    "Title": "The Thing",
+                    "Year": "1982",
+                    "Runtime": "109 min",
+                    "Genre": "Horror, Mystery, Sci-Fi",
+                    "Director": "John Carpenter",
+                    "Actors": "Kurt Russell, Wilford Brimley",
+                    "Plot": "Antarctica.",
+                    "Ratings": [
+                        {"Source": "Internet Movie Database", "Value": "8.2/10"},
+                        {"Source": "Rotten Tomatoes", "Value": "84%"}
+                    ],
+                    "imdbRating": "8.2",
+                    "imdbID": "tt0084787",
+                    "Response": "True"
+                }),
+                (_, None) => json!({"Response": "False", "Error": "Movie not found!"}),
+                _ => json!({"Response": "False", "Error": "Incorrect IMDb ID."}),
+            };
+            warp::reply::json(&body)
+        });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    format!("http://{}/", addr)
+}
+
+#[tokio::test]
+// The client goes wherever it's configured to, and sorts out OMDb's answers.
+async fn omdb_client_details() {
+    let base_url = start_fake_omdb();
+    let client = OmdbClient::new(OmdbConfig {
+        api_key: String::from("key"),
+        base_url: base_url.clone(),
+    });
+    let details = client.details("tt0084787").await.unwrap();
+    assert_eq!(details.title, "The Thing");
+    assert_eq!(details.director, "John Carpenter");
+    assert_eq!(details.genres, vec!["Horror", "Mystery", "Sci-Fi"]);
+    assert_eq!(details.ratings[1].value, "84%");
+    assert_eq!(
+        client.details("tt0000001").await,
+        Err(ProviderError::NotFound)
+    );
+    assert_eq!(client.details("348").await, Err(ProviderError::Unsupported));
+
+    // Nothing for "sta" doesn't mean nothing for "star".
+    let results = client.search("sta").await.unwrap();
+    assert!(results.movies.is_empty());
+    assert!(!results.exhaustive);
+
+    let raw = client.movie("tt0084787").await.unwrap();
+    assert_eq!(raw.Runtime, "109 min");
+
+    let wrong_key = OmdbClient::new(OmdbConfig {
+        api_key: String::from("wrong"),
+        base_url,
+    });
+    assert!(!wrong_key.movie("tt0084787").await.unwrap().found());
+}
@@ -0,0 +1,212 @@
+use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::provider_structs::MovieSource;
+use shared_stuff::shared_structs::MovieDisplay;
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use warp_back::search_cache::{normalize_term, CachedSearch, SearchCache, SearchCacheConfig};
+
+fn test_movie(movie_id: &str, movie_title: &str) -> MovieDisplay {
+    MovieDisplay {
+        movie_id: movie_id.to_string(),
+        movie_title: movie_title.to_string(),
+        movie_year: 1981,
+        movie_images: ImageData {
+            url: String::from("https://m.media-amazon.com/images/test.jpg"),
+            width: 100,
+            height: 100,
+        },
+        movie_stars: String::from(""),
+        source: MovieSource::Imdb,
+    }
+}
+
+fn test_cache(capacity: usize) -> SearchCache {
+    let config = SearchCacheConfig {
+        capacity,
+        ttl_secs: 100,
+        persist: false,
+    };
+    SearchCache::new(config, None, &[MovieSource::Imdb])
+}
+
+// Counts the upstream calls, and answers with every title that has the term in it.
+struct Upstream {
+    calls: AtomicU32,
+    titles: Vec<MovieDisplay>,
+    exhaustive: bool,
+}
+
+impl Upstream {
+    fn new(exhaustive: bool) -> Self {
+        Upstream {
+            calls: AtomicU32::new(0),
+            titles: vec![
+                test_movie("tt1", "Raiders of the Lost Ark"),
+                test_movie("tt2", "Raiders of the Seven Seas"),
+                test_movie("tt3", "The Last Crusade"),
+            ],
+            exhaustive,
+        }
+    }
+
+    async fn fetch(&self, term: String) -> Result<CachedSearch, String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let movies = self
+            .titles
+            .iter()
+            .filter(|movie| movie.movie_title.to_lowercase().contains(&term))
+            .cloned()
+            .collect();
+        Ok(CachedSearch {
+            movies,
+            exhaustive: self.exhaustive,
+        })
+    }
+
+    fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn terms_are_normalized() {
+    assert_eq!(
+        normalize_term("  Raiders   of the\tLost "),
+        Some("raiders of the lost".to_string())
+    );
+    assert_eq!(
+        normalize_term("$$Romeo + Juliet"),
+        Some("romeo + juliet".to_string())
+    );
+    assert_eq!(normalize_term(" *** "), None);
+}
+
+#[tokio::test]
+// Only the first search for a term goes upstream, until it expires.
+async fn hits_and_expiry() {
+    let cache = test_cache(10);
+    let upstream = Upstream::new(false);
+    let first = cache
+        .search("Raiders", 0, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    assert_eq!(first.len(), 2);
+    let again = cache
+        .search("  RAIDERS", 50, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    assert_eq!(again, first);
+    assert_eq!(upstream.calls(), 1);
+    cache
+        .search("raiders", 100, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    assert_eq!(upstream.calls(), 2);
+}
+
+#[tokio::test]
+// A longer term is picked out of a prefix's results, but only if they were everything.
+async fn prefix_reuse() {
+    let cache = test_cache(10);
+    let upstream = Upstream::new(true);
+    cache
+        .search("raiders", 0, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    let longer = cache
+        .search("raiders of the l", 1, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    assert_eq!(longer, vec![test_movie("tt1", "Raiders of the Lost Ark")]);
+    assert_eq!(upstream.calls(), 1);
+
+    let cache = test_cache(10);
+    let upstream = Upstream::new(false);
+    cache
+        .search("raiders", 0, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    cache
+        .search("raiders of the l", 1, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    assert_eq!(upstream.calls(), 2);
+}
+
+#[tokio::test]
+// The least recently used term goes once it's full.
+async fn least_recently_used_is_evicted() {
+    let cache = test_cache(2);
+    let upstream = Upstream::new(false);
+    for (term, now) in [("raiders", 0), ("crusade", 1), ("raiders", 2), ("seas", 3)] {
+        cache
+            .search(term, now, |term| upstream.fetch(term))
+            .await
+            .unwrap();
+    }
+    assert_eq!(cache.len(), 2);
+    assert!(cache.lookup("raiders", 4).is_some());
+    assert!(cache.lookup("crusade", 4).is_none());
+}
+
+#[tokio::test]
+// Searches for the same term at the same time share one upstream call.
+async fn concurrent_searches_are_coalesced() {
+    let cache = test_cache(10);
+    let upstream = Upstream::new(false);
+    let (first, second, third) = tokio::join!(
+        cache.search("raiders", 0, |term| upstream.fetch(term)),
+        cache.search("Raiders ", 0, |term| upstream.fetch(term)),
+        cache.search("crusade", 0, |term| upstream.fetch(term)),
+    );
+    assert_eq!(first.unwrap(), second.unwrap());
+    assert_eq!(third.unwrap().len(), 1);
+    assert_eq!(upstream.calls(), 2);
+}
+
+#[tokio::test]
+// A persisted cache comes back after a restart, without what's expired.
+async fn persisted_cache_survives_restart() {
+    let db_path = std::env::temp_dir().join(format!("search_cache_{}.db", std::process::id()));
+    let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
+    let db = SqlitePool::connect(&db_url).await.unwrap();
+    sqlx::query("CREATE TABLE search_cache (term TEXT NOT NULL PRIMARY KEY, date INTEGER NOT NULL, data TEXT NOT NULL)")
+        .execute(&db)
+        .await
+        .unwrap();
+    let config = SearchCacheConfig {
+        capacity: 10,
+        ttl_secs: 100,
+        persist: true,
+    };
+    let upstream = Upstream::new(false);
+    let cache = SearchCache::new(config.clone(), Some(db.clone()), &[MovieSource::Imdb]);
+    cache
+        .search("raiders", 0, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    cache
+        .search("crusade", 50, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+
+    let restarted = SearchCache::new(config.clone(), Some(db.clone()), &[MovieSource::Imdb]);
+    assert_eq!(restarted.load(120).await.unwrap(), 1);
+    restarted
+        .search("crusade", 120, |term| upstream.fetch(term))
+        .await
+        .unwrap();
+    assert_eq!(upstream.calls(), 2);
+
+    // Nothing found with other providers is used once they've changed.
+    let changed = SearchCache::new(
+        config,
+        Some(db.clone()),
+        &[MovieSource::Tmdb, MovieSource::Imdb],
+    );
+    assert_eq!(changed.load(120).await.unwrap(), 0);
+    db.close().await;
+    std::fs::remove_file(db_path).unwrap();
+}
@@ -0,0 +1,142 @@
+use shared_stuff::event_structs::EventKind;
+use shared_stuff::webhook_structs::{Webhook, WebhookEvent};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use warp::http::StatusCode;
+use warp::Filter;
+use warp_back::webhooks::{deliver, make_payload, resolve_url, sign, SIGNATURE_HEADER};
+
+fn test_webhook(url: String) -> Webhook {
+    Webhook {
+        id: String::from("hook"),
+        group_id: String::from("group"),
+        url,
+        secret: String::from("shh it's a secret"),
+        events: vec![WebhookEvent::RoundFinished],
+        date_created: 0,
+    }
+}
+
+// Answers with the given statuses in turn, then 200, and counts what comes in with a good signature.
+async fn receiver(statuses: Vec<u16>, secret: &str) -> (String, Arc<AtomicU32>) {
+    // The receiver is on this machine, which hooks can't reach otherwise.
+    std::env::set_var("WEBHOOK_ALLOW_PRIVATE", "true");
+    let hits = Arc::new(AtomicU32::new(0));
+    let signed = Arc::new(AtomicU32::new(0));
+    let secret = secret.to_string();
+    let signed_clone = signed.clone();
+    let route = warp::post()
+        .and(warp::header::<String>(SIGNATURE_HEADER))
+        .and(warp::body::bytes())
+        .map(move |signature: String, body: warp::hyper::body::Bytes| {
+            if signature == sign(&secret, &body) {
+                signed_clone.fetch_add(1, Ordering::SeqCst);
+            }
+            let hit = hits.fetch_add(1, Ordering::SeqCst) as usize;
+            let status = statuses.get(hit).copied().unwrap_or(200);
+            warp::reply::with_status(warp::reply(), StatusCode::from_u16(status).unwrap())
+        });
+    let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    (format!("http://{}", addr), signed)
+}
+
+#[test]
+// RFC 4231 test case 2.
+fn signature_matches_known_hmac() {
+    assert_eq!(
+        sign("Jefe", b"what do ya want for nothing?"),
+        "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn secret_vetoes_and_pings_are_filtered() {
+    let webhook = test_webhook(String::new());
+    assert_eq!(
+        WebhookEvent::from_event(&EventKind::MemberAdded {
+            username: String::from("marion@test.com")
+        }),
+        Some(WebhookEvent::MemberJoined)
+    );
+    assert_eq!(
+        WebhookEvent::from_event(&EventKind::SecretVetoCast { movie: None }),
+        None
+    );
+    assert!(webhook.wants(WebhookEvent::Ping));
+    assert!(webhook.wants(WebhookEvent::RoundFinished));
+    assert!(!webhook.wants(WebhookEvent::VetoCast));
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_until_accepted() {
+    let webhook = test_webhook(String::new());
+    let (url, signed) = receiver(vec![500, 503], &webhook.secret).await;
+    let webhook = Webhook { url, ..webhook };
+    let payload = make_payload("group", WebhookEvent::Ping, None);
+    let record = deliver(&webhook, &payload, 5, Duration::from_millis(1)).await;
+    assert_eq!(record.attempts, 3);
+    assert_eq!(record.status, Some(200));
+    assert!(record.succeeded());
+    assert_eq!(signed.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let webhook = test_webhook(String::new());
+    let (url, _) = receiver(vec![410], &webhook.secret).await;
+    let webhook = Webhook { url, ..webhook };
+    let payload = make_payload("group", WebhookEvent::Ping, None);
+    let record = deliver(&webhook, &payload, 5, Duration::from_millis(1)).await;
+    assert_eq!(record.attempts, 1);
+    assert_eq!(record.status, Some(410));
+    assert!(!record.succeeded());
+}
+
+#[tokio::test]
+// Hooks can't be pointed at the server itself or anything else on its network.
+async fn private_addresses_are_refused() {
+    for url in [
+        "http://127.0.0.1:3030/hook",
+        "http://localhost/hook",
+        "http://10.1.2.3/hook",
+        "http://172.16.0.1/hook",
+        "http://192.168.1.1/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://0.0.0.0/hook",
+        "http://100.64.0.1/hook",
+        "http://[::1]/hook",
+        "http://[::]/hook",
+        "http://[fe80::1]/hook",
+        "http://[fd00::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+        "ftp://93.184.216.34/hook",
+        "file:///etc/passwd",
+    ] {
+        assert!(
+            resolve_url(url, false).await.is_err(),
+            "{} was allowed",
+            url
+        );
+    }
+    let (url, addrs) = resolve_url("https://93.184.216.34/hook", false)
+        .await
+        .unwrap();
+    assert_eq!(url.as_str(), "https://93.184.216.34/hook");
+    assert_eq!(addrs, ["93.184.216.34:443".parse().unwrap()]);
+    assert!(resolve_url("http://127.0.0.1:3030/hook", true)
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+// The url is checked again when it's sent to, not just when it's added.
+async fn deliveries_to_bad_urls_are_refused() {
+    let webhook = test_webhook(String::from("ftp://93.184.216.34/hook"));
+    let payload = make_payload("group", WebhookEvent::Ping, None);
+    let record = deliver(&webhook, &payload, 2, Duration::from_millis(1)).await;
+    assert_eq!(record.status, None);
+    assert_eq!(record.error.as_deref(), Some("ftp urls can't be webhooks"));
+    assert!(!record.succeeded());
+}
@@ -22,7 +22,9 @@ use pages::all_groups::AllGroups;
 use pages::group::Group;
 use pages::home::Home;
 use pages::login::Login;
+use pages::notifications::NotificationBell;
 use pages::register::Register;
+use pages::stats::Stats;
 use pages::system::System;
 use pages::user::User;
 
@@ -36,11 +38,19 @@ lazy_static! {
         env!("CORS_ORIGIN")
     };
     pub static ref SEARCH_URL: String = format!("{}/search", *ROOT_URL);
+    pub static ref GET_MOVIE_DETAILS_URL: String = format!("{}/movie", *ROOT_URL);
     pub static ref LOGIN_URL: String = format!("{}/login", *ROOT_URL);
     pub static ref REGISTER_URL: String = format!("{}/register", *ROOT_URL);
     pub static ref ACCESS_URL: String = format!("{}/access_auth", *ROOT_URL);
     pub static ref REFRESH_URL: String = format!("{}/refresh_auth", *ROOT_URL);
-    pub static ref UPDATE_GROUP_DATA_URL: String = format!("{}/update_group_data", *ROOT_URL);
+    pub static ref SYSTEM_ACTION_URL: String = format!("{}/system_action", *ROOT_URL);
+    pub static ref UNDO_VETO_URL: String = format!("{}/undo_veto", *ROOT_URL);
+    pub static ref PLAN_ROUND_URL: String = format!("{}/plan_round", *ROOT_URL);
+    pub static ref BACKLOG_ACTION_URL: String = format!("{}/backlog_action", *ROOT_URL);
+    pub static ref GET_GROUP_HISTORY_URL: String = format!("{}/get_group_history", *ROOT_URL);
+    pub static ref RATE_ROUND_URL: String = format!("{}/rate_round", *ROOT_URL);
+    pub static ref GET_GROUP_RATINGS_URL: String = format!("{}/get_group_ratings", *ROOT_URL);
+    pub static ref GET_GROUP_STATS_URL: String = format!("{}/get_group_stats", *ROOT_URL);
     //pub static ref GET_GROUP_MOVIES_URL: String = format!("{}/get_group_movies", *ROOT_URL);
     //pub static ref SAVE_GROUP_MOVIES_URL: String = format!("{}/save_group_movies", *ROOT_URL);
     pub static ref CREATE_GROUP_URL: String = format!("{}/create_group", *ROOT_URL);
@@ -49,6 +59,17 @@ lazy_static! {
     pub static ref GET_ALL_GROUPS_URL: String = format!("{}/get_all_groups", *ROOT_URL);
     pub static ref GET_GROUP_DATA_URL: String = format!("{}/get_group_data", *ROOT_URL);
     pub static ref GET_USER_PROFILE: String = format!("{}/get_user_profile", *ROOT_URL);
+    pub static ref GET_WATCHLIST_URL: String = format!("{}/get_watchlist", *ROOT_URL);
+    pub static ref WATCHLIST_ACTION_URL: String = format!("{}/watchlist_action", *ROOT_URL);
+    pub static ref GET_EMAIL_PREFS_URL: String = format!("{}/get_email_prefs", *ROOT_URL);
+    pub static ref SET_EMAIL_PREFS_URL: String = format!("{}/set_email_prefs", *ROOT_URL);
+    pub static ref GET_NOTIFICATIONS_URL: String = format!("{}/get_notifications", *ROOT_URL);
+    pub static ref MARK_NOTIFICATIONS_READ_URL: String = format!("{}/mark_notifications_read", *ROOT_URL);
+    pub static ref GET_WEBHOOKS_URL: String = format!("{}/get_webhooks", *ROOT_URL);
+    pub static ref ADD_WEBHOOK_URL: String = format!("{}/add_webhook", *ROOT_URL);
+    pub static ref DELETE_WEBHOOK_URL: String = format!("{}/delete_webhook", *ROOT_URL);
+    pub static ref PING_WEBHOOK_URL: String = format!("{}/ping_webhook", *ROOT_URL);
+    pub static ref GET_WEBHOOK_DELIVERIES_URL: String = format!("{}/get_webhook_deliveries", *ROOT_URL);
 }
 
 #[derive(Debug, Clone, PartialEq, Routable)]
@@ -65,8 +86,13 @@ pub enum Route {
     User { username: String },
     #[at("/group/:group_id")]
     Group { group_id: String },
+    #[at("/system/:group_id/:round_id")]
+    System { group_id: String, round_id: String },
+    /// Whichever round is active at the time.
     #[at("/system/:group_id")]
-    System { group_id: String },
+    ActiveSystem { group_id: String },
+    #[at("/stats/:group_id")]
+    Stats { group_id: String },
     #[at("/404")]
     NotFound,
 }
@@ -159,6 +185,8 @@ impl Component for App {
 }
 
 impl App {
+    // `html!` binds the bell's empty props to a unit `let`.
+    #[allow(clippy::let_unit_value)]
     pub fn new_nav_bar(&self, ctx: &Context<Self>) -> Html {
         html! {
             <nav class="navbar is-primary is-fixed-top" role="navigation" aria-label="main navigation">
@@ -189,6 +217,7 @@ impl App {
                 </div>
                 </div>
                 <div class="navbar-end">
+                <NotificationBell />
                 <div class="navbar-item">
                 {
                 match LocalStorage::raw().get("username").expect("storage issue") {
@@ -223,7 +252,9 @@ fn switch(routes: &Route) -> Html {
         Route::Register => html!{<Register />},
         Route::User { username }=> html!{<User username={username.clone()} />},
         Route::Group { group_id } => html!{<Group id={group_id.clone()}/>},
-        Route::System { group_id } => html!{<System id={group_id.clone()}/>},
+        Route::System { group_id, round_id } => html!{<System id={group_id.clone()} round_id={round_id.clone()}/>},
+        Route::ActiveSystem { group_id } => html!{<System id={group_id.clone()} round_id={String::from("")}/>},
+        Route::Stats { group_id } => html!{<Stats id={group_id.clone()}/>},
         //TODO! something for bad urls?
         Route::NotFound => html!{},
     }}
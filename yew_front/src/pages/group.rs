@@ -1,12 +1,22 @@
-use crate::auth_requests::post_route_with_auth;
-use crate::shared_requests::request_get_group_data;
-use crate::{ADD_USER_URL, LEAVE_GROUP_URL};
-use anyhow::Result;
+use crate::auth_requests::{get_route_with_auth, post_route_with_auth};
+use crate::pages::system::request_system_action;
+use crate::shared_requests::{request_get_group_data, request_get_group_history};
+use crate::{
+    ADD_USER_URL, ADD_WEBHOOK_URL, DELETE_WEBHOOK_URL, GET_GROUP_RATINGS_URL,
+    GET_WEBHOOK_DELIVERIES_URL, GET_WEBHOOKS_URL, LEAVE_GROUP_URL, PING_WEBHOOK_URL, RATE_ROUND_URL,
+};
+use anyhow::{anyhow, Result};
+use gloo_storage::{LocalStorage, Storage};
 use reqwasm::http::Response;
 use shared_stuff::auth_structs::ErrorMessage;
 // use gloo_storage::Result;
 use shared_stuff::db_structs::{DBGroupStruct, GroupData};
-use shared_stuff::group_structs::AddUser;
+use shared_stuff::group_structs::{AddUser, GroupRules, TimeoutAction};
+use shared_stuff::history_structs::{HistoryPage, RatingForm, RatingsSummary, RoundHistory};
+use shared_stuff::selection_structs::TurnOrder;
+use shared_stuff::system_structs::SystemAction;
+use shared_stuff::webhook_structs::{DeliveryRecord, WebhookEvent, WebhookForm, WebhookInfo};
+use std::collections::HashMap;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
@@ -35,6 +45,86 @@ pub async fn get_add_new_user_resp_status(group_id: String, add_user: String) ->
     }
 }
 
+pub async fn request_rate_round(
+    group_id: String,
+    round_id: String,
+    rating_form: RatingForm,
+) -> Result<RoundHistory> {
+    let url = format!("{}/{}/{}", *RATE_ROUND_URL, group_id, round_id);
+    let json_body = serde_json::to_string(&rating_form)?;
+    let resp = post_route_with_auth(&url, json_body).await?;
+    log::info!("request_rate_round resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let history: RoundHistory = resp.json().await?;
+            Ok(history)
+        }
+        _ => {
+            let resp_body: ErrorMessage = resp.json().await?;
+            Err(anyhow!(resp_body.message))
+        }
+    }
+}
+
+pub async fn request_get_group_ratings(group_id: String) -> Result<RatingsSummary> {
+    let url = format!("{}/{}", *GET_GROUP_RATINGS_URL, group_id);
+    let resp = get_route_with_auth(&url).await?;
+    let ratings_summary: RatingsSummary = resp.json().await?;
+    Ok(ratings_summary)
+}
+
+async fn webhooks_from_resp(resp: Response) -> Result<Vec<WebhookInfo>> {
+    match resp.status() {
+        200 => {
+            let webhooks: Vec<WebhookInfo> = resp.json().await?;
+            Ok(webhooks)
+        }
+        _ => {
+            let resp_body: ErrorMessage = resp.json().await?;
+            Err(anyhow!(resp_body.message))
+        }
+    }
+}
+
+pub async fn request_get_webhooks(group_id: String) -> Result<Vec<WebhookInfo>> {
+    let url = format!("{}/{}", *GET_WEBHOOKS_URL, group_id);
+    let resp = get_route_with_auth(&url).await?;
+    webhooks_from_resp(resp).await
+}
+
+pub async fn request_add_webhook(
+    group_id: String,
+    webhook_form: WebhookForm,
+) -> Result<Vec<WebhookInfo>> {
+    let url = format!("{}/{}", *ADD_WEBHOOK_URL, group_id);
+    let json_body = serde_json::to_string(&webhook_form)?;
+    let resp = post_route_with_auth(&url, json_body).await?;
+    webhooks_from_resp(resp).await
+}
+
+pub async fn request_delete_webhook(group_id: String, webhook_id: String) -> Result<Vec<WebhookInfo>> {
+    let url = format!("{}/{}/{}", *DELETE_WEBHOOK_URL, group_id, webhook_id);
+    let resp = post_route_with_auth(&url, String::from("")).await?;
+    webhooks_from_resp(resp).await
+}
+
+pub async fn request_ping_webhook(group_id: String, webhook_id: String) -> Result<DeliveryRecord> {
+    let url = format!("{}/{}/{}", *PING_WEBHOOK_URL, group_id, webhook_id);
+    let resp = post_route_with_auth(&url, String::from("")).await?;
+    let record: DeliveryRecord = resp.json().await?;
+    Ok(record)
+}
+
+pub async fn request_get_webhook_deliveries(
+    group_id: String,
+    webhook_id: String,
+) -> Result<Vec<DeliveryRecord>> {
+    let url = format!("{}/{}/{}", *GET_WEBHOOK_DELIVERIES_URL, group_id, webhook_id);
+    let resp = get_route_with_auth(&url).await?;
+    let deliveries: Vec<DeliveryRecord> = resp.json().await?;
+    Ok(deliveries)
+}
+
 pub async fn request_leave_group(group_id: String) -> Result<()> {
     let uri = LEAVE_GROUP_URL.to_string();
     let url = format!("{}/{}", uri, group_id);
@@ -49,12 +139,31 @@ pub struct Props {
     pub id: String,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GroupTab {
+    Info,
+    History,
+    Webhooks,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Group {
+    pub username: String,
     pub group_id: String,
     pub group_data: Option<GroupData>,
     pub add_user: String,
     pub add_user_status: String,
+    pub tab: GroupTab,
+    pub history: Option<HistoryPage>,
+    pub ratings_summary: Option<RatingsSummary>,
+    pub rating_drafts: HashMap<String, RatingForm>,
+    pub rules: GroupRules,
+    pub rules_status: String,
+    pub webhooks: Option<Vec<WebhookInfo>>,
+    pub webhook_form: WebhookForm,
+    pub webhook_status: String,
+    /// Delivery logs that have been opened, by webhook id.
+    pub deliveries: HashMap<String, Vec<DeliveryRecord>>,
 }
 pub enum GroupMsg {
     Noop,
@@ -64,6 +173,40 @@ pub enum GroupMsg {
     UpdateAddUserStatus(String),
     AddUser,
     Leave,
+    SetTab(GroupTab),
+    GetHistory(u32),
+    UpdateHistory(HistoryPage),
+    SetRatingScore(String, InputEvent),
+    SetRatingComment(String, InputEvent),
+    SubmitRating(String),
+    UpdateRatedRound(RoundHistory),
+    GetRatingsSummary,
+    UpdateRatingsSummary(RatingsSummary),
+    SetMinMovies(InputEvent),
+    SetMaxMovies(InputEvent),
+    SetVetoesPerTurn(InputEvent),
+    ToggleVetoOwnMovie,
+    ToggleStartBeforeAllAdded,
+    ToggleBacklogLosers,
+    SetAddingDeadline(InputEvent),
+    SetTurnDeadline(InputEvent),
+    SetTurnTimeout(TimeoutAction),
+    SetTurnOrder(TurnOrder),
+    MoveTurnUp(usize),
+    SaveRules,
+    RulesSaved(DBGroupStruct),
+    UpdateRulesStatus(String),
+    GetWebhooks,
+    UpdateWebhooks(Vec<WebhookInfo>),
+    SetWebhookUrl(InputEvent),
+    SetWebhookSecret(InputEvent),
+    ToggleWebhookEvent(WebhookEvent),
+    AddWebhook,
+    DeleteWebhook(String),
+    PingWebhook(String),
+    GetDeliveries(String),
+    UpdateDeliveries(String, Vec<DeliveryRecord>),
+    UpdateWebhookStatus(String),
     Error(String),
 }
 
@@ -72,11 +215,30 @@ impl Component for Group {
     type Properties = Props;
     fn create(ctx: &Context<Self>) -> Self {
         ctx.link().send_message(GroupMsg::GetGroupData);
+        let username = LocalStorage::raw()
+            .get("username")
+            .expect("storage error")
+            .unwrap_or_default();
         Self {
+            username,
             group_id: ctx.props().id.clone(),
             group_data: None,
             add_user: String::from(""),
             add_user_status: String::from("ok"),
+            tab: GroupTab::Info,
+            history: None,
+            ratings_summary: None,
+            rating_drafts: HashMap::new(),
+            rules: GroupRules::default(),
+            rules_status: String::from(""),
+            webhooks: None,
+            webhook_form: WebhookForm {
+                url: String::from(""),
+                secret: String::from(""),
+                events: vec![WebhookEvent::RoundStarted, WebhookEvent::RoundFinished],
+            },
+            webhook_status: String::from(""),
+            deliveries: HashMap::new(),
         }
     }
 
@@ -96,6 +258,7 @@ impl Component for Group {
             }),
 
             UpdateGroupData(group_struct) => {
+                self.rules = group_struct.group_data.rules.clone();
                 self.group_data = Some(group_struct.group_data);
                 self.group_id = group_struct.id;
             }
@@ -122,6 +285,247 @@ impl Component for Group {
                 }
             }
 
+            SetTab(tab) => {
+                if tab == GroupTab::History && self.history.is_none() {
+                    link_clone.send_message(GroupMsg::GetHistory(0));
+                    link_clone.send_message(GroupMsg::GetRatingsSummary);
+                }
+                if tab == GroupTab::Webhooks && self.webhooks.is_none() {
+                    link_clone.send_message(GroupMsg::GetWebhooks);
+                }
+                self.tab = tab;
+            }
+
+            GetHistory(page) => link_clone.send_future(async move {
+                match request_get_group_history(group_id, page).await {
+                    Ok(history_page) => GroupMsg::UpdateHistory(history_page),
+                    Err(e) => GroupMsg::Error(e.to_string()),
+                }
+            }),
+
+            UpdateHistory(history_page) => {
+                self.history = Some(history_page);
+            }
+
+            SetRatingScore(round_id, text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    let draft = self.rating_drafts.entry(round_id).or_default();
+                    draft.score = elem.value().parse::<u8>().unwrap_or_default();
+                }
+            }
+
+            SetRatingComment(round_id, text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    let draft = self.rating_drafts.entry(round_id).or_default();
+                    draft.comment = Some(elem.value());
+                }
+            }
+
+            SubmitRating(round_id) => {
+                let rating_form = self.rating_drafts.get(&round_id).cloned().unwrap_or_default();
+                link_clone.send_future(async move {
+                    match request_rate_round(group_id, round_id, rating_form).await {
+                        Ok(history) => GroupMsg::UpdateRatedRound(history),
+                        Err(e) => GroupMsg::Error(e.to_string()),
+                    }
+                })
+            }
+
+            UpdateRatedRound(rated_round) => {
+                self.rating_drafts.remove(&rated_round.id);
+                if let Some(history_page) = self.history.as_mut() {
+                    for history in history_page.entries.iter_mut() {
+                        if history.id == rated_round.id {
+                            *history = rated_round.clone();
+                        }
+                    }
+                }
+                link_clone.send_message(GroupMsg::GetRatingsSummary);
+            }
+
+            GetRatingsSummary => link_clone.send_future(async move {
+                match request_get_group_ratings(group_id).await {
+                    Ok(ratings_summary) => GroupMsg::UpdateRatingsSummary(ratings_summary),
+                    Err(e) => GroupMsg::Error(e.to_string()),
+                }
+            }),
+
+            UpdateRatingsSummary(ratings_summary) => {
+                self.ratings_summary = Some(ratings_summary);
+            }
+
+            SetMinMovies(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.rules.min_movies_per_member = elem.value().parse::<u32>().unwrap_or_default();
+                }
+            }
+
+            // Left empty for no limit.
+            SetMaxMovies(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.rules.max_movies_per_member = elem.value().parse::<u32>().ok();
+                }
+            }
+
+            SetVetoesPerTurn(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.rules.vetoes_per_turn = elem.value().parse::<u32>().unwrap_or(1);
+                }
+            }
+
+            ToggleVetoOwnMovie => {
+                self.rules.veto_own_movie = !self.rules.veto_own_movie;
+            }
+
+            ToggleStartBeforeAllAdded => {
+                self.rules.start_before_all_added = !self.rules.start_before_all_added;
+            }
+
+            ToggleBacklogLosers => {
+                self.rules.backlog_losers = !self.rules.backlog_losers;
+            }
+
+            // Entered in minutes, left empty for no deadline.
+            SetAddingDeadline(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.rules.adding_deadline_secs =
+                        elem.value().parse::<i64>().ok().map(|minutes| minutes * 60);
+                }
+            }
+
+            SetTurnDeadline(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.rules.turn_deadline_secs =
+                        elem.value().parse::<i64>().ok().map(|minutes| minutes * 60);
+                }
+            }
+
+            SetTurnTimeout(on_turn_timeout) => {
+                self.rules.on_turn_timeout = on_turn_timeout;
+            }
+
+            SetTurnOrder(turn_order) => {
+                self.rules.turn_order = turn_order;
+            }
+
+            MoveTurnUp(index) => {
+                if let TurnOrder::Fixed(order) = &mut self.rules.turn_order {
+                    if index > 0 && index < order.len() {
+                        order.swap(index - 1, index);
+                    }
+                }
+            }
+
+            SaveRules => {
+                let action = SystemAction::SetRules(self.rules.clone());
+                let round_id = self
+                    .group_data
+                    .as_ref()
+                    .map(|group_data| group_data.round_id.clone())
+                    .unwrap_or_default();
+                link_clone.send_future(async move {
+                    match request_system_action(group_id, round_id, action).await {
+                        Ok(group_struct) => GroupMsg::RulesSaved(group_struct),
+                        Err(e) => GroupMsg::UpdateRulesStatus(e.to_string()),
+                    }
+                })
+            }
+
+            RulesSaved(group_struct) => {
+                self.rules_status = String::from("saved");
+                link_clone.send_message(GroupMsg::UpdateGroupData(group_struct));
+            }
+
+            UpdateRulesStatus(rules_status) => {
+                self.rules_status = rules_status;
+            }
+
+            GetWebhooks => link_clone.send_future(async move {
+                match request_get_webhooks(group_id).await {
+                    Ok(webhooks) => GroupMsg::UpdateWebhooks(webhooks),
+                    Err(e) => GroupMsg::UpdateWebhookStatus(e.to_string()),
+                }
+            }),
+
+            UpdateWebhooks(webhooks) => {
+                self.webhooks = Some(webhooks);
+            }
+
+            SetWebhookUrl(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.webhook_form.url = elem.value();
+                }
+            }
+
+            SetWebhookSecret(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.webhook_form.secret = elem.value();
+                }
+            }
+
+            ToggleWebhookEvent(event) => {
+                let events = &mut self.webhook_form.events;
+                match events.contains(&event) {
+                    true => events.retain(|wanted| wanted != &event),
+                    false => events.push(event),
+                }
+            }
+
+            AddWebhook => {
+                let webhook_form = self.webhook_form.clone();
+                link_clone.send_future(async move {
+                    match request_add_webhook(group_id, webhook_form).await {
+                        Ok(webhooks) => GroupMsg::UpdateWebhooks(webhooks),
+                        Err(e) => GroupMsg::UpdateWebhookStatus(e.to_string()),
+                    }
+                })
+            }
+
+            DeleteWebhook(webhook_id) => {
+                self.deliveries.remove(&webhook_id);
+                link_clone.send_future(async move {
+                    match request_delete_webhook(group_id, webhook_id).await {
+                        Ok(webhooks) => GroupMsg::UpdateWebhooks(webhooks),
+                        Err(e) => GroupMsg::UpdateWebhookStatus(e.to_string()),
+                    }
+                })
+            }
+
+            PingWebhook(webhook_id) => {
+                let link = link_clone.clone();
+                link_clone.send_future(async move {
+                    match request_ping_webhook(group_id, webhook_id.clone()).await {
+                        Ok(record) => {
+                            let status = match record.succeeded() {
+                                true => String::from("ping delivered"),
+                                false => format!(
+                                    "ping failed: {}",
+                                    record.error.unwrap_or_default()
+                                ),
+                            };
+                            link.send_message(GroupMsg::GetDeliveries(webhook_id));
+                            GroupMsg::UpdateWebhookStatus(status)
+                        }
+                        Err(e) => GroupMsg::UpdateWebhookStatus(e.to_string()),
+                    }
+                })
+            }
+
+            GetDeliveries(webhook_id) => link_clone.send_future(async move {
+                match request_get_webhook_deliveries(group_id, webhook_id.clone()).await {
+                    Ok(deliveries) => GroupMsg::UpdateDeliveries(webhook_id, deliveries),
+                    Err(e) => GroupMsg::UpdateWebhookStatus(e.to_string()),
+                }
+            }),
+
+            UpdateDeliveries(webhook_id, deliveries) => {
+                self.deliveries.insert(webhook_id, deliveries);
+            }
+
+            UpdateWebhookStatus(webhook_status) => {
+                self.webhook_status = webhook_status;
+            }
+
             Leave => ctx.link().send_future(async move {
                 let _resp = request_leave_group(group_id).await;
                 GroupMsg::Noop
@@ -142,6 +546,7 @@ impl Component for Group {
         html! {
             <div>
             { self.view_group_id(ctx) }
+            { self.view_tabs(ctx) }
             { self.user_customized_view(ctx) }
             </div>
 
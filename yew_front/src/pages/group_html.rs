@@ -1,6 +1,12 @@
-use crate::pages::group::{Group, GroupMsg};
+use crate::pages::group::{Group, GroupMsg, GroupTab};
+use shared_stuff::group_structs::TimeoutAction;
+use shared_stuff::selection_structs::TurnOrder;
+use crate::pages::system_html::{image_processing, view_comments};
+use crate::utils::format_date;
 use crate::CORS_ORIGIN;
 use shared_stuff::db_structs::GroupData;
+use shared_stuff::history_structs::RoundHistory;
+use shared_stuff::webhook_structs::{DeliveryRecord, WebhookEvent, WebhookInfo};
 use yew::prelude::*;
 
 impl Group {
@@ -10,12 +16,41 @@ impl Group {
         }
     }
 
+    pub fn view_tabs(&self, ctx: &Context<Self>) -> Html {
+        let tab_class = |tab: GroupTab| if self.tab == tab { "is-active" } else { "" };
+        html! {
+            <div class="tabs">
+                <ul>
+                    <li class={tab_class(GroupTab::Info)}>
+                        <a onclick={ctx.link().callback(|_| GroupMsg::SetTab(GroupTab::Info))}>{"Group"}</a>
+                    </li>
+                    <li class={tab_class(GroupTab::History)}>
+                        <a onclick={ctx.link().callback(|_| GroupMsg::SetTab(GroupTab::History))}>{"History"}</a>
+                    </li>
+                    {
+                        match self.is_owner() {
+                            true => html! {
+                                <li class={tab_class(GroupTab::Webhooks)}>
+                                    <a onclick={ctx.link().callback(|_| GroupMsg::SetTab(GroupTab::Webhooks))}>{"Webhooks"}</a>
+                                </li>
+                            },
+                            false => html! {},
+                        }
+                    }
+                </ul>
+            </div>
+        }
+    }
+
     pub fn user_customized_view(&self, ctx: &Context<Self>) -> Html {
         match &self.group_data {
+            Some(_) if self.tab == GroupTab::History => self.view_history(ctx),
+            Some(_) if self.tab == GroupTab::Webhooks => self.view_webhooks(ctx),
             Some(group_data) => {
                 html! {
                     <div>
                     { self.view_group_data(ctx, &group_data) }
+                    { self.view_rules_editor(ctx) }
                     { self.view_add_user_to_group(ctx) }
                     { self.view_leave_group(ctx) }
                     </div>
@@ -31,6 +66,7 @@ impl Group {
 
     fn view_group_data(&self, _ctx: &Context<Self>, group_data: &GroupData) -> Html {
         let system_url = format!("{}/system/{}", CORS_ORIGIN.to_string(), self.group_id);
+        let stats_url = format!("{}/stats/{}", *CORS_ORIGIN, self.group_id);
         html! {
             <div>
                 <p>{format!("group data is:")}</p>
@@ -42,6 +78,9 @@ impl Group {
                     {"system url: "}
                     <a href= {system_url.clone()}>{system_url}</a>
                 </li>
+                <li>
+                    <a href={stats_url}>{"Group stats"}</a>
+                </li>
             </div>
         }
     }
@@ -66,6 +105,296 @@ impl Group {
         }
     }
 
+    // Only editable between rounds, the server turns it down otherwise.
+    fn view_rules_editor(&self, ctx: &Context<Self>) -> Html {
+        let max_movies = self
+            .rules
+            .max_movies_per_member
+            .map(|max| max.to_string())
+            .unwrap_or_default();
+        let minutes = |secs: Option<i64>| secs.map(|secs| (secs / 60).to_string()).unwrap_or_default();
+        let skip = self.rules.on_turn_timeout == TimeoutAction::Skip;
+        html! {
+        <div class="box">
+            <h1> {"Group Rules"} </h1>
+            <div class="field">
+                <label class="label">{"Minimum movies per member"}</label>
+                <input
+                    class="input"
+                    type="number"
+                    min="0"
+                    value={self.rules.min_movies_per_member.to_string()}
+                    oninput={ctx.link().callback(GroupMsg::SetMinMovies)}
+                />
+            </div>
+            <div class="field">
+                <label class="label">{"Maximum movies per member (empty for no limit)"}</label>
+                <input
+                    class="input"
+                    type="number"
+                    min="1"
+                    value={max_movies}
+                    oninput={ctx.link().callback(GroupMsg::SetMaxMovies)}
+                />
+            </div>
+            <div class="field">
+                <label class="label">{"Vetoes per turn"}</label>
+                <input
+                    class="input"
+                    type="number"
+                    min="1"
+                    value={self.rules.vetoes_per_turn.to_string()}
+                    oninput={ctx.link().callback(GroupMsg::SetVetoesPerTurn)}
+                />
+            </div>
+            <label class="checkbox">
+                <input
+                    type="checkbox"
+                    checked={self.rules.veto_own_movie}
+                    onclick={ctx.link().callback(|_| GroupMsg::ToggleVetoOwnMovie)}
+                />
+                {" Members can veto their own movies"}
+            </label>
+            <br/>
+            <label class="checkbox">
+                <input
+                    type="checkbox"
+                    checked={self.rules.start_before_all_added}
+                    onclick={ctx.link().callback(|_| GroupMsg::ToggleStartBeforeAllAdded)}
+                />
+                {" Start the round before everyone has added a movie"}
+            </label>
+            <br/>
+            <label class="checkbox">
+                <input
+                    type="checkbox"
+                    checked={self.rules.backlog_losers}
+                    onclick={ctx.link().callback(|_| GroupMsg::ToggleBacklogLosers)}
+                />
+                {" Put the movies that lose a round on the group backlog"}
+            </label>
+            <div class="field">
+                <label class="label">{"Adding deadline in minutes, from the first movie (empty for none)"}</label>
+                <input
+                    class="input"
+                    type="number"
+                    min="1"
+                    value={minutes(self.rules.adding_deadline_secs)}
+                    oninput={ctx.link().callback(GroupMsg::SetAddingDeadline)}
+                />
+            </div>
+            <div class="field">
+                <label class="label">{"Veto turn deadline in minutes (empty for none)"}</label>
+                <input
+                    class="input"
+                    type="number"
+                    min="1"
+                    value={minutes(self.rules.turn_deadline_secs)}
+                    oninput={ctx.link().callback(GroupMsg::SetTurnDeadline)}
+                />
+            </div>
+            <div class="buttons has-addons">
+                <button
+                    class={if skip { "button is-selected is-info" } else { "button" }}
+                    onclick={ctx.link().callback(|_| GroupMsg::SetTurnTimeout(TimeoutAction::Skip))}>
+                    { "Skip late turns" }
+                </button>
+                <button
+                    class={if skip { "button" } else { "button is-selected is-info" }}
+                    onclick={ctx.link().callback(|_| GroupMsg::SetTurnTimeout(TimeoutAction::AutoVeto))}>
+                    { "Auto-veto on late turns" }
+                </button>
+            </div>
+            { self.view_turn_order_editor(ctx) }
+            <button
+                class="button is-primary"
+                onclick={ctx.link().callback(|_| GroupMsg::SaveRules)}>
+                { "Save Rules" }
+            </button>
+            <p>{&self.rules_status}</p>
+        </div>
+        }
+    }
+
+    // Picking a fixed order starts from the members in alphabetical order.
+    fn view_turn_order_editor(&self, ctx: &Context<Self>) -> Html {
+        let mut members = self
+            .group_data
+            .as_ref()
+            .map(|group_data| group_data.members.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        members.sort();
+        let policies = [
+            ("Shuffled", TurnOrder::Shuffled),
+            ("Fixed", TurnOrder::Fixed(members)),
+            ("Loser first", TurnOrder::LoserFirst),
+            ("Round robin", TurnOrder::RoundRobin),
+        ];
+        html! {
+            <div class="field">
+                <label class="label">{"Turn order"}</label>
+                <div class="buttons has-addons">
+                {
+                    policies
+                        .into_iter()
+                        .map(|(label, policy)| {
+                            let selected = std::mem::discriminant(&policy)
+                                == std::mem::discriminant(&self.rules.turn_order);
+                            html! {
+                                <button
+                                    class={if selected { "button is-selected is-info" } else { "button" }}
+                                    disabled={selected}
+                                    onclick={ctx.link().callback(move |_| GroupMsg::SetTurnOrder(policy.clone()))}>
+                                    { label }
+                                </button>
+                            }
+                        })
+                        .collect::<Html>()
+                }
+                </div>
+                {
+                    match &self.rules.turn_order {
+                        TurnOrder::Fixed(order) => html! {
+                            <ol>
+                            {
+                                order
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(index, member)| html! {
+                                        <li>
+                                            {member}
+                                            <button
+                                                class="button is-small ml-2"
+                                                disabled={index == 0}
+                                                onclick={ctx.link().callback(move |_| GroupMsg::MoveTurnUp(index))}>
+                                                {"Up"}
+                                            </button>
+                                        </li>
+                                    })
+                                    .collect::<Html>()
+                            }
+                            </ol>
+                        },
+                        _ => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+
+    fn is_owner(&self) -> bool {
+        match &self.group_data {
+            Some(group_data) => group_data.is_owner(&self.username),
+            None => false,
+        }
+    }
+
+    fn view_webhooks(&self, ctx: &Context<Self>) -> Html {
+        let events = [
+            ("Round started", WebhookEvent::RoundStarted),
+            ("Veto cast", WebhookEvent::VetoCast),
+            ("Round finished", WebhookEvent::RoundFinished),
+            ("Member joined", WebhookEvent::MemberJoined),
+        ];
+        html! {
+        <div class="box">
+            <h1> {"Webhooks"} </h1>
+            <p>
+                {"Each delivery is signed with the secret, the "}
+                <code>{"X-Webhook-Signature"}</code>
+                {" header is "}
+                <code>{"sha256="}</code>
+                {" and the hex HMAC-SHA256 of the body."}
+            </p>
+            <div class="field">
+                <label class="label">{"Url"}</label>
+                <input
+                    class="input"
+                    type="url"
+                    placeholder="https://example.com/hook"
+                    value={self.webhook_form.url.clone()}
+                    oninput={ctx.link().callback(GroupMsg::SetWebhookUrl)}
+                />
+            </div>
+            <div class="field">
+                <label class="label">{"Secret (at least 8 characters)"}</label>
+                <input
+                    class="input"
+                    type="password"
+                    value={self.webhook_form.secret.clone()}
+                    oninput={ctx.link().callback(GroupMsg::SetWebhookSecret)}
+                />
+            </div>
+            {
+                events
+                    .into_iter()
+                    .map(|(label, event)| html! {
+                        <label class="checkbox mr-3">
+                            <input
+                                type="checkbox"
+                                checked={self.webhook_form.events.contains(&event)}
+                                onclick={ctx.link().callback(move |_| GroupMsg::ToggleWebhookEvent(event))}
+                            />
+                            {format!(" {}", label)}
+                        </label>
+                    })
+                    .collect::<Html>()
+            }
+            <br/>
+            <button
+                class="button is-primary"
+                onclick={ctx.link().callback(|_| GroupMsg::AddWebhook)}>
+                { "Add Webhook" }
+            </button>
+            <p>{&self.webhook_status}</p>
+            {
+                match &self.webhooks {
+                    Some(webhooks) if !webhooks.is_empty() => webhooks
+                        .iter()
+                        .map(|webhook| self.view_webhook(ctx, webhook))
+                        .collect::<Html>(),
+                    Some(_) => html! { <p>{"No webhooks yet."}</p> },
+                    None => html! {},
+                }
+            }
+        </div>
+        }
+    }
+
+    fn view_webhook(&self, ctx: &Context<Self>, webhook: &WebhookInfo) -> Html {
+        let (ping_id, log_id, delete_id) = (webhook.id.clone(), webhook.id.clone(), webhook.id.clone());
+        html! {
+            <div class="box">
+                <p><strong>{&webhook.url}</strong></p>
+                <p>{format!("Events: {:?}", webhook.events)}</p>
+                <p>{format!("Added {}", format_date(webhook.date_created))}</p>
+                <div class="buttons">
+                    <button
+                        class="button is-small"
+                        onclick={ctx.link().callback(move |_| GroupMsg::PingWebhook(ping_id.clone()))}>
+                        { "Send Ping" }
+                    </button>
+                    <button
+                        class="button is-small"
+                        onclick={ctx.link().callback(move |_| GroupMsg::GetDeliveries(log_id.clone()))}>
+                        { "Delivery Log" }
+                    </button>
+                    <button
+                        class="button is-small is-danger"
+                        onclick={ctx.link().callback(move |_| GroupMsg::DeleteWebhook(delete_id.clone()))}>
+                        { "Delete" }
+                    </button>
+                </div>
+                {
+                    match self.deliveries.get(&webhook.id) {
+                        Some(deliveries) => view_deliveries(deliveries),
+                        None => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+
     pub fn view_leave_group(&self, ctx: &Context<Self>) -> Html {
         html! {
         <div>
@@ -78,4 +407,235 @@ impl Group {
         </div>
         }
     }
+
+    fn view_history(&self, ctx: &Context<Self>) -> Html {
+        match &self.history {
+            Some(history_page) if !history_page.entries.is_empty() => {
+                let page = history_page.page;
+                html! {
+                    <div>
+                    { self.view_ratings_summary(ctx) }
+                    {
+                        history_page
+                            .entries
+                            .iter()
+                            .map(|history| self.view_history_entry(ctx, history))
+                            .collect::<Html>()
+                    }
+                    <nav class="pagination">
+                        <button
+                            class="button pagination-previous"
+                            disabled={page == 0}
+                            onclick={ctx.link().callback(move |_| GroupMsg::GetHistory(page.saturating_sub(1)))}>
+                            {"Newer"}
+                        </button>
+                        <button
+                            class="button pagination-next"
+                            disabled={page + 1 >= history_page.total_pages}
+                            onclick={ctx.link().callback(move |_| GroupMsg::GetHistory(page + 1))}>
+                            {"Older"}
+                        </button>
+                        <p>{format!("page {} of {}", page + 1, history_page.total_pages)}</p>
+                    </nav>
+                    </div>
+                }
+            }
+            Some(_) => html! {
+                <p>{"No rounds finished yet."}</p>
+            },
+            None => html! {
+                <p>{"Loading history..."}</p>
+            },
+        }
+    }
+
+    pub fn view_history_entry(&self, ctx: &Context<Self>, history: &RoundHistory) -> Html {
+        let candidates = history
+            .candidates
+            .iter()
+            .map(|movie| format!("{} ({})", &movie.movie_title, &movie.added_by))
+            .collect::<Vec<String>>()
+            .join(", ");
+        html! {
+            <div class="box">
+                <img src={image_processing(&history.winner.movie_images)}/>
+                <li class="has-text-weight-bold">
+                    {format!("{} ({})", &history.winner.movie_title, &history.winner.movie_year)}
+                </li>
+                <li>{format!("Watched: {}", format_date(history.date_finished))}</li>
+                <li>{format!("Added by: {}", &history.added_by)}</li>
+                <li>{format!("Participants: {}", history.participants.join(", "))}</li>
+                <li>{format!("Candidates: {}", candidates)}</li>
+                {
+                    history
+                        .candidates
+                        .iter()
+                        .filter_map(|movie| {
+                            history.discussions.get(&movie.movie_id).map(|discussion| html! {
+                                <details>
+                                    <summary>{format!("What was said about {}", &movie.movie_title)}</summary>
+                                    { view_comments(discussion) }
+                                </details>
+                            })
+                        })
+                        .collect::<Html>()
+                }
+                {
+                    match history.average_rating() {
+                        Some(average) => html! {
+                            <li>{format!("Rating: {:.1} / 10 from {} ratings", average, history.ratings.len())}</li>
+                        },
+                        None => html! {},
+                    }
+                }
+                {
+                    history
+                        .ratings
+                        .iter()
+                        .map(|(member, rating)| html! {
+                            <p class="is-size-7">
+                                {format!("{}: {}", member, rating.score)}
+                                {
+                                    match &rating.comment {
+                                        Some(comment) => format!(" - {}", comment),
+                                        None => String::from(""),
+                                    }
+                                }
+                            </p>
+                        })
+                        .collect::<Html>()
+                }
+                { self.view_rating_form(ctx, history) }
+            </div>
+        }
+    }
+
+    fn view_rating_form(&self, ctx: &Context<Self>, history: &RoundHistory) -> Html {
+        if !history.participants.contains(&self.username) {
+            return html! {};
+        }
+        let score_id = history.id.clone();
+        let comment_id = history.id.clone();
+        let submit_id = history.id.clone();
+        let button_text = match history.ratings.contains_key(&self.username) {
+            true => "Update rating",
+            false => "Rate it",
+        };
+        html! {
+            <div class="field has-addons">
+                <input
+                    class="input is-small"
+                    type="number"
+                    min="1"
+                    max="10"
+                    placeholder="1-10"
+                    oninput={ctx.link().callback(move |text| GroupMsg::SetRatingScore(score_id.clone(), text))}
+                />
+                <input
+                    class="input is-small"
+                    placeholder="comment (optional)"
+                    maxlength=500
+                    oninput={ctx.link().callback(move |text| GroupMsg::SetRatingComment(comment_id.clone(), text))}
+                />
+                <button
+                    class="button is-primary is-small"
+                    onclick={ctx.link().callback(move |_| GroupMsg::SubmitRating(submit_id.clone()))}>
+                    { button_text }
+                </button>
+            </div>
+        }
+    }
+
+    fn view_ratings_summary(&self, _ctx: &Context<Self>) -> Html {
+        let ratings_summary = match &self.ratings_summary {
+            Some(ratings_summary) if ratings_summary.ratings_count > 0 => ratings_summary,
+            _ => return html! {},
+        };
+        html! {
+            <div class="box">
+                <h4 class="has-text-weight-bold">{"Was the system worth it?"}</h4>
+                <p>{format!(
+                    "Average satisfaction: {:.1} / 10 from {} ratings",
+                    ratings_summary.average.unwrap_or_default(),
+                    ratings_summary.ratings_count
+                )}</p>
+                <table class="table is-narrow">
+                    <thead>
+                        <tr><th>{"Added by"}</th><th>{"Rounds won"}</th><th>{"Average"}</th></tr>
+                    </thead>
+                    <tbody>
+                    {
+                        ratings_summary
+                            .by_added_by
+                            .iter()
+                            .map(|added_by| html! {
+                                <tr>
+                                    <td>{&added_by.added_by}</td>
+                                    <td>{added_by.rounds}</td>
+                                    <td>{format!("{:.1}", added_by.average)}</td>
+                                </tr>
+                            })
+                            .collect::<Html>()
+                    }
+                    </tbody>
+                </table>
+                <table class="table is-narrow">
+                    <thead>
+                        <tr><th>{"Watched"}</th><th>{"Movie"}</th><th>{"Average"}</th><th>{"Running average"}</th></tr>
+                    </thead>
+                    <tbody>
+                    {
+                        ratings_summary
+                            .over_time
+                            .iter()
+                            .map(|round| html! {
+                                <tr>
+                                    <td>{format_date(round.date_finished)}</td>
+                                    <td>{&round.movie_title}</td>
+                                    <td>{format!("{:.1}", round.average)}</td>
+                                    <td>{format!("{:.1}", round.running_average)}</td>
+                                </tr>
+                            })
+                            .collect::<Html>()
+                    }
+                    </tbody>
+                </table>
+            </div>
+        }
+    }
+}
+
+fn view_deliveries(deliveries: &[DeliveryRecord]) -> Html {
+    match deliveries.is_empty() {
+        true => html! { <p>{"Nothing delivered yet."}</p> },
+        false => html! {
+            <table class="table is-narrow">
+                <thead>
+                    <tr>
+                        <th>{"Date"}</th>
+                        <th>{"Event"}</th>
+                        <th>{"Attempts"}</th>
+                        <th>{"Status"}</th>
+                        <th>{"Error"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                {
+                    deliveries
+                        .iter()
+                        .map(|record| html! {
+                            <tr class={if record.succeeded() { "" } else { "has-text-danger" }}>
+                                <td>{format_date(record.date)}</td>
+                                <td>{format!("{:?}", record.event)}</td>
+                                <td>{record.attempts.to_string()}</td>
+                                <td>{record.status.map(|status| status.to_string()).unwrap_or_default()}</td>
+                                <td>{record.error.clone().unwrap_or_default()}</td>
+                            </tr>
+                        })
+                        .collect::<Html>()
+                }
+                </tbody>
+            </table>
+        },
+    }
 }
@@ -6,8 +6,11 @@ pub mod home;
 pub mod home_html;
 pub mod login;
 pub mod login_html;
+pub mod notifications;
 pub mod register;
 pub mod register_html;
+pub mod stats;
+pub mod stats_html;
 pub mod system;
 pub mod system_html;
 pub mod user;
@@ -0,0 +1,151 @@
+use crate::auth_requests::{get_route_with_auth, post_route_with_auth};
+use crate::{GET_NOTIFICATIONS_URL, MARK_NOTIFICATIONS_READ_URL};
+use anyhow::Result;
+use gloo_storage::{LocalStorage, Storage};
+use gloo_timers::callback::Interval;
+use shared_stuff::notification_structs::{Inbox, MarkRead};
+use yew::prelude::*;
+
+// Nobody needs to hear about their turn the second it comes round.
+const POLL_MILLIS: u32 = 30000;
+
+pub async fn request_get_inbox() -> Result<Inbox> {
+    let resp = get_route_with_auth(&GET_NOTIFICATIONS_URL).await?;
+    log::info!("request_get_inbox resp: {:?}", &resp);
+    let inbox: Inbox = resp.json().await?;
+    Ok(inbox)
+}
+
+pub async fn request_mark_read(mark: MarkRead) -> Result<Inbox> {
+    let json_body = serde_json::to_string(&mark)?;
+    let resp = post_route_with_auth(&MARK_NOTIFICATIONS_READ_URL, json_body).await?;
+    log::info!("request_mark_read resp: {:?}", &resp);
+    let inbox: Inbox = resp.json().await?;
+    Ok(inbox)
+}
+
+/// The bell in the navbar, with the unread count and a menu of the latest notifications.
+#[derive(Debug)]
+pub struct NotificationBell {
+    pub inbox: Option<Inbox>,
+    pub open: bool,
+    _poll: Interval,
+}
+
+pub enum NotificationMsg {
+    Noop,
+    GetInbox,
+    UpdateInbox(Inbox),
+    MarkRead(MarkRead),
+    ToggleMenu,
+}
+
+impl Component for NotificationBell {
+    type Message = NotificationMsg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(NotificationMsg::GetInbox);
+        let poll_link = ctx.link().clone();
+        let _poll = Interval::new(POLL_MILLIS, move || {
+            poll_link.send_message(NotificationMsg::GetInbox)
+        });
+        Self {
+            inbox: None,
+            open: false,
+            _poll,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let link_clone = ctx.link().clone();
+        use NotificationMsg::*;
+        match msg {
+            Noop => return false,
+            GetInbox => {
+                // Logged out, so there's nobody to ask for.
+                let logged_in: Option<String> =
+                    LocalStorage::raw().get("username").expect("storage issue");
+                if logged_in.is_none() {
+                    return false;
+                }
+                link_clone.send_future(async move {
+                    match request_get_inbox().await {
+                        Ok(inbox) => NotificationMsg::UpdateInbox(inbox),
+                        _ => NotificationMsg::Noop,
+                    }
+                })
+            }
+            UpdateInbox(inbox) => self.inbox = Some(inbox),
+            MarkRead(mark) => link_clone.send_future(async move {
+                match request_mark_read(mark).await {
+                    Ok(inbox) => NotificationMsg::UpdateInbox(inbox),
+                    _ => NotificationMsg::Noop,
+                }
+            }),
+            ToggleMenu => self.open = !self.open,
+        }
+        true
+    }
+
+    fn changed(&mut self, _ctx: &Context<Self>) -> bool {
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let inbox = match &self.inbox {
+            Some(inbox) => inbox,
+            None => return html! {},
+        };
+        let dropdown_class = match self.open {
+            true => "navbar-item has-dropdown is-active",
+            false => "navbar-item has-dropdown",
+        };
+        html! {
+            <div class={dropdown_class}>
+                <a class="navbar-link is-arrowless" onclick={ctx.link().callback(|_| NotificationMsg::ToggleMenu)}>
+                    <i class="material-icons">{"notifications"}</i>
+                    {
+                        match inbox.unread {
+                            0 => html! {},
+                            unread => html! { <span class="tag is-danger is-rounded">{unread.to_string()}</span> },
+                        }
+                    }
+                </a>
+                <div class="navbar-dropdown is-right">
+                    <a class="navbar-item" onclick={ctx.link().callback(|_| NotificationMsg::MarkRead(MarkRead::All))}>
+                        {"Mark all as read"}
+                    </a>
+                    <hr class="navbar-divider"/>
+                    { self.view_notifications(ctx, inbox) }
+                </div>
+            </div>
+        }
+    }
+}
+
+impl NotificationBell {
+    fn view_notifications(&self, ctx: &Context<Self>, inbox: &Inbox) -> Html {
+        if inbox.notifications.is_empty() {
+            return html! { <p class="navbar-item">{"Nothing yet"}</p> };
+        }
+        inbox
+            .notifications
+            .iter()
+            .map(|notification| {
+                let id = notification.id;
+                let href = format!("/system/{}", notification.kind.group().uuid);
+                let class = match notification.read {
+                    true => "navbar-item",
+                    false => "navbar-item has-text-weight-bold",
+                };
+                html! {
+                    <a class={class} href={href}
+                        onclick={ctx.link().callback(move |_| NotificationMsg::MarkRead(MarkRead::Ids(vec![id])))}>
+                        { notification.kind.message() }
+                    </a>
+                }
+            })
+            .collect::<Html>()
+    }
+}
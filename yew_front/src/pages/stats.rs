@@ -0,0 +1,77 @@
+use crate::auth_requests::get_route_with_auth;
+use crate::GET_GROUP_STATS_URL;
+use anyhow::Result;
+use shared_stuff::stats_structs::GroupStats;
+use yew::prelude::*;
+
+pub async fn request_get_group_stats(group_id: String) -> Result<GroupStats> {
+    let url = format!("{}/{}", *GET_GROUP_STATS_URL, group_id);
+    let resp = get_route_with_auth(&url).await?;
+    log::info!("request_get_group_stats resp: {:?}", &resp);
+    let group_stats: GroupStats = resp.json().await?;
+    Ok(group_stats)
+}
+
+#[derive(Properties, Debug, PartialEq, Clone)]
+pub struct Props {
+    pub id: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stats {
+    pub group_id: String,
+    pub stats: Option<GroupStats>,
+}
+
+pub enum StatsMsg {
+    GetStats,
+    UpdateStats(GroupStats),
+    Error(String),
+}
+
+impl Component for Stats {
+    type Message = StatsMsg;
+    type Properties = Props;
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(StatsMsg::GetStats);
+        Self {
+            group_id: ctx.props().id.clone(),
+            stats: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let group_id = self.group_id.clone();
+        use StatsMsg::*;
+        match msg {
+            GetStats => ctx.link().send_future(async move {
+                match request_get_group_stats(group_id).await {
+                    Ok(group_stats) => StatsMsg::UpdateStats(group_stats),
+                    Err(e) => StatsMsg::Error(e.to_string()),
+                }
+            }),
+
+            UpdateStats(group_stats) => {
+                self.stats = Some(group_stats);
+            }
+
+            Error(err_msg) => {
+                log::info!("{:?}", &err_msg);
+            }
+        }
+        true
+    }
+
+    fn changed(&mut self, _ctx: &Context<Self>) -> bool {
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div>
+            <h3>{format!("stats for group: {}", &ctx.props().id)}</h3>
+            { self.view_stats(ctx) }
+            </div>
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::pages::stats::Stats;
+use shared_stuff::stats_structs::GroupStats;
+use yew::prelude::*;
+
+const CHART_WIDTH: f64 = 400.0;
+const BAR_HEIGHT: f64 = 20.0;
+const LABEL_WIDTH: f64 = 120.0;
+
+impl Stats {
+    pub fn view_stats(&self, ctx: &Context<Self>) -> Html {
+        match &self.stats {
+            Some(stats) if stats.rounds_played == 0 => html! {
+                <p>{"No rounds have been finished yet."}</p>
+            },
+            Some(stats) => html! {
+                <div>
+                { self.view_summary(ctx, stats) }
+                { self.view_members(ctx, stats) }
+                { self.view_veto_counts(ctx, stats) }
+                { self.view_turn_positions(ctx, stats) }
+                </div>
+            },
+            None => html! {
+                <p>{"Loading stats..."}</p>
+            },
+        }
+    }
+
+    fn view_summary(&self, _ctx: &Context<Self>, stats: &GroupStats) -> Html {
+        html! {
+            <div class="box">
+                <li>{format!("Rounds played: {}", stats.rounds_played)}</li>
+                <li>{format!("Average candidates per round: {:.1}", stats.average_candidates)}</li>
+                <li>{format!("Average round length: {}", format_duration(stats.average_round_duration))}</li>
+            </div>
+        }
+    }
+
+    fn view_members(&self, _ctx: &Context<Self>, stats: &GroupStats) -> Html {
+        let wins: Vec<(String, f64)> = stats
+            .members
+            .iter()
+            .map(|member| (member.username.clone(), member.wins as f64))
+            .collect();
+        html! {
+            <div class="box">
+                <h4>{"Members"}</h4>
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>{"Member"}</th>
+                            <th>{"Wins"}</th>
+                            <th>{"Movies added"}</th>
+                            <th>{"Vetoes cast"}</th>
+                            <th>{"Movies vetoed"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                    {
+                        stats.members.iter().map(|member| html! {
+                            <tr>
+                                <td>{&member.username}</td>
+                                <td>{member.wins}</td>
+                                <td>{member.movies_added}</td>
+                                <td>{member.vetoes_cast}</td>
+                                <td>{member.movies_vetoed}</td>
+                            </tr>
+                        }).collect::<Html>()
+                    }
+                    </tbody>
+                </table>
+                <h5>{"Wins per member"}</h5>
+                { bar_chart(&wins, |value| format!("{}", value)) }
+            </div>
+        }
+    }
+
+    fn view_veto_counts(&self, _ctx: &Context<Self>, stats: &GroupStats) -> Html {
+        html! {
+            <div class="box">
+                <h4>{"Who vetoes whom"}</h4>
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>{"Vetoed by"}</th>
+                            <th>{"Added by"}</th>
+                            <th>{"Count"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                    {
+                        stats.veto_counts.iter().map(|veto_count| html! {
+                            <tr>
+                                <td>{&veto_count.vetoed_by}</td>
+                                <td>{&veto_count.added_by}</td>
+                                <td>{veto_count.count}</td>
+                            </tr>
+                        }).collect::<Html>()
+                    }
+                    </tbody>
+                </table>
+            </div>
+        }
+    }
+
+    fn view_turn_positions(&self, _ctx: &Context<Self>, stats: &GroupStats) -> Html {
+        let win_rates: Vec<(String, f64)> = stats
+            .turn_positions
+            .iter()
+            .map(|turn| (format!("Turn {}", turn.position), turn.win_rate))
+            .collect();
+        html! {
+            <div class="box">
+                <h4>{"Win rate by turn position, veto rounds only"}</h4>
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>{"Position"}</th>
+                            <th>{"Rounds"}</th>
+                            <th>{"Wins"}</th>
+                            <th>{"Win rate"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                    {
+                        stats.turn_positions.iter().map(|turn| html! {
+                            <tr>
+                                <td>{turn.position}</td>
+                                <td>{turn.rounds}</td>
+                                <td>{turn.wins}</td>
+                                <td>{format!("{:.0}%", turn.win_rate * 100.0)}</td>
+                            </tr>
+                        }).collect::<Html>()
+                    }
+                    </tbody>
+                </table>
+                { bar_chart(&win_rates, |value| format!("{:.0}%", value * 100.0)) }
+            </div>
+        }
+    }
+}
+
+/// Horizontal bars scaled against the largest value.
+fn bar_chart(values: &[(String, f64)], format_value: fn(f64) -> String) -> Html {
+    let max = values.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+    let height = BAR_HEIGHT * values.len() as f64 + 5.0;
+    let bar_space = CHART_WIDTH - LABEL_WIDTH - 50.0;
+    html! {
+        <svg width={CHART_WIDTH.to_string()} height={height.to_string()}>
+        {
+            values.iter().enumerate().map(|(i, (label, value))| {
+                let y = BAR_HEIGHT * i as f64;
+                let width = if max > 0.0 { value / max * bar_space } else { 0.0 };
+                html! {
+                    <g>
+                        <text x="0" y={(y + 14.0).to_string()}>{label}</text>
+                        <rect
+                            x={LABEL_WIDTH.to_string()}
+                            y={(y + 2.0).to_string()}
+                            width={width.to_string()}
+                            height={(BAR_HEIGHT - 4.0).to_string()}
+                            fill="hsl(171, 100%, 41%)"
+                        />
+                        <text x={(LABEL_WIDTH + width + 5.0).to_string()} y={(y + 14.0).to_string()}>
+                            {format_value(*value)}
+                        </text>
+                    </g>
+                }
+            }).collect::<Html>()
+        }
+        </svg>
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as i64;
+    match minutes {
+        0..=59 => format!("{} minutes", minutes),
+        _ => format!("{}h {}m", minutes / 60, minutes % 60),
+    }
+}
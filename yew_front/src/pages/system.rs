@@ -1,42 +1,113 @@
+use crate::auth_requests::post_route_with_auth;
+use crate::auth_requests::get_route_with_auth;
+use crate::BACKLOG_ACTION_URL;
+use crate::GET_MOVIE_DETAILS_URL;
 use crate::SEARCH_URL;
-use crate::UPDATE_GROUP_DATA_URL;
-use anyhow::Result;
+use crate::SYSTEM_ACTION_URL;
+use crate::PLAN_ROUND_URL;
+use crate::UNDO_VETO_URL;
+use anyhow::{anyhow, Result};
 use gloo_storage::{LocalStorage, Storage};
+use gloo_timers::callback::Interval;
 use reqwasm::http::{Request, RequestMode};
 
-use crate::shared_requests::request_get_group_data;
-use shared_stuff::db_structs::GroupData;
+use crate::shared_requests::{
+    request_get_group_history, request_get_group_overview, request_get_watchlist,
+    request_watchlist_action,
+};
+use shared_stuff::auth_structs::ErrorMessage;
+use shared_stuff::backlog_structs::{BacklogAction, BacklogPick};
+use shared_stuff::db_structs::{DBGroupStruct, GroupData};
+use shared_stuff::history_structs::RoundHistory;
 use shared_stuff::imdb_structs::ImdbQuery;
+use shared_stuff::provider_structs::MovieDetails;
+use shared_stuff::round_structs::{GroupOverview, RoundInfo};
+use shared_stuff::selection_structs::SelectionMode;
 use shared_stuff::shared_structs::{MovieDisplay, SystemState, YewMovieDisplay};
-use std::collections::{HashSet, VecDeque};
+use shared_stuff::system_structs::SystemAction;
+use shared_stuff::watchlist_structs::{WatchlistAction, WatchlistEntry};
+use std::collections::{HashMap, HashSet};
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 #[derive(Properties, Debug, PartialEq, Clone)]
 pub struct Props {
     pub id: String,
+    /// Empty to follow whichever round is active.
+    pub round_id: String,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// How often the page checks for moves by the other members.
+const POLL_MILLIS: u32 = 3000;
+// Redraws the deadline countdown.
+const TICK_MILLIS: u32 = 1000;
+
+#[derive(Debug)]
 pub struct System {
     pub username: String,
     pub group_id: String,
+    pub round_id: String,
     pub group_data: GroupData,
+    pub past_rounds: Vec<RoundInfo>,
+    /// Sent with the overview, the page doesn't know the grace window itself.
+    pub undo_deadline: Option<i64>,
     pub autocomplete_movies: HashSet<MovieDisplay>,
     pub current_movies: HashSet<YewMovieDisplay>,
+    pub last_round: Option<RoundHistory>,
     pub loaded: bool,
+    pub ranking: Vec<YewMovieDisplay>,
+    pub dragging: Option<usize>,
+    pub scores: HashMap<String, u8>,
+    pub watchlist: Vec<WatchlistEntry>,
+    pub fill_count: u32,
+    /// Unsent comments and pitches, by movie id.
+    pub comment_drafts: HashMap<String, String>,
+    pub pitch_drafts: HashMap<String, String>,
+    /// The candidate in the detail modal, and its details once they're in.
+    pub details_movie: Option<YewMovieDisplay>,
+    pub movie_details: Option<std::result::Result<MovieDetails, String>>,
+    _poll: Interval,
+    _tick: Interval,
 }
 pub enum SystemMsg {
     Noop,
+    Tick,
     GetGroupData,
-    UpdateGroupData(DBGroupStruct),
+    UpdateGroupData(Box<DBGroupStruct>),
+    UpdateOverview(Box<GroupOverview>),
+    PlanRound,
+    GetLastRound,
+    UpdateLastRound(Option<Box<RoundHistory>>),
     Error(String),
-    SaveMovies,
     DeleteEntry(YewMovieDisplay),
     DeleteEntryChangeTurn(YewMovieDisplay),
+    UndoVeto,
+    SetMode(SelectionMode),
+    DragStart(usize),
+    DropOn(usize),
+    SubmitRanking,
+    SetScore(String, InputEvent),
+    SubmitScores,
+    VoteMatchup(usize, String),
+    SecretVeto(YewMovieDisplay),
     QueryAutocomplete(InputEvent),
     UpdateAutocomplete(Vec<MovieDisplay>),
     AddMovie(MovieDisplay),
+    AddRewatch(MovieDisplay),
+    GetWatchlist,
+    UpdateWatchlist(Vec<WatchlistEntry>),
+    AddToWatchlist(MovieDisplay),
+    AddToBacklog(MovieDisplay),
+    RemoveFromBacklog(String),
+    SetFillCount(InputEvent),
+    FillFromBacklog(BacklogPick),
+    SetCommentDraft(String, InputEvent),
+    SendComment(String),
+    SetPitchDraft(String, InputEvent),
+    SavePitch(String),
+    ShowDetails(YewMovieDisplay),
+    UpdateDetails(String, std::result::Result<MovieDetails, String>),
+    CloseDetails,
     SetReady,
     UnsetReady,
 }
@@ -46,6 +117,14 @@ impl Component for System {
     type Properties = Props;
     fn create(ctx: &Context<Self>) -> Self {
         ctx.link().send_message(SystemMsg::GetGroupData);
+        ctx.link().send_message(SystemMsg::GetLastRound);
+        ctx.link().send_message(SystemMsg::GetWatchlist);
+        let poll_link = ctx.link().clone();
+        let _poll = Interval::new(POLL_MILLIS, move || {
+            poll_link.send_message(SystemMsg::GetGroupData)
+        });
+        let tick_link = ctx.link().clone();
+        let _tick = Interval::new(TICK_MILLIS, move || tick_link.send_message(SystemMsg::Tick));
         let storage = LocalStorage::raw();
         let id = &ctx.props().id;
         let mut username = String::from("");
@@ -56,132 +135,138 @@ impl Component for System {
         Self {
             username,
             group_id: id.to_string(),
+            round_id: ctx.props().round_id.clone(),
             group_data: GroupData::new_empty(),
+            past_rounds: Vec::new(),
+            undo_deadline: None,
             autocomplete_movies: HashSet::new(),
             current_movies,
+            last_round: None,
             loaded: false,
+            ranking: Vec::new(),
+            dragging: None,
+            scores: HashMap::new(),
+            watchlist: Vec::new(),
+            fill_count: 3,
+            comment_drafts: HashMap::new(),
+            pitch_drafts: HashMap::new(),
+            details_movie: None,
+            movie_details: None,
+            _poll,
+            _tick,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         let link_clone = ctx.link().clone();
         let id = self.group_id.clone();
-        let storage = LocalStorage::raw();
-        let username = storage
-            .get("username")
-            .expect("storage error")
-            .expect("ummm");
         self.current_movies = self.group_data.current_movies.clone();
         use SystemMsg::*;
         match msg {
             Noop => {}
-            SetReady => {
-                if let Some(user_status) = self.group_data.members.get_mut(&username) {
-                    user_status.ready_status = true;
-                } else {
-                    log::info!("some problem here");
-                };
-                if self
-                    .group_data
-                    .members
-                    .values()
-                    .all(|user_status| user_status.ready_status == true)
-                {
-                    self.group_data.system_state = SystemState::SystemStarted;
-                    self.group_data.system_order = self
-                        .group_data
-                        .members
-                        .keys()
-                        .cloned()
-                        .collect::<VecDeque<String>>();
-                    let current_turn = self
-                        .group_data
-                        .system_order
-                        .pop_front()
-                        .expect("no members?");
-                    self.group_data.turn = current_turn.clone();
-                    self.group_data.system_order.push_back(current_turn);
+            Tick => return self.group_data.deadline().is_some() || self.undo_deadline.is_some(),
+            SetReady => self.send_action(ctx, SystemAction::SetReady),
+            UnsetReady => self.send_action(ctx, SystemAction::UnsetReady),
+            DeleteEntry(movie) => self.send_action(ctx, SystemAction::DeleteMovie(movie)),
+            DeleteEntryChangeTurn(movie) => self.send_action(ctx, SystemAction::Veto(movie)),
+            AddMovie(movie) => self.send_action(ctx, SystemAction::AddMovie(movie)),
+            AddRewatch(movie) => self.send_action(ctx, SystemAction::AddRewatch(movie)),
+            GetWatchlist => link_clone.send_future(async move {
+                match request_get_watchlist().await {
+                    Ok(watchlist) => SystemMsg::UpdateWatchlist(watchlist),
+                    Err(e) => SystemMsg::Error(e.to_string()),
+                }
+            }),
+            UpdateWatchlist(watchlist) => self.watchlist = watchlist,
+            AddToBacklog(movie) => self.send_backlog_action(ctx, BacklogAction::Add(movie)),
+            RemoveFromBacklog(movie_id) => {
+                self.send_backlog_action(ctx, BacklogAction::Remove(movie_id))
+            }
+            SetFillCount(text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.fill_count = elem.value().parse().unwrap_or(0);
                 }
-                let cloned_data = self.group_data.clone();
-                let cloned_id = self.group_id.clone();
-                link_clone.send_future(async move {
-                    let resp = request_update_group_data(cloned_id, cloned_data).await;
-                    log::info!("resp is: {:?}", &resp);
-                    SystemMsg::Noop
-                })
             }
-            UnsetReady => {
-                if let Some(user_status) = self.group_data.members.get_mut(&username) {
-                    user_status.ready_status = false;
-                } else {
-                    log::info!("some problem here");
+            FillFromBacklog(pick) => {
+                let action = BacklogAction::Fill {
+                    round_id: self.shown_round_id(),
+                    count: self.fill_count,
+                    pick,
                 };
-                let cloned_data = self.group_data.clone();
-                let cloned_id = self.group_id.clone();
-                link_clone.send_future(async move {
-                    let resp = request_update_group_data(cloned_id, cloned_data).await;
-                    log::info!("resp is: {:?}", &resp);
-                    SystemMsg::Noop
-                })
+                self.send_backlog_action(ctx, action)
             }
-            SaveMovies => {
-                log::info!("inside save movies");
-                self.group_data.current_movies = self.current_movies.clone();
-
-                let cloned_data = self.group_data.clone();
-                let cloned_id = self.group_id.clone();
-                link_clone.send_future(async move {
-                    let resp = request_update_group_data(cloned_id, cloned_data).await;
-                    log::info!("resp is: {:?}", &resp);
-                    SystemMsg::Noop
-                })
+            AddToWatchlist(movie) => link_clone.send_future(async move {
+                match request_watchlist_action(WatchlistAction::Add(movie)).await {
+                    Ok(watchlist) => SystemMsg::UpdateWatchlist(watchlist),
+                    Err(e) => SystemMsg::Error(e.to_string()),
+                }
+            }),
+            SetMode(selection_mode) => self.send_action(ctx, SystemAction::SetMode(selection_mode)),
+            DragStart(index) => self.dragging = Some(index),
+            DropOn(index) => {
+                if let Some(from) = self.dragging.take() {
+                    let movie = self.ranking.remove(from);
+                    self.ranking.insert(index, movie);
+                }
             }
-
-            DeleteEntry(movie) => {
-                self.current_movies.remove(&movie);
-                self.group_data.current_movies = self.current_movies.clone();
-                let cloned_data = self.group_data.clone();
-                let cloned_id = self.group_id.clone();
-                link_clone.send_future(async move {
-                    let resp = request_update_group_data(cloned_id, cloned_data).await;
-                    log::info!("resp is: {:?}", &resp);
-                    SystemMsg::Noop
-                })
+            SubmitRanking => {
+                let ranking = self
+                    .ranking
+                    .iter()
+                    .map(|movie| movie.movie_id.clone())
+                    .collect();
+                self.send_action(ctx, SystemAction::SubmitRanking(ranking))
             }
-
-            DeleteEntryChangeTurn(movie) => {
-                self.current_movies.remove(&movie);
-                self.group_data.current_movies = self.current_movies.clone();
-                let current_turn = self
-                    .group_data
-                    .system_order
-                    .pop_front()
-                    .expect("members empty?");
-                self.group_data.turn = current_turn.clone();
-                self.group_data.system_order.push_back(current_turn);
-                if self.current_movies.len() == 1 {
-                    self.group_data.system_state = SystemState::Finished;
+            SetScore(movie_id, text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    let score = match elem.type_().as_str() {
+                        "checkbox" => elem.checked() as u8,
+                        _ => elem.value().parse::<u8>().unwrap_or_default(),
+                    };
+                    self.scores.insert(movie_id, score);
                 }
-                link_clone.send_message(SystemMsg::UpdateGroupData(
-                    self.group_data.clone().into_db_group_struct(&self.group_id),
-                ));
-                let cloned_data = self.group_data.clone();
-                let cloned_id = self.group_id.clone();
-
-                link_clone.send_future(async move {
-                    let resp = request_update_group_data(cloned_id, cloned_data).await;
-                    log::info!("resp is: {:?}", &resp);
-                    SystemMsg::Noop
-                })
             }
-            AddMovie(movie) => {
-                self.current_movies
-                    .insert(movie.clone().into_yew_display(self.username.clone()));
-                self.group_data
+            SubmitScores => {
+                // Anything left untouched counts as a zero.
+                let scores = self
                     .current_movies
-                    .insert(movie.clone().into_yew_display(self.username.clone()));
-                log::info!("current_movies: {:?}", &self.current_movies);
+                    .iter()
+                    .map(|movie| {
+                        let score = self.scores.get(&movie.movie_id).cloned().unwrap_or(0);
+                        (movie.movie_id.clone(), score)
+                    })
+                    .collect();
+                self.send_action(ctx, SystemAction::SubmitScores(scores))
             }
+            SecretVeto(movie) => self.send_action(ctx, SystemAction::SecretVeto(movie)),
+            SetCommentDraft(movie_id, text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.comment_drafts.insert(movie_id, elem.value());
+                }
+            }
+            SendComment(movie_id) => {
+                let text = self.comment_drafts.remove(&movie_id).unwrap_or_default();
+                self.send_action(ctx, SystemAction::Comment { movie_id, text })
+            }
+            SetPitchDraft(movie_id, text) => {
+                if let Some(elem) = text.target_dyn_into::<HtmlInputElement>() {
+                    self.pitch_drafts.insert(movie_id, elem.value());
+                }
+            }
+            SavePitch(movie_id) => {
+                let pitch = self.pitch_drafts.remove(&movie_id);
+                self.send_action(ctx, SystemAction::SetPitch { movie_id, pitch })
+            }
+            VoteMatchup(matchup, movie_id) => {
+                self.send_action(ctx, SystemAction::VoteMatchup { matchup, movie_id })
+            }
+            // The overview comes with the undo deadline for whichever veto is last now.
+            UndoVeto => link_clone.send_future(async move {
+                match request_undo_veto(id).await {
+                    Ok(_) => SystemMsg::GetGroupData,
+                    Err(e) => SystemMsg::Error(e.to_string()),
+                }
+            }),
             QueryAutocomplete(text) => {
                 if text.current_target().is_some() {
                     link_clone.clone().send_future(async move {
@@ -213,19 +298,81 @@ impl Component for System {
             }
 
             GetGroupData => link_clone.send_future(async move {
-                let group_data_resp = request_get_group_data(id).await;
-                log::info!("group_data_resp: {:?}", &group_data_resp);
-                match group_data_resp {
-                    Ok(group_data) => SystemMsg::UpdateGroupData(group_data),
+                let overview_resp = request_get_group_overview(id).await;
+                log::info!("overview_resp: {:?}", &overview_resp);
+                match overview_resp {
+                    Ok(overview) => SystemMsg::UpdateOverview(Box::new(overview)),
+                    Err(e) => SystemMsg::Error(e.to_string()),
+                }
+            }),
+
+            UpdateOverview(overview) => {
+                self.past_rounds = overview.past_rounds;
+                self.undo_deadline = overview.undo_deadline;
+                link_clone.send_message(SystemMsg::UpdateGroupData(Box::new(DBGroupStruct {
+                    id: overview.id,
+                    group_data: overview.group_data,
+                })));
+            }
+
+            PlanRound => link_clone.send_future(async move {
+                match request_plan_round(id).await {
+                    Ok(group_struct) => SystemMsg::UpdateGroupData(Box::new(group_struct)),
                     Err(e) => SystemMsg::Error(e.to_string()),
                 }
             }),
 
             UpdateGroupData(group_struct) => {
+                // A round that just finished comes back already reset, so go and get the winner.
+                if self.group_data.system_state != SystemState::AddingMovies
+                    && group_struct.group_data.system_state == SystemState::AddingMovies
+                {
+                    link_clone.send_message(SystemMsg::GetLastRound);
+                }
                 self.group_data = group_struct.group_data.clone();
                 self.current_movies = group_struct.group_data.current_movies;
                 self.group_id = group_struct.id;
                 self.loaded = true;
+                self.sync_ranking();
+            }
+
+            GetLastRound => link_clone.send_future(async move {
+                match request_get_group_history(id, 0).await {
+                    Ok(history_page) => {
+                        let last_round = history_page.entries.into_iter().next();
+                        SystemMsg::UpdateLastRound(last_round.map(Box::new))
+                    }
+                    Err(e) => SystemMsg::Error(e.to_string()),
+                }
+            }),
+
+            UpdateLastRound(last_round) => {
+                self.last_round = last_round.map(|round| *round);
+            }
+
+            ShowDetails(movie) => {
+                let movie_id = movie.movie_id.clone();
+                self.details_movie = Some(movie);
+                self.movie_details = None;
+                link_clone.send_future(async move {
+                    let details = request_get_movie_details(&movie_id)
+                        .await
+                        .map_err(|e| e.to_string());
+                    SystemMsg::UpdateDetails(movie_id, details)
+                })
+            }
+
+            // Dropped if the modal was closed or moved on to another movie meanwhile.
+            UpdateDetails(movie_id, details) => {
+                match &self.details_movie {
+                    Some(movie) if movie.movie_id == movie_id => self.movie_details = Some(details),
+                    _ => return false,
+                }
+            }
+
+            CloseDetails => {
+                self.details_movie = None;
+                self.movie_details = None;
             }
 
             Error(err_msg) => {
@@ -235,36 +382,197 @@ impl Component for System {
         true
     }
 
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.round_id = ctx.props().round_id.clone();
+        true
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.loaded && !self.viewing_active_round() {
+            return html! {
+                <div>
+                { self.view_rounds_nav(ctx) }
+                { self.view_other_round(ctx) }
+                </div>
+            };
+        }
         html! {
             <div>
+            { self.view_rounds_nav(ctx) }
             { self.ready_status_buttons(ctx) }
+            { self.view_selection_mode(ctx) }
             { self.view_group_id(ctx) }
+            { self.view_undo_veto(ctx) }
+            { self.view_deadline(ctx) }
+            { self.view_turn_order(ctx) }
+            { self.view_ranking_ballot(ctx) }
+            { self.view_score_ballot(ctx) }
+            { self.view_live_bracket(ctx) }
+            { self.view_secret_veto_status(ctx) }
+            { self.view_last_round(ctx) }
             { self.user_customized_view(ctx) }
             { self.full_search_html(ctx) }
             //{ self.display_current_members(ctx) }
             //{ self.search_bar(ctx) }
             { self.add_stuff(ctx) }
+            { self.view_movie_details(ctx) }
             </div>
         }
     }
 }
 
-use shared_stuff::db_structs::DBGroupStruct;
+impl System {
+    // Keeps the ballot being dragged around, unless the candidates changed underneath it.
+    fn sync_ranking(&mut self) {
+        let mut ranked_ids = self
+            .ranking
+            .iter()
+            .map(|movie| movie.movie_id.clone())
+            .collect::<Vec<_>>();
+        ranked_ids.sort();
+        let mut candidate_ids = self
+            .current_movies
+            .iter()
+            .map(|movie| movie.movie_id.clone())
+            .collect::<Vec<_>>();
+        candidate_ids.sort();
+        if ranked_ids == candidate_ids {
+            return;
+        }
+        let mut ranking = self.current_movies.iter().cloned().collect::<Vec<_>>();
+        match self.group_data.round.rankings.get(&self.username) {
+            Some(submitted) => ranking.sort_by_key(|movie| {
+                submitted.iter().position(|movie_id| movie_id == &movie.movie_id)
+            }),
+            None => ranking.sort_by(|a, b| a.movie_title.cmp(&b.movie_title)),
+        }
+        self.ranking = ranking;
+    }
+
+    pub fn viewing_active_round(&self) -> bool {
+        self.round_id.is_empty() || self.round_id == self.group_data.round_id
+    }
 
-pub async fn request_update_group_data(group_id: String, group_data: GroupData) -> Result<()> {
-    let db_group = DBGroupStruct {
-        id: group_id,
-        group_data,
-    };
-    let serialized_db_group = serde_json::to_string(&db_group).expect("serialization error");
-    let _resp = Request::post(&UPDATE_GROUP_DATA_URL)
-        .header("content-type", "application/json; charset=UTF-8")
-        .mode(RequestMode::Cors)
-        .body(serialized_db_group)
-        .send()
-        .await?;
-    Ok(())
+    fn shown_round_id(&self) -> String {
+        match self.round_id.is_empty() {
+            true => self.group_data.round_id.clone(),
+            false => self.round_id.clone(),
+        }
+    }
+
+    // Actions go to the round on screen, so a stale page can't act on the next round by mistake.
+    fn send_action(&self, ctx: &Context<Self>, action: SystemAction) {
+        let group_id = self.group_id.clone();
+        let round_id = self.shown_round_id();
+        // A veto moves the undo deadline, which only comes with the overview.
+        let is_veto = matches!(action, SystemAction::Veto(_));
+        ctx.link().send_future(async move {
+            match request_system_action(group_id, round_id, action).await {
+                Ok(_) if is_veto => SystemMsg::GetGroupData,
+                Ok(group_struct) => SystemMsg::UpdateGroupData(Box::new(group_struct)),
+                Err(e) => SystemMsg::Error(e.to_string()),
+            }
+        })
+    }
+
+    fn send_backlog_action(&self, ctx: &Context<Self>, action: BacklogAction) {
+        let group_id = self.group_id.clone();
+        ctx.link().send_future(async move {
+            match request_backlog_action(group_id, action).await {
+                Ok(group_struct) => SystemMsg::UpdateGroupData(Box::new(group_struct)),
+                Err(e) => SystemMsg::Error(e.to_string()),
+            }
+        })
+    }
+}
+
+pub async fn request_system_action(
+    group_id: String,
+    round_id: String,
+    action: SystemAction,
+) -> Result<DBGroupStruct> {
+    let url = format!("{}/{}/{}", *SYSTEM_ACTION_URL, group_id, round_id);
+    let json_body = serde_json::to_string(&action)?;
+    let resp = post_route_with_auth(&url, json_body).await?;
+    log::info!("request_system_action resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let group_struct: DBGroupStruct = resp.json().await?;
+            Ok(group_struct)
+        }
+        _ => {
+            let error_message: ErrorMessage = resp.json().await?;
+            Err(anyhow!(error_message.message))
+        }
+    }
+}
+
+pub async fn request_plan_round(group_id: String) -> Result<DBGroupStruct> {
+    let url = format!("{}/{}", *PLAN_ROUND_URL, group_id);
+    let resp = post_route_with_auth(&url, String::from("")).await?;
+    log::info!("request_plan_round resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let group_struct: DBGroupStruct = resp.json().await?;
+            Ok(group_struct)
+        }
+        _ => {
+            let error_message: ErrorMessage = resp.json().await?;
+            Err(anyhow!(error_message.message))
+        }
+    }
+}
+
+pub async fn request_backlog_action(
+    group_id: String,
+    action: BacklogAction,
+) -> Result<DBGroupStruct> {
+    let url = format!("{}/{}", *BACKLOG_ACTION_URL, group_id);
+    let json_body = serde_json::to_string(&action)?;
+    let resp = post_route_with_auth(&url, json_body).await?;
+    log::info!("request_backlog_action resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let group_struct: DBGroupStruct = resp.json().await?;
+            Ok(group_struct)
+        }
+        _ => {
+            let error_message: ErrorMessage = resp.json().await?;
+            Err(anyhow!(error_message.message))
+        }
+    }
+}
+
+pub async fn request_undo_veto(group_id: String) -> Result<DBGroupStruct> {
+    let url = format!("{}/{}", *UNDO_VETO_URL, group_id);
+    let resp = post_route_with_auth(&url, String::from("")).await?;
+    log::info!("request_undo_veto resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let group_struct: DBGroupStruct = resp.json().await?;
+            Ok(group_struct)
+        }
+        _ => {
+            let error_message: ErrorMessage = resp.json().await?;
+            Err(anyhow!(error_message.message))
+        }
+    }
+}
+
+pub async fn request_get_movie_details(movie_id: &str) -> Result<MovieDetails> {
+    let url = format!("{}/{}", *GET_MOVIE_DETAILS_URL, movie_id);
+    let resp = get_route_with_auth(&url).await?;
+    log::info!("request_get_movie_details resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let movie_details: MovieDetails = resp.json().await?;
+            Ok(movie_details)
+        }
+        _ => {
+            let error_message: ErrorMessage = resp.json().await?;
+            Err(anyhow!(error_message.message))
+        }
+    }
 }
 
 pub async fn request_get_search_results(url: &str, body: ImdbQuery) -> Result<Vec<MovieDisplay>> {
@@ -1,9 +1,17 @@
 use crate::pages::system::{System, SystemMsg};
+use shared_stuff::backlog_structs::BacklogPick;
 use shared_stuff::db_structs::GroupData;
+use shared_stuff::discussion_structs::{Discussion, MAX_COMMENT_LENGTH};
+use shared_stuff::history_structs::RoundHistory;
+use shared_stuff::selection_structs::{Bracket, SelectionMode, TurnOrder, MAX_SCORE};
 use shared_stuff::shared_structs::{SystemState, YewMovieDisplay};
+use std::collections::HashSet;
 use std::collections::VecDeque;
 
+use crate::utils::format_date;
+use crate::CORS_ORIGIN;
 use shared_stuff::imdb_structs::ImageData;
+use shared_stuff::provider_structs::{MovieDetails, MovieSource};
 use yew::prelude::*;
 
 impl System {
@@ -44,6 +52,463 @@ impl System {
         }
     }
 
+    // Only the member who made the last veto gets the button, until the grace window runs out.
+    pub fn view_undo_veto(&self, ctx: &Context<Self>) -> Html {
+        let last_veto = match self.group_data.round.vetoes.last() {
+            Some(last_veto) if last_veto.username == self.username => last_veto,
+            _ => return html! {},
+        };
+        let now = (js_sys::Date::now() / 1000.0) as i64;
+        if self.group_data.system_state == SystemState::AddingMovies
+            || self.undo_deadline.is_none_or(|deadline| now > deadline)
+        {
+            return html! {};
+        }
+        html! {
+            <div class="notification is-warning">
+                {format!("You vetoed {}. ", &last_veto.movie.movie_title)}
+                <button
+                    class="button is-small"
+                    onclick={ctx.link().callback(|_| SystemMsg::UndoVeto)}>
+                    { "Undo" }
+                </button>
+            </div>
+        }
+    }
+
+    // Shown from the start of a veto round so everyone knows who's up when.
+    pub fn view_turn_order(&self, _ctx: &Context<Self>) -> Html {
+        if self.group_data.system_state != SystemState::SystemStarted
+            || self.group_data.selection_mode != SelectionMode::Veto
+        {
+            return html! {};
+        }
+        let policy = match &self.group_data.rules.turn_order {
+            TurnOrder::Shuffled => match self.group_data.round.order_seed {
+                Some(seed) => format!("shuffled, seed {}", seed),
+                None => String::from("shuffled"),
+            },
+            TurnOrder::Fixed(_) => String::from("fixed by the owner"),
+            TurnOrder::LoserFirst => String::from("longest without a win goes first"),
+            TurnOrder::RoundRobin => String::from("round robin"),
+        };
+        html! {
+            <div class="box">
+                <p class="has-text-weight-bold">{format!("Turn order ({})", policy)}</p>
+                <p>{self.group_data.round.participants.join(" → ")}</p>
+            </div>
+        }
+    }
+
+    pub fn view_deadline(&self, _ctx: &Context<Self>) -> Html {
+        let deadline = match self.group_data.deadline() {
+            Some(deadline) => deadline,
+            None => return html! {},
+        };
+        let now = (js_sys::Date::now() / 1000.0) as i64;
+        let left = (deadline - now).max(0);
+        let label = match self.group_data.system_state {
+            SystemState::AddingMovies => String::from("Round starts in"),
+            _ if self.group_data.turn == self.username => String::from("Your turn ends in"),
+            _ => format!("{}'s turn ends in", &self.group_data.turn),
+        };
+        html! {
+            <div class="notification is-info">
+                {format!("{} {}:{:02}", label, left / 60, left % 60)}
+            </div>
+        }
+    }
+
+    pub fn view_selection_mode(&self, ctx: &Context<Self>) -> Html {
+        if self.group_data.system_state != SystemState::AddingMovies {
+            return html! {};
+        }
+        let modes = [
+            ("Veto", SelectionMode::Veto),
+            ("Random", SelectionMode::RandomDraw { weighted: false }),
+            ("Random, favour recent losers", SelectionMode::RandomDraw { weighted: true }),
+            ("Ranked choice", SelectionMode::RankedChoice),
+            ("Scores", SelectionMode::Score { approval: false }),
+            ("Approval", SelectionMode::Score { approval: true }),
+            ("Bracket", SelectionMode::Bracket),
+            ("Secret veto", SelectionMode::SecretVeto),
+        ];
+        html! {
+            <div class="buttons has-addons">
+            {
+                modes.into_iter().map(|(label, selection_mode)| {
+                    let class = if self.group_data.selection_mode == selection_mode {
+                        "button is-selected is-info"
+                    } else {
+                        "button"
+                    };
+                    html! {
+                        <button
+                            class={class}
+                            onclick={ctx.link().callback(move |_| SystemMsg::SetMode(selection_mode.clone()))}>
+                            { label }
+                        </button>
+                    }
+                }).collect::<Html>()
+            }
+            </div>
+        }
+    }
+
+    pub fn view_ranking_ballot(&self, ctx: &Context<Self>) -> Html {
+        if self.group_data.selection_mode != SelectionMode::RankedChoice
+            || self.group_data.system_state != SystemState::SystemStarted
+        {
+            return html! {};
+        }
+        let waiting_on = self
+            .group_data
+            .round
+            .participants
+            .iter()
+            .filter(|participant| !self.group_data.round.rankings.contains_key(*participant))
+            .cloned()
+            .collect::<Vec<_>>();
+        let submit_text = match self.group_data.round.rankings.contains_key(&self.username) {
+            true => "Change my ballot",
+            false => "Submit ballot",
+        };
+        html! {
+            <div class="box">
+                <p class="has-text-weight-bold">{"Drag the movies into your order, favourite first"}</p>
+                <ol>
+                {
+                    self.ranking.iter().enumerate().map(|(index, movie)| html! {
+                        <li
+                            class="box"
+                            draggable="true"
+                            ondragstart={ctx.link().callback(move |_: DragEvent| SystemMsg::DragStart(index))}
+                            ondragover={Callback::from(|e: DragEvent| e.prevent_default())}
+                            ondrop={ctx.link().callback(move |e: DragEvent| {
+                                e.prevent_default();
+                                SystemMsg::DropOn(index)
+                            })}>
+                            {format!("{} ({})", &movie.movie_title, &movie.movie_year)}
+                        </li>
+                    }).collect::<Html>()
+                }
+                </ol>
+                <button
+                    class="button is-primary"
+                    onclick={ctx.link().callback(|_| SystemMsg::SubmitRanking)}>
+                    { submit_text }
+                </button>
+                <p>{format!("Waiting on: {}", waiting_on.join(", "))}</p>
+            </div>
+        }
+    }
+
+    pub fn view_score_ballot(&self, ctx: &Context<Self>) -> Html {
+        let approval = match self.group_data.selection_mode {
+            SelectionMode::Score { approval } => approval,
+            _ => return html! {},
+        };
+        if self.group_data.system_state != SystemState::SystemStarted {
+            return html! {};
+        }
+        let mut movies = self.current_movies.iter().cloned().collect::<Vec<_>>();
+        movies.sort_by(|a, b| a.movie_title.cmp(&b.movie_title));
+        let waiting_on = self
+            .group_data
+            .round
+            .participants
+            .iter()
+            .filter(|participant| !self.group_data.round.scores.contains_key(*participant))
+            .cloned()
+            .collect::<Vec<_>>();
+        html! {
+            <div class="box">
+                <p class="has-text-weight-bold">
+                {
+                    match approval {
+                        true => String::from("Tick everything you'd be happy to watch"),
+                        false => format!("Score every movie from 0 to {}", MAX_SCORE),
+                    }
+                }
+                </p>
+                {
+                    movies.into_iter().map(|movie| {
+                        let movie_id = movie.movie_id.clone();
+                        let oninput = ctx.link().callback(move |e| SystemMsg::SetScore(movie_id.clone(), e));
+                        html! {
+                            <div class="field">
+                                <label class="label">{format!("{} ({})", &movie.movie_title, &movie.movie_year)}</label>
+                                {
+                                    match approval {
+                                        true => html! { <input type="checkbox" {oninput}/> },
+                                        false => html! {
+                                            <input class="input" type="number" min="0" max={MAX_SCORE.to_string()} value="0" {oninput}/>
+                                        },
+                                    }
+                                }
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+                <button
+                    class="button is-primary"
+                    onclick={ctx.link().callback(|_| SystemMsg::SubmitScores)}>
+                    { "Submit ballot" }
+                </button>
+                <p>{format!("Waiting on: {}", waiting_on.join(", "))}</p>
+            </div>
+        }
+    }
+
+    // Other members' choices come back blank from the server, only who's voted is known.
+    pub fn view_secret_veto_status(&self, _ctx: &Context<Self>) -> Html {
+        if self.group_data.selection_mode != SelectionMode::SecretVeto
+            || self.group_data.system_state != SystemState::SystemStarted
+        {
+            return html! {};
+        }
+        let waiting_on = self
+            .group_data
+            .round
+            .participants
+            .iter()
+            .filter(|participant| !self.group_data.round.secret_vetoes.contains_key(*participant))
+            .cloned()
+            .collect::<Vec<_>>();
+        let my_veto = self
+            .group_data
+            .round
+            .secret_vetoes
+            .get(&self.username)
+            .and_then(|movie_id| self.current_movies.iter().find(|movie| &movie.movie_id == movie_id));
+        html! {
+            <div class="box">
+                <p class="has-text-weight-bold">{"Pick one movie to veto, they're all revealed together"}</p>
+                {
+                    match my_veto {
+                        Some(movie) => html! { <p>{format!("Your veto: {}", &movie.movie_title)}</p> },
+                        None => html! { <p>{"You haven't vetoed anything yet."}</p> },
+                    }
+                }
+                <p>{format!("Waiting on: {}", waiting_on.join(", "))}</p>
+            </div>
+        }
+    }
+
+    pub fn view_live_bracket(&self, ctx: &Context<Self>) -> Html {
+        if self.group_data.system_state != SystemState::SystemStarted {
+            return html! {};
+        }
+        match &self.group_data.round.bracket {
+            Some(bracket) => html! {
+                <div class="box">
+                    <p class="has-text-weight-bold">{"Vote in each match-up, winners go through"}</p>
+                    { self.view_bracket(ctx, bracket, &self.group_data.round.candidates, true) }
+                </div>
+            },
+            None => html! {},
+        }
+    }
+
+    /// One column per round. Voting buttons only show on live match-ups in the current round.
+    fn view_bracket(
+        &self,
+        ctx: &Context<Self>,
+        bracket: &Bracket,
+        candidates: &[YewMovieDisplay],
+        live: bool,
+    ) -> Html {
+        let title = |movie_id: &str| {
+            candidates
+                .iter()
+                .find(|movie| movie.movie_id == movie_id)
+                .map(|movie| movie.movie_title.clone())
+                .unwrap_or_else(|| movie_id.to_string())
+        };
+        let current = bracket.rounds.len() - 1;
+        html! {
+            <div class="columns">
+            {
+                bracket.rounds.iter().enumerate().map(|(round_index, round)| html! {
+                    <div class="column">
+                        <p class="has-text-weight-bold">{format!("Round {}", round_index + 1)}</p>
+                        {
+                            round.iter().enumerate().map(|(matchup_index, matchup)| {
+                                let entrants = std::iter::once(matchup.first.clone())
+                                    .chain(matchup.second.clone())
+                                    .collect::<Vec<_>>();
+                                let voters = matchup.votes.keys().cloned().collect::<HashSet<_>>();
+                                html! {
+                                    <div class="box">
+                                    {
+                                        entrants.into_iter().map(|movie_id| {
+                                            let votes = matchup.votes.values().filter(|id| **id == movie_id).count();
+                                            let class = match matchup.winner.as_ref() == Some(&movie_id) {
+                                                true => "has-text-weight-bold",
+                                                false => "",
+                                            };
+                                            let can_vote = live
+                                                && round_index == current
+                                                && matchup.winner.is_none()
+                                                && matchup.votes.get(&self.username) != Some(&movie_id);
+                                            let vote_id = movie_id.clone();
+                                            html! {
+                                                <p class={class}>
+                                                    {format!("{} ({})", title(&movie_id), votes)}
+                                                    {
+                                                        match can_vote {
+                                                            true => html! {
+                                                                <button
+                                                                    class="button is-small"
+                                                                    onclick={ctx.link().callback(move |_| SystemMsg::VoteMatchup(matchup_index, vote_id.clone()))}>
+                                                                    { "Vote" }
+                                                                </button>
+                                                            },
+                                                            false => html! {},
+                                                        }
+                                                    }
+                                                </p>
+                                            }
+                                        }).collect::<Html>()
+                                    }
+                                    {
+                                        match matchup.second {
+                                            None => html! { <p>{"bye"}</p> },
+                                            Some(_) if live && matchup.winner.is_none() => html! {
+                                                <p>{format!("voted: {}", voters.into_iter().collect::<Vec<_>>().join(", "))}</p>
+                                            },
+                                            Some(_) => html! {},
+                                        }
+                                    }
+                                    </div>
+                                }
+                            }).collect::<Html>()
+                        }
+                    </div>
+                }).collect::<Html>()
+            }
+            </div>
+        }
+    }
+
+    fn view_score_table(&self, last_round: &RoundHistory) -> Html {
+        let score = match &last_round.score {
+            Some(score) => score,
+            None => return html! {},
+        };
+        let mut voters = score.ballots.keys().cloned().collect::<Vec<_>>();
+        voters.sort();
+        let title = |movie_id: &str| {
+            last_round
+                .candidates
+                .iter()
+                .find(|movie| movie.movie_id == movie_id)
+                .map(|movie| movie.movie_title.clone())
+                .unwrap_or_else(|| movie_id.to_string())
+        };
+        html! {
+            <table class="table">
+                <thead>
+                    <tr>
+                        <th>{"Movie"}</th>
+                        { voters.iter().map(|voter| html! { <th>{voter}</th> }).collect::<Html>() }
+                        <th>{"Total"}</th>
+                        <th>{"Zeros"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                {
+                    score.totals.iter().map(|total| html! {
+                        <tr>
+                            <td>{title(&total.movie_id)}</td>
+                            {
+                                voters.iter().map(|voter| {
+                                    let given = score.ballots[voter].get(&total.movie_id).cloned().unwrap_or(0);
+                                    html! { <td>{given}</td> }
+                                }).collect::<Html>()
+                            }
+                            <td>{total.total}</td>
+                            <td>{total.zeros}</td>
+                        </tr>
+                    }).collect::<Html>()
+                }
+                </tbody>
+            </table>
+        }
+    }
+
+    fn view_runoff(&self, last_round: &RoundHistory) -> Html {
+        let runoff = match &last_round.runoff {
+            Some(runoff) => runoff,
+            None => return html! {},
+        };
+        let title = |movie_id: &str| {
+            last_round
+                .candidates
+                .iter()
+                .find(|movie| movie.movie_id == movie_id)
+                .map(|movie| movie.movie_title.clone())
+                .unwrap_or_else(|| movie_id.to_string())
+        };
+        html! {
+            <table class="table">
+                <tbody>
+                {
+                    runoff.rounds.iter().enumerate().map(|(i, round)| html! {
+                        <tr>
+                            <th>{format!("Round {}", i + 1)}</th>
+                            {
+                                round.counts.iter().map(|count| html! {
+                                    <td>{format!("{}: {}", title(&count.movie_id), count.votes)}</td>
+                                }).collect::<Html>()
+                            }
+                            <td>
+                            {
+                                match &round.eliminated {
+                                    Some(movie_id) => format!("out: {}", title(movie_id)),
+                                    None => String::from("winner found"),
+                                }
+                            }
+                            </td>
+                        </tr>
+                    }).collect::<Html>()
+                }
+                </tbody>
+            </table>
+        }
+    }
+
+    pub fn view_last_round(&self, ctx: &Context<Self>) -> Html {
+        match &self.last_round {
+            Some(last_round) => html! {
+                <div class="box">
+                    <p class="has-text-weight-bold">{"Last round's winner"}</p>
+                    <img src={image_processing(&last_round.winner.movie_images)}/>
+                    <li>{format!("{} ({})", &last_round.winner.movie_title, &last_round.winner.movie_year)}</li>
+                    <li>{format!("added by: {}", &last_round.added_by)}</li>
+                    <li>{format!("finished: {}", format_date(last_round.date_finished))}</li>
+                    { self.view_runoff(last_round) }
+                    { self.view_score_table(last_round) }
+                    {
+                        match &last_round.bracket {
+                            Some(bracket) => self.view_bracket(ctx, bracket, &last_round.candidates, false),
+                            None => html! {},
+                        }
+                    }
+                    {
+                        match &last_round.draw {
+                            Some(draw) => html! {
+                                <li>{format!("drawn at random, seed: {}", draw.seed)}</li>
+                            },
+                            None => html! {},
+                        }
+                    }
+                </div>
+            },
+            None => html! {},
+        }
+    }
+
     pub fn search_results(&self, ctx: &Context<Self>) -> Html {
         //let callback = ctx.link().callback(SystemMsg::AddMovie);
         {
@@ -53,7 +518,19 @@ impl System {
                 // Still not handling the no images nicely?
                 .map(|movie| {
                     let movie_clone = movie.clone();
-                    let imdb_link = format!("https://imdb.com/title/{}", &movie.movie_id);
+                    let (movie_link, link_text) = movie_link(&movie.movie_id, movie.source);
+                    let watched = self.group_data.movies_watched.contains(&movie.movie_id);
+                    let watchlist_movie = movie.clone();
+                    let backlog_movie = movie.clone();
+                    let on_backlog = self
+                        .group_data
+                        .backlog
+                        .iter()
+                        .any(|entry| entry.movie.movie_id == movie.movie_id);
+                    let on_watchlist = self
+                        .watchlist
+                        .iter()
+                        .any(|entry| entry.movie.movie_id == movie.movie_id);
                     
                         html! {
                         <a class="panel-block px-0">
@@ -63,19 +540,60 @@ impl System {
                         <div class="column is-flex-direction-column p-0" id = {movie.movie_id.clone()}>
                         <li class="content mb-1 ml-3 is-size-5 is-size-6-mobile ellipsis is-ellipsis-1">
                         {&movie.movie_title}
+                        {
+                            match watched {
+                                true => html! { <span class="tag is-warning ml-2">{"Already watched"}</span> },
+                                false => html! {},
+                            }
+                        }
                         </li>
                         <li class="content mb-1 ml-3 is-size-6 is-size-7-mobile">
                         {&movie.movie_year}
+                        {
+                            match movie.source {
+                                MovieSource::Imdb => html! {},
+                                source => html! { <span class="tag is-light ml-2">{format!("{:?}", source)}</span> },
+                            }
+                        }
                         </li>
                         <li class="content mb-1 ml-3 is-size-6 is-size-7-mobile">
                         {&movie.movie_stars}
                         </li>
                         <div class="columns is-mobile">
                         <div class="column ml-3">
+                        {
+                            match watched {
+                                true => html! {
+                                    <button
+                                        class="button is-warning is-small is-fullwidth"
+                                    onclick={&ctx.link().callback(move |_| SystemMsg::AddRewatch(movie_clone.clone()))}>
+                                        { "Add as Rewatch" }
+                                    </button>
+                                },
+                                false => html! {
+                                    <button
+                                        class="button is-primary is-small is-fullwidth"
+                                    onclick={&ctx.link().callback(move |_| SystemMsg::AddMovie(movie_clone.clone()))}>
+                                        { "Add to System" }
+                                    </button>
+                                },
+                            }
+                        }
+                        </div>
+                        <div class="column">
                         <button
-                            class="button is-primary is-small is-fullwidth"
-                        onclick={&ctx.link().callback(move |_| SystemMsg::AddMovie(movie_clone.clone()))}>
-                            { "Add to System" }
+                            class="button is-link is-small is-fullwidth"
+                            disabled={on_backlog}
+                            onclick={&ctx.link().callback(move |_| SystemMsg::AddToBacklog(backlog_movie.clone()))}>
+                            { match on_backlog { true => "On Backlog", false => "Add to Backlog" } }
+                        </button>
+                        </div>
+                        <div class="column">
+                        <button
+                            class="button is-info is-small is-fullwidth"
+                            disabled={on_watchlist}
+                            onclick={&ctx.link().callback(move |_| SystemMsg::AddToWatchlist(watchlist_movie.clone()))}>
+                            { match on_watchlist { true => "On Watchlist", false => "Add to Watchlist" } }
                         </button>
                         </div>
                         <div class="column mr-3">
@@ -83,8 +601,8 @@ impl System {
                             class="button is-primary is-small is-fullwidth"
                             target="_blank"
                             rel="noopener noreferrer"
-                            href={imdb_link}>
-                            { "Visit IMDB" }
+                            href={movie_link}>
+                            { link_text }
                         </a>
                         </div>
                         </div>
@@ -164,36 +682,264 @@ impl System {
     pub fn search_bar(&self, ctx: &Context<Self>) -> Html {
         //if let Some(data) = self.group_data.clone() {
         match self.group_data.system_state {
-            SystemState::AddingMovies => html! {
-                        <div>
-                        <div>
-                            <p class="control has-icons-left">
-                            <input class="input" type="text" placeholder="Movie Search"
-                            maxlength=50
-                            oninput={ctx.link().callback(SystemMsg::QueryAutocomplete)}/>
-                            <span class="icon is-small is-left">
-                            <i class="material-icons">{"search"}</i>
-                            </span>
-                            </p>
-                        </div>
-                            <ul>
-                            {self.search_results(ctx)}
-                            </ul>
-                        </div>
-            },
+            SystemState::AddingMovies => self.search_input(ctx),
 
             SystemState::SystemStarted => html! {},
             SystemState::Finished => html! {},
         }
     }
 
+    fn search_input(&self, ctx: &Context<Self>) -> Html {
+        html! {
+                    <div>
+                    <div>
+                        <p class="control has-icons-left">
+                        <input class="input" type="text" placeholder="Movie Search"
+                        maxlength=50
+                        oninput={ctx.link().callback(SystemMsg::QueryAutocomplete)}/>
+                        <span class="icon is-small is-left">
+                        <i class="material-icons">{"search"}</i>
+                        </span>
+                        </p>
+                    </div>
+                        <ul>
+                        {self.search_results(ctx)}
+                        </ul>
+                    { self.view_watchlist_picker(ctx) }
+                    { self.view_backlog(ctx) }
+                    </div>
+        }
+    }
+
+    // One click adds from the watchlist, skipping anything already in the round.
+    pub fn view_watchlist_picker(&self, ctx: &Context<Self>) -> Html {
+        let in_round = match self
+            .group_data
+            .planned_rounds
+            .iter()
+            .find(|planned| planned.id == self.round_id)
+        {
+            Some(planned) => planned.candidates.iter().map(|movie| &movie.movie_id).collect::<HashSet<_>>(),
+            None => self.current_movies.iter().map(|movie| &movie.movie_id).collect(),
+        };
+        let entries = self
+            .watchlist
+            .iter()
+            .filter(|entry| !in_round.contains(&entry.movie.movie_id))
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class="box">
+                <p class="has-text-weight-bold">{"Add from my watchlist"}</p>
+                {
+                    entries
+                        .into_iter()
+                        .map(|entry| {
+                            let movie = entry.movie.clone();
+                            let watched = self.group_data.movies_watched.contains(&movie.movie_id);
+                            let onclick = match watched {
+                                true => ctx.link().callback(move |_| SystemMsg::AddRewatch(movie.clone())),
+                                false => ctx.link().callback(move |_| SystemMsg::AddMovie(movie.clone())),
+                            };
+                            html! {
+                                <div class="level is-mobile mb-1">
+                                    <div class="level-left">
+                                        <span class="level-item">{&entry.movie.movie_title}</span>
+                                        <span class="level-item tag">{format!("{:?}", entry.priority)}</span>
+                                        <span class="level-item is-size-7">{&entry.note}</span>
+                                    </div>
+                                    <div class="level-right">
+                                        <button class="button is-small is-primary level-item" onclick={onclick}>
+                                            { match watched { true => "Add as Rewatch", false => "Add" } }
+                                        </button>
+                                    </div>
+                                </div>
+                            }
+                        })
+                        .collect::<Html>()
+                }
+            </div>
+        }
+    }
+
+    // Movies that lost a round or were put aside for later, and a quick way to fill the round with them.
+    pub fn view_backlog(&self, ctx: &Context<Self>) -> Html {
+        if self.group_data.backlog.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class="box">
+                <p class="has-text-weight-bold">{format!("Group backlog ({})", self.group_data.backlog.len())}</p>
+                <div class="field has-addons">
+                    <p class="control">
+                        <input class="input is-small" type="number" min="1"
+                            value={self.fill_count.to_string()}
+                            oninput={ctx.link().callback(SystemMsg::SetFillCount)}/>
+                    </p>
+                    <p class="control">
+                        <button class="button is-small is-primary"
+                            onclick={ctx.link().callback(|_| SystemMsg::FillFromBacklog(BacklogPick::Random))}>
+                            {"Add random"}
+                        </button>
+                    </p>
+                    <p class="control">
+                        <button class="button is-small is-primary"
+                            onclick={ctx.link().callback(|_| SystemMsg::FillFromBacklog(BacklogPick::Oldest))}>
+                            {"Add oldest"}
+                        </button>
+                    </p>
+                </div>
+                {
+                    self.group_data
+                        .backlog
+                        .iter()
+                        .map(|entry| {
+                            let movie_id = entry.movie.movie_id.clone();
+                            let origin = match entry.from_round {
+                                Some(_) => format!("lost a round {}", format_date(entry.date_added)),
+                                None => format!("added {}", format_date(entry.date_added)),
+                            };
+                            html! {
+                                <div class="level is-mobile mb-1">
+                                    <div class="level-left">
+                                        <span class="level-item">{&entry.movie.movie_title}</span>
+                                        <span class="level-item is-size-7">
+                                            {format!("{}, picked by {}", origin, &entry.movie.added_by)}
+                                        </span>
+                                    </div>
+                                    <div class="level-right">
+                                        <button class="button is-small level-item"
+                                            onclick={ctx.link().callback(move |_| SystemMsg::RemoveFromBacklog(movie_id.clone()))}>
+                                            {"Remove"}
+                                        </button>
+                                    </div>
+                                </div>
+                            }
+                        })
+                        .collect::<Html>()
+                }
+            </div>
+        }
+    }
+
+    pub fn view_rounds_nav(&self, ctx: &Context<Self>) -> Html {
+        let round_url = |round_id: &str| {
+            format!("{}/system/{}/{}", *CORS_ORIGIN, self.group_id, round_id)
+        };
+        let tag_class = |round_id: &str| {
+            let viewing = round_id == self.round_id
+                || (self.round_id.is_empty() && round_id == self.group_data.round_id);
+            if viewing { "tag is-medium is-info" } else { "tag is-medium" }
+        };
+        html! {
+            <div class="tags">
+                <a class={tag_class(&self.group_data.round_id)} href={round_url(&self.group_data.round_id)}>
+                    {"Current round"}
+                </a>
+                {
+                    self.group_data
+                        .planned_rounds
+                        .iter()
+                        .enumerate()
+                        .map(|(index, planned)| html! {
+                            <a class={tag_class(&planned.id)} href={round_url(&planned.id)}>
+                                {format!("Planned {}", index + 1)}
+                            </a>
+                        })
+                        .collect::<Html>()
+                }
+                <button
+                    class="button is-small"
+                    onclick={ctx.link().callback(|_| SystemMsg::PlanRound)}>
+                    {"Plan another round"}
+                </button>
+                {
+                    self.past_rounds
+                        .iter()
+                        .take(5)
+                        .map(|past| html! {
+                            <a class={tag_class(&past.id)} href={round_url(&past.id)}>
+                                {format!("Finished {}", format_date(past.date_finished.unwrap_or_default()))}
+                            </a>
+                        })
+                        .collect::<Html>()
+                }
+            </div>
+        }
+    }
+
+    // A planned round takes candidates like the active one does before it starts,
+    // a finished one just shows how it went.
+    pub fn view_other_round(&self, ctx: &Context<Self>) -> Html {
+        if let Some(planned) = self
+            .group_data
+            .planned_rounds
+            .iter()
+            .find(|planned| planned.id == self.round_id)
+        {
+            return html! {
+                <div class="box">
+                    <p class="has-text-weight-bold">{"Planned round, starts once the current one is over"}</p>
+                    <ul>
+                    {
+                        planned
+                            .candidates
+                            .iter()
+                            .cloned()
+                            .map(|movie| {
+                                let own = movie.added_by == self.username;
+                                html! {
+                                    <li>
+                                        {format!("{} ({}), added by {}", &movie.movie_title, &movie.movie_year, &movie.added_by)}
+                                        {
+                                            match own {
+                                                true => html! {
+                                                    <button
+                                                        class="delete ml-2"
+                                                        onclick={ctx.link().callback(move |_| SystemMsg::DeleteEntry(movie.clone()))}>
+                                                    </button>
+                                                },
+                                                false => html! {},
+                                            }
+                                        }
+                                    </li>
+                                }
+                            })
+                            .collect::<Html>()
+                    }
+                    </ul>
+                    { self.search_input(ctx) }
+                </div>
+            };
+        }
+        match self.past_rounds.iter().find(|past| past.id == self.round_id) {
+            Some(past) => html! {
+                <div class="box">
+                    <p class="has-text-weight-bold">{"Finished round"}</p>
+                    {
+                        match &past.winner {
+                            Some(winner) => html! { <p>{format!("Winner: {} ({})", &winner.movie_title, &winner.movie_year)}</p> },
+                            None => html! {},
+                        }
+                    }
+                    <p>{format!("Turn order: {}", past.order.join(" → "))}</p>
+                    <p>{format!("Candidates: {}", past.candidates.iter().map(|movie| movie.movie_title.clone()).collect::<Vec<_>>().join(", "))}</p>
+                </div>
+            },
+            None => html! {
+                <p>{"This round doesn't exist."}</p>
+            },
+        }
+    }
+
     pub fn add_stuff(&self, ctx: &Context<Self>) -> Html {
         html! {
             <div>
                 <ul>
                 {self.added_movies(ctx)}
                 </ul>
-                {self.save_movies(ctx)}
             </div>
 
         }
@@ -234,6 +980,17 @@ impl System {
                 onclick={&ctx.link().callback(move|_| SystemMsg::DeleteEntry(movie.clone()))}>
                 { "delete entry" }
             </button>  }
+        } else if self.group_data.system_state == SystemState::SystemStarted
+            && self.group_data.selection_mode == SelectionMode::SecretVeto
+        {
+            let chosen = self.group_data.round.secret_vetoes.get(&self.username) == Some(&movie.movie_id);
+            html! {
+            <button
+                class={if chosen { "button is-small is-danger" } else { "button is-small" }}
+                title = {movie.movie_title.clone()}
+                onclick={&ctx.link().callback(move|_| SystemMsg::SecretVeto(movie.clone()))}>
+                { "secret veto" }
+            </button>  }
         } else if self.group_data.system_state == SystemState::SystemStarted
             && self.group_data.turn == self.username
         {
@@ -253,10 +1010,8 @@ impl System {
     pub fn added_movies(&self, ctx: &Context<Self>) -> Html {
         {
             log::info!("self.current_movies: {:?}", &self.current_movies);
-            let current = self.current_movies.clone();
-            current
+            self.current_movies
                 .iter()
-                .cloned()
                 .map(|movie| {
                     let _formatted = format!("{} {}", &movie.movie_title, &movie.movie_year);
                     html! {
@@ -267,8 +1022,16 @@ impl System {
                             <li> {&movie.movie_title} </li>
                             <li> {&movie.movie_year} </li>
                             <li> {format!("added by: {}", &movie.added_by)} </li>
+                            {
+                                match movie.rewatch {
+                                    true => html! { <li><span class="tag is-warning">{"Rewatch"}</span></li> },
+                                    false => html! {},
+                                }
+                            }
                             </ul>
-                            {   self.delete_movie_button(ctx, movie) }
+                            {   self.movie_details_button(ctx, movie.clone()) }
+                            {   self.delete_movie_button(ctx, movie.clone()) }
+                            {   self.view_discussion(ctx, movie) }
                             </div>
 
                     }
@@ -277,24 +1040,210 @@ impl System {
         }
     }
 
-    pub fn save_movies(&self, ctx: &Context<Self>) -> Html {
+    fn movie_details_button(&self, ctx: &Context<Self>, movie: YewMovieDisplay) -> Html {
         html! {
-            <div>
-                <button
-                    class="save_movies"
-                    onclick={&ctx.link().callback(|_| SystemMsg::SaveMovies)}>
-                    { "Save Movies" }
-                </button>
+            <button
+                class="button is-info is-light is-small"
+                onclick={ctx.link().callback(move |_| SystemMsg::ShowDetails(movie.clone()))}>
+                { "Details" }
+            </button>
+        }
+    }
+
+    pub fn view_movie_details(&self, ctx: &Context<Self>) -> Html {
+        let movie = match &self.details_movie {
+            Some(movie) => movie,
+            None => return html! {},
+        };
+        let body = match &self.movie_details {
+            None => html! { <progress class="progress is-small is-info" max="100"/> },
+            Some(Err(e)) => html! {
+                <div class="notification is-warning">
+                    { format!("Couldn't get the details: {}", e) }
+                </div>
+            },
+            Some(Ok(details)) => view_details(details),
+        };
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background" onclick={ctx.link().callback(|_| SystemMsg::CloseDetails)}></div>
+                <div class="modal-card">
+                    <header class="modal-card-head">
+                        <p class="modal-card-title">
+                            { format!("{} ({})", &movie.movie_title, &movie.movie_year) }
+                        </p>
+                        <button
+                            class="delete"
+                            aria-label="close"
+                            onclick={ctx.link().callback(|_| SystemMsg::CloseDetails)}>
+                        </button>
+                    </header>
+                    <section class="modal-card-body">
+                        { body }
+                    </section>
+                </div>
             </div>
+        }
+    }
 
+    // The pitch can only be changed by whoever added the movie while movies are being added,
+    // anyone can comment until the round is finished.
+    fn view_discussion(&self, ctx: &Context<Self>, movie: &YewMovieDisplay) -> Html {
+        let discussion = self
+            .group_data
+            .discussions
+            .get(&movie.movie_id)
+            .cloned()
+            .unwrap_or_default();
+        let can_pitch = self.group_data.system_state == SystemState::AddingMovies
+            && movie.added_by == self.username;
+        let can_comment = self.group_data.system_state != SystemState::Finished;
+        let (pitch_id, save_id, comment_id, send_id) = (
+            movie.movie_id.clone(),
+            movie.movie_id.clone(),
+            movie.movie_id.clone(),
+            movie.movie_id.clone(),
+        );
+        html! {
+            <div class="discussion">
+            {
+                match can_pitch {
+                    true => html! {
+                        <div class="field has-addons">
+                            <input
+                                class="input is-small"
+                                placeholder="Why should we watch it?"
+                                maxlength={MAX_COMMENT_LENGTH.to_string()}
+                                value={
+                                    self.pitch_drafts
+                                        .get(&movie.movie_id)
+                                        .cloned()
+                                        .or_else(|| discussion.pitch.clone())
+                                        .unwrap_or_default()
+                                }
+                                oninput={ctx.link().callback(move |e| SystemMsg::SetPitchDraft(pitch_id.clone(), e))}
+                            />
+                            <button
+                                class="button is-small"
+                                onclick={ctx.link().callback(move |_| SystemMsg::SavePitch(save_id.clone()))}>
+                                { "Save Pitch" }
+                            </button>
+                        </div>
+                    },
+                    false => html! {},
+                }
+            }
+            { view_comments(&discussion) }
+            {
+                match can_comment {
+                    true => html! {
+                        <div class="field has-addons">
+                            <input
+                                class="input is-small"
+                                placeholder="Say something about it"
+                                maxlength={MAX_COMMENT_LENGTH.to_string()}
+                                value={self.comment_drafts.get(&movie.movie_id).cloned().unwrap_or_default()}
+                                oninput={ctx.link().callback(move |e| SystemMsg::SetCommentDraft(comment_id.clone(), e))}
+                            />
+                            <button
+                                class="button is-small"
+                                onclick={ctx.link().callback(move |_| SystemMsg::SendComment(send_id.clone()))}>
+                                { "Comment" }
+                            </button>
+                        </div>
+                    },
+                    false => html! {},
+                }
+            }
+            </div>
+        }
+    }
+}
+
+/// The pitch and comments, as they're shown under a candidate and in the history.
+pub fn view_comments(discussion: &Discussion) -> Html {
+    html! {
+        <div>
+        {
+            match &discussion.pitch {
+                Some(pitch) => html! { <p class="is-italic">{format!("Pitch: {}", pitch)}</p> },
+                None => html! {},
+            }
+        }
+        {
+            discussion
+                .comments
+                .iter()
+                .map(|comment| html! {
+                    <p class="is-size-7">
+                        <strong>{&comment.username}</strong>
+                        {format!(" ({}): {}", format_date(comment.date), &comment.text)}
+                    </p>
+                })
+                .collect::<Html>()
         }
+        </div>
     }
 }
 
+// Anything the source didn't have is left out.
+fn view_details(details: &MovieDetails) -> Html {
+    let rows = vec![
+        ("Runtime", details.runtime.clone()),
+        ("Genre", details.genres.join(", ")),
+        ("Director", details.director.clone()),
+        ("Cast", details.actors.join(", ")),
+    ];
+    html! {
+        <div class="content">
+            {
+                match details.plot.is_empty() {
+                    true => html! {},
+                    false => html! { <p>{ &details.plot }</p> },
+                }
+            }
+            <table class="table is-narrow">
+                <tbody>
+                {
+                    rows.into_iter()
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(name, value)| html! {
+                            <tr><th>{ name }</th><td>{ value }</td></tr>
+                        })
+                        .collect::<Html>()
+                }
+                {
+                    details.ratings.iter()
+                        .map(|rating| html! {
+                            <tr><th>{ &rating.source }</th><td>{ &rating.value }</td></tr>
+                        })
+                        .collect::<Html>()
+                }
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+// Only IMDb's posters can be asked for as a thumbnail, the rest are used as they are.
 pub fn image_processing(image: &ImageData) -> String {
     let mut image_url = image.url.to_owned();
-    assert!(&image_url[image_url.len() - 4..] == ".jpg");
-    image_url.truncate(image_url.len() - 4);
-    image_url.push_str("QL75_UX80_CR0,5,80,120_.jpg");
+    if image_url.contains("media-amazon.com") && image_url.ends_with(".jpg") {
+        image_url.truncate(image_url.len() - 4);
+        image_url.push_str("QL75_UX80_CR0,5,80,120_.jpg");
+    }
     image_url
 }
+
+// Where to read more about a movie, and what to call the button.
+pub fn movie_link(movie_id: &str, source: MovieSource) -> (String, &'static str) {
+    match source {
+        MovieSource::Tmdb => (
+            format!("https://www.themoviedb.org/movie/{}", movie_id),
+            "Visit TMDB",
+        ),
+        MovieSource::Imdb | MovieSource::Omdb => {
+            (format!("https://imdb.com/title/{}", movie_id), "Visit IMDB")
+        }
+    }
+}
@@ -1,7 +1,12 @@
 use crate::auth_requests::{get_route_with_auth, post_route_with_auth};
-use crate::{CREATE_GROUP_URL, GET_ALL_GROUPS_URL, GET_USER_PROFILE};
+use crate::shared_requests::{request_get_watchlist, request_watchlist_action};
+use crate::{
+    CREATE_GROUP_URL, GET_ALL_GROUPS_URL, GET_EMAIL_PREFS_URL, GET_USER_PROFILE, SET_EMAIL_PREFS_URL,
+};
 use anyhow::Result;
 use shared_stuff::group_structs::{GroupForm, GroupInfo, GroupUser, UserProfile};
+use shared_stuff::notification_structs::EmailPrefs;
+use shared_stuff::watchlist_structs::{WatchlistAction, WatchlistEntry};
 use std::collections::HashSet;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
@@ -36,12 +41,28 @@ pub async fn request_create_group(username: String, group_name: String) -> Resul
     Ok(())
 }
 
+pub async fn request_get_email_prefs() -> Result<EmailPrefs> {
+    let resp = get_route_with_auth(&GET_EMAIL_PREFS_URL).await?;
+    let email_prefs: EmailPrefs = resp.json().await?;
+    Ok(email_prefs)
+}
+
+pub async fn request_set_email_prefs(email_prefs: EmailPrefs) -> Result<EmailPrefs> {
+    let json_body = serde_json::to_string(&email_prefs)?;
+    let resp = post_route_with_auth(&SET_EMAIL_PREFS_URL, json_body).await?;
+    log::info!("set_email_prefs resp: {:?}", &resp);
+    let email_prefs: EmailPrefs = resp.json().await?;
+    Ok(email_prefs)
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct User {
     pub username: String,
     pub user_profile: Option<UserProfile>,
     pub create_group_name: String,
     pub all_groups: HashSet<GroupInfo>,
+    pub watchlist: Vec<WatchlistEntry>,
+    pub email_prefs: Option<EmailPrefs>,
 }
 
 #[derive(Properties, Debug, PartialEq, Clone)]
@@ -57,6 +78,12 @@ pub enum UserMsg {
     GetAllGroups,
     UpdateGroups(HashSet<GroupInfo>),
     UpdateUserProfile(UserProfile),
+    GetWatchlist,
+    UpdateWatchlist(Vec<WatchlistEntry>),
+    Watchlist(WatchlistAction),
+    SetWatchlistNote(String, Event),
+    UpdateEmailPrefs(EmailPrefs),
+    SaveEmailPrefs(EmailPrefs),
 }
 
 impl Component for User {
@@ -70,6 +97,8 @@ impl Component for User {
             user_profile: None,
             create_group_name,
             all_groups: HashSet::new(),
+            watchlist: Vec::new(),
+            email_prefs: None,
         }
     }
 
@@ -115,6 +144,50 @@ impl Component for User {
             UpdateUserProfile(user_profile) => {
                 self.user_profile = Some(user_profile);
                 ctx.link().send_future(async move { UserMsg::GetAllGroups });
+                ctx.link().send_message(UserMsg::GetWatchlist);
+                ctx.link().send_future(async move {
+                    match request_get_email_prefs().await {
+                        Ok(email_prefs) => UserMsg::UpdateEmailPrefs(email_prefs),
+                        _ => UserMsg::Noop,
+                    }
+                });
+            }
+            UpdateEmailPrefs(email_prefs) => {
+                self.email_prefs = Some(email_prefs);
+            }
+            SaveEmailPrefs(email_prefs) => link_clone.send_future(async move {
+                match request_set_email_prefs(email_prefs).await {
+                    Ok(email_prefs) => UserMsg::UpdateEmailPrefs(email_prefs),
+                    _ => UserMsg::Noop,
+                }
+            }),
+            GetWatchlist => link_clone.send_future(async move {
+                match request_get_watchlist().await {
+                    Ok(watchlist) => UserMsg::UpdateWatchlist(watchlist),
+                    _ => UserMsg::Noop,
+                }
+            }),
+            UpdateWatchlist(watchlist) => {
+                self.watchlist = watchlist;
+            }
+            Watchlist(action) => link_clone.send_future(async move {
+                match request_watchlist_action(action).await {
+                    Ok(watchlist) => UserMsg::UpdateWatchlist(watchlist),
+                    Err(e) => {
+                        log::info!("watchlist action error: {:?}", &e);
+                        UserMsg::Noop
+                    }
+                }
+            }),
+            // Sent on change rather than input, so a note is saved once the user is done typing.
+            SetWatchlistNote(movie_id, event) => {
+                if let Some(elem) = event.target_dyn_into::<HtmlInputElement>() {
+                    let action = WatchlistAction::SetNote {
+                        movie_id,
+                        note: elem.value(),
+                    };
+                    ctx.link().send_message(UserMsg::Watchlist(action));
+                }
             }
         }
         true
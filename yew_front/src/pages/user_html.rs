@@ -1,5 +1,7 @@
 use crate::pages::user::{User, UserMsg};
 use crate::CORS_ORIGIN;
+use shared_stuff::notification_structs::EmailPrefs;
+use shared_stuff::watchlist_structs::{Priority, WatchlistAction, MAX_NOTE_LENGTH};
 use yew::prelude::*;
 
 use super::all_groups;
@@ -24,6 +26,8 @@ impl User {
                 { self.display_user_info(ctx) }
                 { self.create_group(ctx) }
                 { self.display_all_groups(ctx) }
+                { self.display_watchlist(ctx) }
+                { self.display_email_prefs(ctx) }
                 </div>
             }
         } else {
@@ -94,4 +98,103 @@ impl User {
             }
         }
     }
+
+    pub fn display_email_prefs(&self, ctx: &Context<Self>) -> Html {
+        let email_prefs = match &self.email_prefs {
+            Some(email_prefs) => email_prefs,
+            None => return html! {},
+        };
+        let checkbox = |label: &str, checked: bool, toggle: fn(&mut EmailPrefs)| {
+            let mut toggled = email_prefs.clone();
+            toggle(&mut toggled);
+            html! {
+                <label class="checkbox">
+                    <input
+                        type="checkbox"
+                        checked={checked}
+                        onclick={ctx.link().callback(move |_| UserMsg::SaveEmailPrefs(toggled.clone()))}
+                    />
+                    {format!(" {}", label)}
+                    <br/>
+                </label>
+            }
+        };
+        html! {
+        <div>
+            <h1> {"Email me when"} </h1>
+            { checkbox("it's my turn", email_prefs.your_turn, |prefs| prefs.your_turn = !prefs.your_turn) }
+            { checkbox("a round starts", email_prefs.round_started, |prefs| prefs.round_started = !prefs.round_started) }
+            { checkbox("a round finishes", email_prefs.round_finished, |prefs| prefs.round_finished = !prefs.round_finished) }
+            { checkbox("I'm added to a group", email_prefs.added_to_group, |prefs| prefs.added_to_group = !prefs.added_to_group) }
+            { checkbox("I'm invited to a group", email_prefs.invite_received, |prefs| prefs.invite_received = !prefs.invite_received) }
+        </div>
+        }
+    }
+
+    pub fn display_watchlist(&self, ctx: &Context<Self>) -> Html {
+        if self.watchlist.is_empty() {
+            return html! {
+                <div>
+                    <h1> {"Watchlist"} </h1>
+                    <p> {"Nothing here yet, add movies from the search on a group's system page."} </p>
+                </div>
+            };
+        }
+        html! {
+        <div>
+            <h1> {"Watchlist"} </h1>
+            {
+                self.watchlist
+                    .iter()
+                    .map(|entry| {
+                        let movie_id = entry.movie.movie_id.clone();
+                        let priority_button = |priority: Priority| {
+                            let movie_id = movie_id.clone();
+                            let class = match entry.priority == priority {
+                                true => "button is-small is-info",
+                                false => "button is-small",
+                            };
+                            html! {
+                                <button
+                                    class={class}
+                                    onclick={ctx.link().callback(move |_| UserMsg::Watchlist(WatchlistAction::SetPriority {
+                                        movie_id: movie_id.clone(),
+                                        priority,
+                                    }))}>
+                                    {format!("{:?}", priority)}
+                                </button>
+                            }
+                        };
+                        let note_id = movie_id.clone();
+                        let remove_id = movie_id.clone();
+                        html! {
+                            <div class="box">
+                                <p class="has-text-weight-bold">
+                                    {format!("{} ({})", &entry.movie.movie_title, &entry.movie.movie_year)}
+                                </p>
+                                <div class="buttons has-addons">
+                                    { priority_button(Priority::Low) }
+                                    { priority_button(Priority::Medium) }
+                                    { priority_button(Priority::High) }
+                                </div>
+                                <input
+                                    class="input is-small"
+                                    placeholder="note"
+                                    maxlength={MAX_NOTE_LENGTH.to_string()}
+                                    value={entry.note.clone()}
+                                    onchange={ctx.link().callback(move |event| UserMsg::SetWatchlistNote(note_id.clone(), event))}
+                                />
+                                <button
+                                    class="button is-small is-danger mt-2"
+                                    onclick={ctx.link().callback(move |_| UserMsg::Watchlist(WatchlistAction::Remove(remove_id.clone())))}>
+                                    { "Remove" }
+                                </button>
+                            </div>
+                        }
+                    })
+                    .collect::<Html>()
+            }
+        </div>
+        }
+    }
 }
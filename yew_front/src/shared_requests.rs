@@ -1,7 +1,11 @@
-use crate::auth_requests::get_route_with_auth;
-use crate::GET_GROUP_DATA_URL;
-use anyhow::Result;
+use crate::auth_requests::{get_route_with_auth, post_route_with_auth};
+use crate::{GET_GROUP_DATA_URL, GET_GROUP_HISTORY_URL, GET_WATCHLIST_URL, WATCHLIST_ACTION_URL};
+use anyhow::{anyhow, Result};
+use shared_stuff::auth_structs::ErrorMessage;
 use shared_stuff::db_structs::DBGroupStruct;
+use shared_stuff::history_structs::HistoryPage;
+use shared_stuff::round_structs::GroupOverview;
+use shared_stuff::watchlist_structs::{WatchlistAction, WatchlistEntry};
 
 pub async fn request_get_group_data(group_id: String) -> Result<DBGroupStruct> {
     let uri = GET_GROUP_DATA_URL.to_string();
@@ -15,3 +19,43 @@ pub async fn request_get_group_data(group_id: String) -> Result<DBGroupStruct> {
     );
     Ok(group_struct)
 }
+
+/// The same request as `request_get_group_data`, keeping the rounds as well.
+pub async fn request_get_group_overview(group_id: String) -> Result<GroupOverview> {
+    let url = format!("{}/{}", *GET_GROUP_DATA_URL, group_id);
+    let resp = get_route_with_auth(&url).await?;
+    let overview: GroupOverview = resp.json().await?;
+    Ok(overview)
+}
+
+pub async fn request_get_group_history(group_id: String, page: u32) -> Result<HistoryPage> {
+    let uri = GET_GROUP_HISTORY_URL.to_string();
+    let url = format!("{}/{}/{}", uri, group_id, page);
+    let resp = get_route_with_auth(&url).await?;
+    log::info!("request_get_group_history resp: {:?}", &resp);
+    let history_page: HistoryPage = resp.json().await?;
+    Ok(history_page)
+}
+
+pub async fn request_get_watchlist() -> Result<Vec<WatchlistEntry>> {
+    let resp = get_route_with_auth(&GET_WATCHLIST_URL).await?;
+    log::info!("request_get_watchlist resp: {:?}", &resp);
+    let watchlist: Vec<WatchlistEntry> = resp.json().await?;
+    Ok(watchlist)
+}
+
+pub async fn request_watchlist_action(action: WatchlistAction) -> Result<Vec<WatchlistEntry>> {
+    let json_body = serde_json::to_string(&action)?;
+    let resp = post_route_with_auth(&WATCHLIST_ACTION_URL, json_body).await?;
+    log::info!("request_watchlist_action resp: {:?}", &resp);
+    match resp.status() {
+        200 => {
+            let watchlist: Vec<WatchlistEntry> = resp.json().await?;
+            Ok(watchlist)
+        }
+        _ => {
+            let error_message: ErrorMessage = resp.json().await?;
+            Err(anyhow!(error_message.message))
+        }
+    }
+}
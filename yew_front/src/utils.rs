@@ -14,3 +14,10 @@ pub fn image_processing(image: Option<&ImageData>) -> String {
         "need to get a decent no pic available pic".to_string()
     }
 }
+
+// Timestamps from warp_back are in seconds, js wants milliseconds.
+pub fn format_date(timestamp: i64) -> String {
+    let date = js_sys::Date::new_0();
+    date.set_time(timestamp as f64 * 1000.0);
+    String::from(date.to_date_string())
+}